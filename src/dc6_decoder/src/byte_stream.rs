@@ -1,42 +1,60 @@
+use crate::error::{Dc6Error, Dc6ErrorReason};
+
 pub struct ByteStream<'bytes> {
     bytes: &'bytes [u8],
     offset: usize,
+    // offset of the bytes in the file, used for the errors
+    file_offset: usize,
 }
 
 impl<'bytes> ByteStream<'bytes> {
     pub fn new(bytes: &'bytes [u8]) -> Self {
-        Self { bytes, offset: 0 }
+        Self::new_at(bytes, 0)
     }
 
-    fn stream_four_bytes(&mut self) -> [u8; 4] {
-        let bytes: [u8; 4] = self.bytes[self.offset..self.offset + 4]
-            .try_into()
-            .expect("slice with incorrect length");
-        self.offset += 4;
-        bytes
+    pub fn new_at(bytes: &'bytes [u8], file_offset: usize) -> Self {
+        Self {
+            bytes,
+            offset: 0,
+            file_offset,
+        }
     }
 
-    pub fn stream_uint(&mut self) -> u32 {
-        u32::from_le_bytes(self.stream_four_bytes())
+    pub fn file_offset(&self) -> usize {
+        self.file_offset + self.offset
     }
 
-    pub fn stream_int(&mut self) -> i32 {
-        i32::from_le_bytes(self.stream_four_bytes())
+    pub fn error(&self, reason: Dc6ErrorReason) -> Dc6Error {
+        Dc6Error::new(self.file_offset(), reason)
     }
 
-    pub fn stream_byte(&mut self) -> u8 {
-        let byte = self.bytes[self.offset];
-        self.offset += 1;
-        byte
+    fn stream_four_bytes(&mut self) -> Result<[u8; 4], Dc6Error> {
+        Ok(self.stream_bytes(4)?.try_into().unwrap())
     }
 
-    pub fn skip_bytes(&mut self, num_bytes: u32) {
-        self.offset += num_bytes as usize;
+    pub fn stream_uint(&mut self) -> Result<u32, Dc6Error> {
+        Ok(u32::from_le_bytes(self.stream_four_bytes()?))
+    }
+
+    pub fn stream_int(&mut self) -> Result<i32, Dc6Error> {
+        Ok(i32::from_le_bytes(self.stream_four_bytes()?))
+    }
+
+    pub fn stream_byte(&mut self) -> Result<u8, Dc6Error> {
+        Ok(self.stream_bytes(1)?[0])
+    }
+
+    pub fn skip_bytes(&mut self, num_bytes: u32) -> Result<(), Dc6Error> {
+        self.stream_bytes(num_bytes).map(|_| ())
     }
 
-    pub fn stream_bytes(&mut self, num_bytes: u32) -> &'bytes [u8] {
-        let byte_slice = &self.bytes[self.offset..self.offset + num_bytes as usize];
+    pub fn stream_bytes(&mut self, num_bytes: u32) -> Result<&'bytes [u8], Dc6Error> {
+        let byte_slice = self
+            .offset
+            .checked_add(num_bytes as usize)
+            .and_then(|end| self.bytes.get(self.offset..end))
+            .ok_or_else(|| self.error(Dc6ErrorReason::UnexpectedEnd))?;
         self.offset += num_bytes as usize;
-        byte_slice
+        Ok(byte_slice)
     }
 }
@@ -1,8 +1,17 @@
-use crate::byte_stream::ByteStream;
+use std::ops::Range;
 
-const END_OF_SCAN_LINE: u8 = 128;
-const MAX_RUN_LENGTH: u8 = 127;
-const EXPECTED_DC6_VERSION: i32 = 6;
+use crate::{
+    byte_stream::ByteStream,
+    error::{Dc6Error, Dc6ErrorReason},
+};
+
+pub(crate) const END_OF_SCAN_LINE: u8 = 128;
+pub(crate) const MAX_RUN_LENGTH: u8 = 127;
+pub(crate) const EXPECTED_DC6_VERSION: i32 = 6;
+// Largest height and width of a frame, far larger than any frame of the game
+pub(crate) const MAX_FRAME_SIZE: u32 = 4096;
+// Largest number of pixels of an image composed of frames, as many as 4 of the largest frames
+pub(crate) const MAX_IMAGE_PIXELS: u64 = 4 * MAX_FRAME_SIZE as u64 * MAX_FRAME_SIZE as u64;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ScanlineState {
@@ -19,7 +28,7 @@ pub struct Direction<'dc6_file> {
     pub encoded_frames: Vec<EncodedFrame<'dc6_file>>,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FrameMetadata {
     pub width: u32,
     pub height: u32,
@@ -30,8 +39,11 @@ pub struct FrameMetadata {
 pub struct EncodedFrame<'dc6_file> {
     pub meta_data: FrameMetadata,
     encoded_bytes: &'dc6_file [u8],
+    encoded_bytes_offset: usize,
 }
 
+/// A palette indexed frame with the rows from top to bottom, color 0 is transparent
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DecodedFrame {
     pub meta_data: FrameMetadata,
     pub decoded_bytes: Vec<u8>,
@@ -43,19 +55,24 @@ struct Dc6Header {
 }
 
 impl<'dc6_file> Dc6<'dc6_file> {
+    /// Parses a DC6 file, panics if the file is malformed
     pub fn new(data: &'dc6_file [u8]) -> Self {
+        Self::try_new(data).unwrap_or_else(|e| panic!("Invalid dc6 file: {e}"))
+    }
+
+    pub fn try_new(data: &'dc6_file [u8]) -> Result<Self, Dc6Error> {
         let mut byte_reader = ByteStream::new(data);
-        let header = Dc6Header::load(&mut byte_reader);
+        let header = Dc6Header::load(&mut byte_reader)?;
 
-        Self {
-            directions: Self::load_directions(&mut byte_reader, &header),
-        }
+        Ok(Self {
+            directions: Self::load_directions(&mut byte_reader, &header)?,
+        })
     }
 
     fn load_directions(
         byte_reader: &mut ByteStream<'dc6_file>,
         header: &Dc6Header,
-    ) -> Vec<Direction<'dc6_file>> {
+    ) -> Result<Vec<Direction<'dc6_file>>, Dc6Error> {
         (0..header.num_directions)
             .map(|_| Direction::load(byte_reader, header.num_frames_per_direction))
             .collect()
@@ -63,49 +80,146 @@ impl<'dc6_file> Dc6<'dc6_file> {
 }
 
 impl Dc6Header {
-    fn load(byte_reader: &mut ByteStream) -> Self {
-        let version = byte_reader.stream_int();
+    fn load(byte_reader: &mut ByteStream) -> Result<Self, Dc6Error> {
+        let version = byte_reader.stream_int()?;
         if version != EXPECTED_DC6_VERSION {
-            panic!("unexpected dc6 version")
+            return Err(Dc6Error::new(
+                0,
+                Dc6ErrorReason::UnsupportedVersion(version),
+            ));
         }
 
         // Skip flags, encoding and termination
-        byte_reader.skip_bytes(4 * 3);
+        byte_reader.skip_bytes(4 * 3)?;
 
-        let num_directions = byte_reader.stream_uint();
-        let num_frames_per_direction = byte_reader.stream_uint();
+        let num_directions = byte_reader.stream_uint()?;
+        let num_frames_per_direction = byte_reader.stream_uint()?;
 
         // Skip blocks
-        byte_reader.skip_bytes(num_directions * num_frames_per_direction * 4);
+        let blocks_size = num_directions
+            .checked_mul(num_frames_per_direction)
+            .and_then(|num_blocks| num_blocks.checked_mul(4))
+            .ok_or_else(|| byte_reader.error(Dc6ErrorReason::UnexpectedEnd))?;
+        byte_reader.skip_bytes(blocks_size)?;
 
-        Self {
+        Ok(Self {
             num_directions,
             num_frames_per_direction,
-        }
+        })
     }
 }
 
 impl<'dc6_file> Direction<'dc6_file> {
-    fn load(byte_reader: &mut ByteStream<'dc6_file>, num_frames_per_direction: u32) -> Self {
+    fn load(
+        byte_reader: &mut ByteStream<'dc6_file>,
+        num_frames_per_direction: u32,
+    ) -> Result<Self, Dc6Error> {
         let frames = (0..num_frames_per_direction)
             .map(|_| EncodedFrame::load(byte_reader))
-            .collect();
-        Self {
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
             encoded_frames: frames,
+        })
+    }
+
+    /// Composes frames into a grid, panics if the frames are out of bounds, `frames_per_row`
+    /// is 0 or the encoded pixels are malformed
+    pub fn compose_grid(&self, frames: Range<usize>, frames_per_row: usize) -> DecodedFrame {
+        self.try_compose_grid(frames, frames_per_row)
+            .unwrap_or_else(|e| panic!("Invalid dc6 frame: {e}"))
+    }
+
+    /// Composes the frames into a grid of `frames_per_row` columns, like the panels which
+    /// are split into several frames. Each column is as wide as its widest frame and each
+    /// row as high as its highest frame, the frames are placed at the top left of their
+    /// cells. The offsets of the composed frame are 0.
+    pub fn try_compose_grid(
+        &self,
+        frames: Range<usize>,
+        frames_per_row: usize,
+    ) -> Result<DecodedFrame, Dc6Error> {
+        if frames_per_row == 0 {
+            return Err(Dc6Error::new(0, Dc6ErrorReason::NoColumns));
+        }
+
+        let frames = self.encoded_frames.get(frames.clone()).ok_or_else(|| {
+            Dc6Error::new(
+                0,
+                Dc6ErrorReason::FramesOutOfBounds {
+                    start: frames.start,
+                    end: frames.end,
+                    num_frames: self.encoded_frames.len(),
+                },
+            )
+        })?;
+
+        let mut col_widths = vec![0; frames_per_row.min(frames.len())];
+        let mut row_heights = vec![0; frames.len().div_ceil(frames_per_row)];
+
+        for (i, frame) in frames.iter().enumerate() {
+            let col_width = &mut col_widths[i % frames_per_row];
+            *col_width = frame.meta_data.width.max(*col_width);
+            let row_height = &mut row_heights[i / frames_per_row];
+            *row_height = frame.meta_data.height.max(*row_height);
+        }
+
+        let too_large = || Dc6Error::new(0, Dc6ErrorReason::ImageTooLarge);
+        let width = col_widths
+            .iter()
+            .try_fold(0u32, |width, col_width| width.checked_add(*col_width))
+            .ok_or_else(too_large)?;
+        let height = row_heights
+            .iter()
+            .try_fold(0u32, |height, row_height| height.checked_add(*row_height))
+            .ok_or_else(too_large)?;
+
+        if u64::from(width) * u64::from(height) > MAX_IMAGE_PIXELS {
+            return Err(too_large());
+        }
+
+        let mut data = vec![0; width as usize * height as usize];
+
+        let mut row = 0;
+        for (row_frames, row_height) in frames.chunks(frames_per_row).zip(&row_heights) {
+            let mut col = 0;
+            for (frame, col_width) in row_frames.iter().zip(&col_widths) {
+                frame.try_decode_into(&mut data, width, row as i32, col as i32)?;
+                col += col_width;
+            }
+            row += row_height;
         }
+
+        Ok(DecodedFrame {
+            meta_data: FrameMetadata {
+                width,
+                height,
+                offset_row: 0,
+                offset_col: 0,
+            },
+            decoded_bytes: data,
+        })
     }
 }
 
 impl<'dc6_file> EncodedFrame<'dc6_file> {
-    fn load(byte_reader: &mut ByteStream<'dc6_file>) -> Self {
+    fn load(byte_reader: &mut ByteStream<'dc6_file>) -> Result<Self, Dc6Error> {
+        let frame_offset = byte_reader.file_offset();
+
         // Skip flipped
-        byte_reader.skip_bytes(4);
+        byte_reader.skip_bytes(4)?;
 
-        let width = byte_reader.stream_uint();
-        let height = byte_reader.stream_uint();
+        let width = byte_reader.stream_uint()?;
+        let height = byte_reader.stream_uint()?;
+
+        if width > MAX_FRAME_SIZE || height > MAX_FRAME_SIZE {
+            return Err(Dc6Error::new(
+                frame_offset,
+                Dc6ErrorReason::FrameTooLarge { width, height },
+            ));
+        }
 
-        let offset_row = byte_reader.stream_int();
-        let offset_col = byte_reader.stream_int();
+        let offset_row = byte_reader.stream_int()?;
+        let offset_col = byte_reader.stream_int()?;
 
         let meta_data = FrameMetadata {
             height,
@@ -115,31 +229,92 @@ impl<'dc6_file> EncodedFrame<'dc6_file> {
         };
 
         // unknown, next_block
-        byte_reader.skip_bytes(2 * 4);
+        byte_reader.skip_bytes(2 * 4)?;
 
-        let num_frame_bytes = byte_reader.stream_uint();
-        let encoded_bytes = byte_reader.stream_bytes(num_frame_bytes);
+        let num_frame_bytes = byte_reader.stream_uint()?;
+        let encoded_bytes_offset = byte_reader.file_offset();
+        let encoded_bytes = byte_reader.stream_bytes(num_frame_bytes)?;
 
         // Skip terminator
-        byte_reader.skip_bytes(3);
+        byte_reader.skip_bytes(3)?;
 
-        Self {
+        Ok(Self {
             meta_data,
             encoded_bytes,
-        }
+            encoded_bytes_offset,
+        })
     }
 
+    /// Decodes the frame, panics if the encoded pixels are malformed
     pub fn decode(&self) -> DecodedFrame {
+        self.try_decode()
+            .unwrap_or_else(|e| panic!("Invalid dc6 frame: {e}"))
+    }
+
+    pub fn try_decode(&self) -> Result<DecodedFrame, Dc6Error> {
         let mut data: Vec<u8> =
             vec![0; self.meta_data.width as usize * self.meta_data.height as usize];
 
+        self.try_decode_into(&mut data, self.meta_data.width, 0, 0)?;
+
+        Ok(DecodedFrame {
+            meta_data: self.meta_data,
+            decoded_bytes: data,
+        })
+    }
+
+    /// Draws the frame into a target, panics if the encoded pixels are malformed
+    pub fn decode_into(&self, target: &mut [u8], target_width: u32, row: i32, col: i32) {
+        self.try_decode_into(target, target_width, row, col)
+            .unwrap_or_else(|e| panic!("Invalid dc6 frame: {e}"));
+    }
+
+    /// Draws the frame with its top left corner at `row` and `col` of a target with
+    /// rows of `target_width` pixels. The pixels outside of the target are clipped and
+    /// the transparent pixels keep the colors of the target.
+    pub fn try_decode_into(
+        &self,
+        target: &mut [u8],
+        target_width: u32,
+        row: i32,
+        col: i32,
+    ) -> Result<(), Dc6Error> {
+        let target_width = target_width as usize;
+        let target_height = target.len().checked_div(target_width).unwrap_or(0);
+
+        self.decode_runs(|frame_row, frame_col, pixels| {
+            let Some(target_row) = usize::try_from(i64::from(row) + i64::from(frame_row))
+                .ok()
+                .filter(|&target_row| target_row < target_height)
+            else {
+                return;
+            };
+
+            let target_col = i64::from(col) + i64::from(frame_col);
+            let skip = usize::try_from(-target_col).unwrap_or(0).min(pixels.len());
+            let target_col = usize::try_from(target_col).unwrap_or(0);
+            let len = (pixels.len() - skip).min(target_width.saturating_sub(target_col));
+
+            let idx = target_row * target_width + target_col;
+            target[idx..idx + len].copy_from_slice(&pixels[skip..skip + len]);
+        })
+    }
+
+    /// Calls `draw` with the row, the column and the pixels of each run of opaque pixels
+    fn decode_runs(&self, mut draw: impl FnMut(u32, u32, &[u8])) -> Result<(), Dc6Error> {
+        if self.meta_data.height == 0 {
+            return Ok(());
+        }
+
         let mut col: u32 = 0;
         let mut row = self.meta_data.height - 1;
 
-        let mut encoded_byte_reader = ByteStream::new(self.encoded_bytes);
+        let mut encoded_byte_reader =
+            ByteStream::new_at(self.encoded_bytes, self.encoded_bytes_offset);
 
         loop {
-            let byte = encoded_byte_reader.stream_byte();
+            let byte_offset = encoded_byte_reader.file_offset();
+            let byte = encoded_byte_reader.stream_byte()?;
 
             let scan_line_type = Self::scan_line_type(byte);
             match scan_line_type {
@@ -153,23 +328,25 @@ impl<'dc6_file> EncodedFrame<'dc6_file> {
                 }
                 ScanlineState::RunOfTransparentPixels => {
                     let num_transparent_pixels = byte & MAX_RUN_LENGTH;
-                    col += u32::from(num_transparent_pixels);
+                    col = col.saturating_add(u32::from(num_transparent_pixels));
                 }
                 ScanlineState::RunOfOpaquePixels => {
-                    let idx = row as usize * self.meta_data.width as usize + col as usize;
-                    for i in 0..byte as usize {
-                        data[idx + i] = encoded_byte_reader.stream_byte();
+                    if col + u32::from(byte) > self.meta_data.width {
+                        return Err(Dc6Error::new(
+                            byte_offset,
+                            Dc6ErrorReason::PixelOutOfBounds { row, col },
+                        ));
                     }
 
+                    let pixels = encoded_byte_reader.stream_bytes(u32::from(byte))?;
+                    draw(row, col, pixels);
+
                     col += u32::from(byte);
                 }
             }
         }
 
-        DecodedFrame {
-            meta_data: self.meta_data,
-            decoded_bytes: data,
-        }
+        Ok(())
     }
 
     fn scan_line_type(byte: u8) -> ScanlineState {
@@ -184,3 +361,240 @@ impl<'dc6_file> EncodedFrame<'dc6_file> {
         ScanlineState::RunOfOpaquePixels
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a single 3x2 frame, the bottom row is decoded first
+    fn dc6_file(encoded_bytes: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for value in [6_u32, 1, 0, 0xEEEE_EEEE, 1, 1, 32] {
+            data.extend(value.to_le_bytes());
+        }
+        for value in [0, 3, 2, 0, 0, 0, 0, encoded_bytes.len() as u32] {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(encoded_bytes);
+        data.extend([0xEE; 3]);
+        data
+    }
+
+    #[test]
+    fn test_decode_frame() {
+        let data = dc6_file(&[
+            0x81,
+            2,
+            5,
+            6,
+            END_OF_SCAN_LINE,
+            3,
+            1,
+            2,
+            3,
+            END_OF_SCAN_LINE,
+        ]);
+        let dc6 = Dc6::try_new(&data).unwrap();
+        let frame = dc6.directions[0].encoded_frames[0].try_decode().unwrap();
+
+        assert_eq!(frame.decoded_bytes, [1, 2, 3, 0, 5, 6]);
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let data = dc6_file(&[
+            0x81,
+            2,
+            5,
+            6,
+            END_OF_SCAN_LINE,
+            3,
+            1,
+            2,
+            3,
+            END_OF_SCAN_LINE,
+        ]);
+
+        for len in 0..data.len() - 1 {
+            let error = Dc6::try_new(&data[..len]).err().unwrap();
+            assert_eq!(error.reason, Dc6ErrorReason::UnexpectedEnd);
+        }
+
+        let error = Dc6::try_new(&dc6_file(&[])[4..]).err().unwrap();
+        assert_eq!(error.reason, Dc6ErrorReason::UnsupportedVersion(1));
+
+        let data = dc6_file(&[4, 1, 2, 3, 4, END_OF_SCAN_LINE]);
+        let dc6 = Dc6::try_new(&data).unwrap();
+        let error = dc6.directions[0].encoded_frames[0]
+            .try_decode()
+            .err()
+            .unwrap();
+        assert_eq!(error.byte_offset, 60);
+        assert_eq!(
+            error.reason,
+            Dc6ErrorReason::PixelOutOfBounds { row: 1, col: 0 }
+        );
+
+        let data = dc6_file(&[2, 1, 2]);
+        let dc6 = Dc6::try_new(&data).unwrap();
+        let error = dc6.directions[0].encoded_frames[0]
+            .try_decode()
+            .err()
+            .unwrap();
+        assert_eq!(error.reason, Dc6ErrorReason::UnexpectedEnd);
+    }
+
+    fn frame(width: u32, height: u32, pixels: Vec<u8>) -> DecodedFrame {
+        DecodedFrame {
+            meta_data: FrameMetadata {
+                width,
+                height,
+                offset_row: 0,
+                offset_col: 0,
+            },
+            decoded_bytes: pixels,
+        }
+    }
+
+    #[test]
+    fn test_decode_into() {
+        let data = dc6_file(&[
+            0x81,
+            2,
+            5,
+            6,
+            END_OF_SCAN_LINE,
+            3,
+            1,
+            2,
+            3,
+            END_OF_SCAN_LINE,
+        ]);
+        let dc6 = Dc6::try_new(&data).unwrap();
+        let encoded_frame = &dc6.directions[0].encoded_frames[0];
+
+        let mut target = vec![9; 4 * 3];
+        encoded_frame.decode_into(&mut target, 4, 2, -1);
+        encoded_frame.decode_into(&mut target, 4, -1, 2);
+
+        #[rustfmt::skip]
+        assert_eq!(target, [
+            9, 9, 9, 5,
+            9, 9, 9, 9,
+            2, 3, 9, 9,
+        ]);
+    }
+
+    #[test]
+    fn test_compose_grid() {
+        let bytes = crate::Dc6Builder::new()
+            .add_direction(vec![
+                frame(1, 1, vec![7]),
+                frame(2, 1, vec![1, 2]),
+                frame(1, 2, vec![3, 4]),
+                frame(1, 1, vec![5]),
+                frame(1, 1, vec![6]),
+            ])
+            .to_bytes()
+            .unwrap();
+        let dc6 = Dc6::try_new(&bytes).unwrap();
+
+        let grid = dc6.directions[0].try_compose_grid(1..5, 2).unwrap();
+
+        assert_eq!((grid.meta_data.width, grid.meta_data.height), (3, 3));
+        #[rustfmt::skip]
+        assert_eq!(grid.decoded_bytes, [
+            1, 2, 3,
+            0, 0, 4,
+            5, 0, 6,
+        ]);
+
+        let direction = &dc6.directions[0];
+        assert_eq!(
+            direction.try_compose_grid(1..5, 0).unwrap_err().reason,
+            Dc6ErrorReason::NoColumns
+        );
+        assert_eq!(
+            direction.try_compose_grid(4..7, 2).unwrap_err().reason,
+            Dc6ErrorReason::FramesOutOfBounds {
+                start: 4,
+                end: 7,
+                num_frames: 5
+            }
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 3..1;
+        assert!(direction.try_compose_grid(reversed, 2).is_err());
+    }
+
+    // the layout of the inventory and stash panels before they were composed as a grid,
+    // the frames are copied whole with the top left frame copied last
+    fn panel_layout(frames: &[DecodedFrame]) -> (u32, u32, Vec<u8>) {
+        let [top_left, top_right, bottom_left, bottom_right] = frames else {
+            panic!("a panel has 4 frames");
+        };
+        let width = top_left.meta_data.width + top_right.meta_data.width;
+        let height = top_left.meta_data.height + bottom_left.meta_data.height;
+        let mut data = vec![0; width as usize * height as usize];
+
+        for (frame, row, col) in [
+            (top_right, 0, top_left.meta_data.width),
+            (bottom_left, top_left.meta_data.height, 0),
+            (
+                bottom_right,
+                top_left.meta_data.height,
+                top_left.meta_data.width,
+            ),
+            (top_left, 0, 0),
+        ] {
+            let frame_width = frame.meta_data.width as usize;
+
+            for (i, frame_row) in frame.decoded_bytes.chunks(frame_width).enumerate() {
+                let start = (row as usize + i) * width as usize + col as usize;
+                data[start..start + frame_width].copy_from_slice(frame_row);
+            }
+        }
+
+        (width, height, data)
+    }
+
+    #[test]
+    fn test_compose_grid_matches_panel_layout() {
+        let pixels = |width: u32, height: u32, seed: u8| -> Vec<u8> {
+            (0..width * height)
+                .map(|i| [0, seed, 0, seed.wrapping_mul(3), 17][i as usize % 5])
+                .collect()
+        };
+
+        // the columns and rows differ in size and the bottom right frame is smaller than
+        // its cell, the frames of the first row and column are the largest like in the game
+        for sizes in [
+            [(4, 3), (4, 3), (4, 3), (4, 3)],
+            [(4, 3), (2, 3), (4, 5), (2, 5)],
+            [(4, 3), (2, 2), (3, 2), (1, 1)],
+        ] {
+            let frames: Vec<DecodedFrame> = sizes
+                .iter()
+                .enumerate()
+                .map(|(i, &(width, height))| {
+                    frame(width, height, pixels(width, height, i as u8 + 1))
+                })
+                .collect();
+
+            let bytes = crate::Dc6Builder::new()
+                .add_direction(frames.clone())
+                .to_bytes()
+                .unwrap();
+            let dc6 = Dc6::try_new(&bytes).unwrap();
+
+            let grid = dc6.directions[0].try_compose_grid(0..4, 2).unwrap();
+            let (width, height, data) = panel_layout(&frames);
+
+            assert_eq!(
+                (grid.meta_data.width, grid.meta_data.height),
+                (width, height)
+            );
+            assert_eq!(grid.decoded_bytes, data);
+        }
+    }
+}
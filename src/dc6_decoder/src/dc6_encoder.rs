@@ -0,0 +1,271 @@
+use crate::{
+    dc6_decoder::{
+        DecodedFrame, END_OF_SCAN_LINE, EXPECTED_DC6_VERSION, MAX_FRAME_SIZE, MAX_RUN_LENGTH,
+    },
+    error::Dc6EncodeError,
+};
+
+const FLAGS: u32 = 1;
+const ENCODING: u32 = 0;
+const TERMINATION: u32 = 0xEEEE_EEEE;
+const FRAME_TERMINATOR: [u8; 3] = [0xEE; 3];
+const HEADER_SIZE: usize = 6 * 4;
+const FRAME_HEADER_SIZE: usize = 8 * 4;
+
+/// Builds a DC6 file from palette indexed frames.
+///
+/// Every direction must have the same number of frames. The pixels are run length
+/// encoded, color 0 is transparent.
+///
+/// ```
+/// use dc6_decoder::{Dc6, Dc6Builder, DecodedFrame, FrameMetadata};
+///
+/// let frame = DecodedFrame {
+///     meta_data: FrameMetadata {
+///         width: 2,
+///         height: 2,
+///         offset_row: 0,
+///         offset_col: 0,
+///     },
+///     decoded_bytes: vec![1, 2, 0, 1],
+/// };
+///
+/// let bytes = Dc6Builder::new().add_direction(vec![frame.clone()]).to_bytes().unwrap();
+/// let dc6 = Dc6::try_new(&bytes).unwrap();
+///
+/// assert_eq!(dc6.directions[0].encoded_frames[0].decode(), frame);
+/// ```
+#[derive(Debug, Default)]
+pub struct Dc6Builder {
+    directions: Vec<Vec<DecodedFrame>>,
+}
+
+impl Dc6Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_direction(&mut self, frames: Vec<DecodedFrame>) -> &mut Self {
+        self.directions.push(frames);
+        self
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Dc6EncodeError> {
+        let num_frames = self.directions.first().map_or(0, Vec::len);
+
+        for (direction, frames) in self.directions.iter().enumerate() {
+            if frames.len() != num_frames {
+                return Err(Dc6EncodeError::InvalidFrameCount { direction });
+            }
+        }
+
+        let encoded_frames = self
+            .directions
+            .iter()
+            .enumerate()
+            .flat_map(|(direction, frames)| {
+                frames
+                    .iter()
+                    .enumerate()
+                    .map(move |(frame, decoded_frame)| {
+                        encode_frame(direction, frame, decoded_frame)
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let blocks_size = 4 * encoded_frames.len();
+        let frames_size: usize = encoded_frames
+            .iter()
+            .map(|encoded_bytes| block_size(encoded_bytes))
+            .sum();
+
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + blocks_size + frames_size);
+        bytes.extend(EXPECTED_DC6_VERSION.to_le_bytes());
+        bytes.extend(FLAGS.to_le_bytes());
+        bytes.extend(ENCODING.to_le_bytes());
+        bytes.extend(TERMINATION.to_le_bytes());
+        bytes.extend((self.directions.len() as u32).to_le_bytes());
+        bytes.extend((num_frames as u32).to_le_bytes());
+
+        let mut block_offset = HEADER_SIZE + blocks_size;
+        for encoded_bytes in &encoded_frames {
+            bytes.extend((block_offset as u32).to_le_bytes());
+            block_offset += block_size(encoded_bytes);
+        }
+
+        let frames = self.directions.iter().flatten();
+        for (decoded_frame, encoded_bytes) in frames.zip(&encoded_frames) {
+            let meta_data = &decoded_frame.meta_data;
+            let next_block = bytes.len() + block_size(encoded_bytes);
+
+            // not flipped, the rows are stored from bottom to top
+            bytes.extend(0_u32.to_le_bytes());
+            bytes.extend(meta_data.width.to_le_bytes());
+            bytes.extend(meta_data.height.to_le_bytes());
+            bytes.extend(meta_data.offset_row.to_le_bytes());
+            bytes.extend(meta_data.offset_col.to_le_bytes());
+            // unknown
+            bytes.extend(0_u32.to_le_bytes());
+            bytes.extend((next_block as u32).to_le_bytes());
+            bytes.extend((encoded_bytes.len() as u32).to_le_bytes());
+            bytes.extend(encoded_bytes);
+            bytes.extend(FRAME_TERMINATOR);
+        }
+
+        Ok(bytes)
+    }
+}
+
+fn block_size(encoded_bytes: &[u8]) -> usize {
+    FRAME_HEADER_SIZE + encoded_bytes.len() + FRAME_TERMINATOR.len()
+}
+
+fn encode_frame(
+    direction: usize,
+    frame: usize,
+    decoded_frame: &DecodedFrame,
+) -> Result<Vec<u8>, Dc6EncodeError> {
+    let meta_data = &decoded_frame.meta_data;
+
+    if meta_data.width > MAX_FRAME_SIZE
+        || meta_data.height > MAX_FRAME_SIZE
+        || decoded_frame.decoded_bytes.len() != meta_data.width as usize * meta_data.height as usize
+    {
+        return Err(Dc6EncodeError::InvalidFrame { direction, frame });
+    }
+
+    let mut encoded_bytes = Vec::new();
+
+    if meta_data.width == 0 {
+        encoded_bytes.resize(meta_data.height as usize, END_OF_SCAN_LINE);
+        return Ok(encoded_bytes);
+    }
+
+    for row in decoded_frame
+        .decoded_bytes
+        .chunks(meta_data.width as usize)
+        .rev()
+    {
+        encode_scan_line(row, &mut encoded_bytes);
+    }
+
+    Ok(encoded_bytes)
+}
+
+fn encode_scan_line(row: &[u8], encoded_bytes: &mut Vec<u8>) {
+    // the transparent pixels at the end of the line are implied by the end of the line
+    let len = row
+        .iter()
+        .rposition(|&pixel| pixel != 0)
+        .map_or(0, |i| i + 1);
+    let mut pixels = &row[..len];
+
+    while !pixels.is_empty() {
+        let is_transparent = pixels[0] == 0;
+        let run_length = pixels
+            .iter()
+            .take(usize::from(MAX_RUN_LENGTH))
+            .take_while(|&&pixel| (pixel == 0) == is_transparent)
+            .count();
+
+        if is_transparent {
+            encoded_bytes.push(END_OF_SCAN_LINE | run_length as u8);
+        } else {
+            encoded_bytes.push(run_length as u8);
+            encoded_bytes.extend(&pixels[..run_length]);
+        }
+
+        pixels = &pixels[run_length..];
+    }
+
+    encoded_bytes.push(END_OF_SCAN_LINE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dc6, FrameMetadata};
+
+    fn frame(width: u32, height: u32, offset_row: i32, offset_col: i32) -> DecodedFrame {
+        let decoded_bytes = (0..width * height)
+            .map(|i| match i % 7 {
+                0 | 1 => 0,
+                n => (i / 7 % 200) as u8 + n as u8,
+            })
+            .collect();
+
+        DecodedFrame {
+            meta_data: FrameMetadata {
+                width,
+                height,
+                offset_row,
+                offset_col,
+            },
+            decoded_bytes,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut wide = frame(300, 2, 0, 0);
+        // long runs of opaque and transparent pixels and a transparent end of line
+        wide.decoded_bytes[..200].fill(9);
+        wide.decoded_bytes[300..560].fill(0);
+
+        let directions = vec![
+            vec![frame(5, 4, -3, 7), wide, frame(0, 3, 0, 0)],
+            vec![frame(1, 1, 0, 0), frame(8, 0, 2, 2), frame(13, 9, 5, -5)],
+        ];
+
+        let mut builder = Dc6Builder::new();
+        for frames in &directions {
+            builder.add_direction(frames.clone());
+        }
+        let bytes = builder.to_bytes().unwrap();
+        let dc6 = Dc6::try_new(&bytes).unwrap();
+
+        assert_eq!(dc6.directions.len(), directions.len());
+        for (direction, frames) in dc6.directions.iter().zip(&directions) {
+            let decoded_frames: Vec<_> = direction
+                .encoded_frames
+                .iter()
+                .map(|frame| frame.try_decode().unwrap())
+                .collect();
+            assert_eq!(&decoded_frames, frames);
+        }
+
+        // the block pointers point to the frames
+        let block = u32::from_le_bytes(bytes[HEADER_SIZE + 4..HEADER_SIZE + 8].try_into().unwrap());
+        let width = &bytes[block as usize + 4..block as usize + 8];
+        assert_eq!(width, 300_u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+        let mut invalid = frame(3, 3, 0, 0);
+        invalid.decoded_bytes.pop();
+
+        let error = Dc6Builder::new()
+            .add_direction(vec![frame(2, 2, 0, 0)])
+            .add_direction(vec![invalid])
+            .to_bytes()
+            .err();
+        assert_eq!(
+            error,
+            Some(Dc6EncodeError::InvalidFrame {
+                direction: 1,
+                frame: 0
+            })
+        );
+
+        let error = Dc6Builder::new()
+            .add_direction(vec![frame(2, 2, 0, 0)])
+            .add_direction(Vec::new())
+            .to_bytes()
+            .err();
+        assert_eq!(
+            error,
+            Some(Dc6EncodeError::InvalidFrameCount { direction: 1 })
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+/// Why a DC6 file couldn't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dc6ErrorReason {
+    /// a stream read past the end of the data
+    UnexpectedEnd,
+    UnsupportedVersion(i32),
+    /// the frame is larger than any frame of the game
+    FrameTooLarge {
+        width: u32,
+        height: u32,
+    },
+    /// a run of pixels goes past the edge of the frame
+    PixelOutOfBounds {
+        row: u32,
+        col: u32,
+    },
+    /// the image composed of the frames would be too large, the byte offset is 0
+    ImageTooLarge,
+    /// the frames to compose aren't all in the direction, the byte offset is 0
+    FramesOutOfBounds {
+        start: usize,
+        end: usize,
+        num_frames: usize,
+    },
+    /// the frames are composed into a grid without columns, the byte offset is 0
+    NoColumns,
+}
+
+/// A malformed DC6 file, with the position in the file where the problem was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dc6Error {
+    pub byte_offset: usize,
+    pub reason: Dc6ErrorReason,
+}
+
+impl Dc6Error {
+    pub(crate) fn new(byte_offset: usize, reason: Dc6ErrorReason) -> Self {
+        Self {
+            byte_offset,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Dc6ErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dc6ErrorReason::UnexpectedEnd => write!(f, "unexpected end of data"),
+            Dc6ErrorReason::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {version}")
+            }
+            Dc6ErrorReason::FrameTooLarge { width, height } => {
+                write!(f, "frame of {width}x{height} pixels is too large")
+            }
+            Dc6ErrorReason::PixelOutOfBounds { row, col } => {
+                write!(
+                    f,
+                    "pixel at row {row}, column {col} is outside of the frame"
+                )
+            }
+            Dc6ErrorReason::ImageTooLarge => write!(f, "composed image is too large"),
+            Dc6ErrorReason::FramesOutOfBounds {
+                start,
+                end,
+                num_frames,
+            } => {
+                write!(
+                    f,
+                    "frames {start}..{end} are outside of the {num_frames} frames of the direction"
+                )
+            }
+            Dc6ErrorReason::NoColumns => write!(f, "grid of frames has no columns"),
+        }
+    }
+}
+
+impl fmt::Display for Dc6Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.reason, self.byte_offset)
+    }
+}
+
+impl std::error::Error for Dc6Error {}
+
+/// Why frames couldn't be encoded into a DC6 file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dc6EncodeError {
+    /// the directions have different numbers of frames
+    InvalidFrameCount { direction: usize },
+    /// the pixels don't match the size of the frame, or the frame is too large
+    InvalidFrame { direction: usize, frame: usize },
+}
+
+impl fmt::Display for Dc6EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dc6EncodeError::InvalidFrameCount { direction } => {
+                write!(f, "invalid number of frames in direction {direction}")
+            }
+            Dc6EncodeError::InvalidFrame { direction, frame } => {
+                write!(f, "invalid frame {frame} in direction {direction}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Dc6EncodeError {}
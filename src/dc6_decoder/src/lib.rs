@@ -1,7 +1,14 @@
 mod byte_stream;
 mod dc6_decoder;
+mod dc6_encoder;
+mod error;
+mod sprite_sheet;
 
 pub use crate::dc6_decoder::Dc6;
 pub use crate::dc6_decoder::DecodedFrame;
 pub use crate::dc6_decoder::Direction;
 pub use crate::dc6_decoder::EncodedFrame;
+pub use crate::dc6_decoder::FrameMetadata;
+pub use crate::dc6_encoder::Dc6Builder;
+pub use crate::error::{Dc6EncodeError, Dc6Error, Dc6ErrorReason};
+pub use crate::sprite_sheet::{SpriteSheet, SpriteSheetFrame};
@@ -0,0 +1,196 @@
+use std::fmt::Write;
+
+use crate::{
+    dc6_decoder::{Dc6, FrameMetadata, MAX_IMAGE_PIXELS},
+    error::{Dc6Error, Dc6ErrorReason},
+};
+
+/// All frames of a DC6 file laid out on a single palette indexed image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteSheet {
+    pub width: u32,
+    pub height: u32,
+    /// the rows of the image from top to bottom, color 0 is transparent
+    pub pixels: Vec<u8>,
+    /// where each frame is placed, in the order of the directions and frames
+    pub frames: Vec<SpriteSheetFrame>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteSheetFrame {
+    pub direction: usize,
+    pub frame: usize,
+    /// top left corner of the frame on the sprite sheet
+    pub row: u32,
+    pub col: u32,
+    pub meta_data: FrameMetadata,
+}
+
+impl SpriteSheet {
+    /// Lays the frames out from left to right, in rows of `frames_per_row` frames.
+    /// Each row is as high as its highest frame. Returns an error instead of allocating a sheet
+    /// of more than `MAX_IMAGE_PIXELS` pixels.
+    ///
+    /// Panics if `frames_per_row` is 0.
+    pub fn new(dc6: &Dc6, frames_per_row: usize) -> Result<Self, Dc6Error> {
+        let encoded_frames: Vec<_> = dc6
+            .directions
+            .iter()
+            .enumerate()
+            .flat_map(|(direction, frames)| {
+                frames
+                    .encoded_frames
+                    .iter()
+                    .enumerate()
+                    .map(move |(frame, encoded_frame)| (direction, frame, encoded_frame))
+            })
+            .collect();
+
+        let too_large = || Dc6Error::new(0, Dc6ErrorReason::ImageTooLarge);
+
+        let mut frames = Vec::with_capacity(encoded_frames.len());
+        let mut width = 0;
+        let mut height: u32 = 0;
+
+        for row_frames in encoded_frames.chunks(frames_per_row) {
+            let mut col = 0;
+            for &(direction, frame, encoded_frame) in row_frames {
+                frames.push(SpriteSheetFrame {
+                    direction,
+                    frame,
+                    row: height,
+                    col,
+                    meta_data: encoded_frame.meta_data,
+                });
+                col = col
+                    .checked_add(encoded_frame.meta_data.width)
+                    .ok_or_else(too_large)?;
+            }
+
+            width = width.max(col);
+            height = row_frames
+                .iter()
+                .map(|(_, _, encoded_frame)| encoded_frame.meta_data.height)
+                .max()
+                .and_then(|row_height| height.checked_add(row_height))
+                .ok_or_else(too_large)?;
+        }
+
+        if u64::from(width) * u64::from(height) > MAX_IMAGE_PIXELS {
+            return Err(too_large());
+        }
+
+        let mut pixels = vec![0; width as usize * height as usize];
+
+        for (placement, (_, _, encoded_frame)) in frames.iter().zip(&encoded_frames) {
+            encoded_frame.try_decode_into(
+                &mut pixels,
+                width,
+                placement.row as i32,
+                placement.col as i32,
+            )?;
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            frames,
+        })
+    }
+
+    /// Returns a tab separated table with a line per frame
+    pub fn metadata_table(&self) -> String {
+        let mut table =
+            String::from("direction\tframe\trow\tcol\twidth\theight\toffset_row\toffset_col\n");
+
+        for frame in &self.frames {
+            writeln!(
+                table,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                frame.direction,
+                frame.frame,
+                frame.row,
+                frame.col,
+                frame.meta_data.width,
+                frame.meta_data.height,
+                frame.meta_data.offset_row,
+                frame.meta_data.offset_col
+            )
+            .unwrap();
+        }
+
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dc6Builder, DecodedFrame};
+
+    fn frame(width: u32, height: u32, color: u8) -> DecodedFrame {
+        DecodedFrame {
+            meta_data: FrameMetadata {
+                width,
+                height,
+                offset_row: 0,
+                offset_col: -1,
+            },
+            decoded_bytes: vec![color; width as usize * height as usize],
+        }
+    }
+
+    #[test]
+    fn test_sprite_sheet() {
+        let bytes = Dc6Builder::new()
+            .add_direction(vec![frame(2, 1, 1), frame(1, 2, 2), frame(3, 1, 3)])
+            .to_bytes()
+            .unwrap();
+        let dc6 = Dc6::try_new(&bytes).unwrap();
+
+        let sheet = SpriteSheet::new(&dc6, 2).unwrap();
+
+        assert_eq!((sheet.width, sheet.height), (3, 3));
+        #[rustfmt::skip]
+        assert_eq!(sheet.pixels, [
+            1, 1, 2,
+            0, 0, 2,
+            3, 3, 3,
+        ]);
+        assert_eq!((sheet.frames[1].row, sheet.frames[1].col), (0, 2));
+        assert_eq!((sheet.frames[2].row, sheet.frames[2].col), (2, 0));
+
+        let table = sheet.metadata_table();
+        assert_eq!(table.lines().count(), 4);
+        assert_eq!(table.lines().nth(3), Some("0\t2\t2\t0\t3\t1\t0\t-1"));
+    }
+
+    #[test]
+    fn test_sprite_sheet_too_large() {
+        let empty_frame = DecodedFrame {
+            meta_data: FrameMetadata {
+                width: 4096,
+                height: 1024,
+                offset_row: 0,
+                offset_col: 0,
+            },
+            decoded_bytes: vec![0; 4096 * 1024],
+        };
+        let sprite_sheet = |num_frames| {
+            let bytes = Dc6Builder::new()
+                .add_direction(vec![empty_frame.clone(); num_frames])
+                .to_bytes()
+                .unwrap();
+            let dc6 = Dc6::try_new(&bytes).unwrap();
+
+            SpriteSheet::new(&dc6, num_frames).map(|sheet| sheet.width)
+        };
+
+        assert_eq!(sprite_sheet(16), Ok(16 * 4096));
+        assert_eq!(
+            sprite_sheet(17).unwrap_err().reason,
+            Dc6ErrorReason::ImageTooLarge
+        );
+    }
+}
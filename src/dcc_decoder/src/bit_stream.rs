@@ -1,3 +1,5 @@
+use crate::error::{DccError, DccErrorReason};
+
 #[derive(Debug)]
 pub struct BitStream<'bytes> {
     pub bytes: &'bytes [u8],
@@ -7,75 +9,84 @@ pub struct BitStream<'bytes> {
 
 impl<'bytes> BitStream<'bytes> {
     pub fn new(bytes: &'bytes [u8]) -> Self {
-        Self {
-            bytes,
-            byte_offset: 0,
-            bit_offset: 0,
-        }
+        Self::new_at(bytes, 0, 0)
     }
 
-    pub fn new_with_bit_offset(bytes: &'bytes [u8], bit_offset: u8) -> Self {
+    /// Creates a stream starting at an offset of the bytes, the offsets of the
+    /// errors are relative to the start of the bytes
+    pub fn new_at(bytes: &'bytes [u8], byte_offset: usize, bit_offset: u8) -> Self {
         Self {
             bytes,
-            byte_offset: 0,
+            byte_offset,
             bit_offset,
         }
     }
 
-    pub fn stream_alligned_uint(&mut self) -> u32 {
-        u32::from_le_bytes(self.stream_four_bytes())
+    pub fn error(&self, reason: DccErrorReason) -> DccError {
+        DccError::new(self.byte_offset, self.bit_offset, reason)
     }
 
-    pub fn stream_alligned_byte(&mut self) -> u8 {
-        self.validate_byte_read();
-        let byte = self.bytes[self.byte_offset];
+    pub fn stream_alligned_uint(&mut self) -> Result<u32, DccError> {
+        Ok(u32::from_le_bytes(self.stream_four_bytes()?))
+    }
+
+    pub fn stream_alligned_byte(&mut self) -> Result<u8, DccError> {
+        self.validate_byte_read()?;
+        let byte = self.current_byte()?;
         self.byte_offset += 1;
-        byte
+        Ok(byte)
     }
 
-    pub fn stream_byte(&mut self, num_bits: u8) -> u8 {
+    pub fn stream_byte(&mut self, num_bits: u8) -> Result<u8, DccError> {
+        if num_bits > 8 {
+            return Err(self.error(DccErrorReason::TooManyBits {
+                num_bits,
+                max_bits: 8,
+            }));
+        }
+
         let mut byte = 0;
 
         for i in 0..num_bits {
-            if Self::has_bit(self.bytes[self.byte_offset], self.bit_offset) {
+            if self.stream_bit()? {
                 byte |= 1 << i;
             }
-
-            self.increment_bit_offset();
         }
 
-        byte
+        Ok(byte)
     }
 
-    pub fn stream_bits(&mut self, num_bits: u8) -> u32 {
-        let bytes = self.get_bits_as_bytes(num_bits);
-        u32::from_le_bytes(bytes)
+    pub fn stream_bits(&mut self, num_bits: u8) -> Result<u32, DccError> {
+        let bytes = self.get_bits_as_bytes(num_bits)?;
+        Ok(u32::from_le_bytes(bytes))
     }
 
-    pub fn stream_bit(&mut self) -> bool {
-        let result = Self::has_bit(self.bytes[self.byte_offset], self.bit_offset);
+    pub fn stream_bit(&mut self) -> Result<bool, DccError> {
+        let result = Self::has_bit(self.current_byte()?, self.bit_offset);
 
         self.increment_bit_offset();
 
-        result
+        Ok(result)
     }
 
-    pub fn stream_signed_bits(&mut self, num_bits: u8) -> i32 {
+    pub fn stream_signed_bits(&mut self, num_bits: u8) -> Result<i32, DccError> {
         if num_bits == 0 {
-            return 0;
+            return Ok(0);
         }
 
         if num_bits == 1 {
-            if Self::has_bit(self.bytes[self.byte_offset], self.bit_offset) {
-                self.increment_bit_offset();
-                return -1;
-            }
-            self.increment_bit_offset();
-            return 0;
+            return Ok(if self.stream_bit()? { -1 } else { 0 });
         }
 
-        let bytes = self.get_bits_as_bytes(num_bits);
-        Self::extend_sign(bytes, num_bits)
+        let bytes = self.get_bits_as_bytes(num_bits)?;
+        Ok(Self::extend_sign(bytes, num_bits))
+    }
+
+    fn current_byte(&self) -> Result<u8, DccError> {
+        self.bytes
+            .get(self.byte_offset)
+            .copied()
+            .ok_or_else(|| self.error(DccErrorReason::UnexpectedEnd))
     }
 
     fn increment_bit_offset(&mut self) {
@@ -89,50 +100,56 @@ impl<'bytes> BitStream<'bytes> {
     fn extend_sign(bytes: [u8; 4], bits_number: u8) -> i32 {
         let mut value = i32::from_le_bytes(bytes);
 
-        if value & (1 << (bits_number - 1)) != 0 {
+        if bits_number < 32 && value & (1 << (bits_number - 1)) != 0 {
             value |= !((1 << bits_number) - 1);
         }
 
         value
     }
 
-    fn validate_byte_read(&self) {
+    fn validate_byte_read(&self) -> Result<(), DccError> {
         if self.bit_offset != 0 {
-            panic!("Attempt to read unalligned byte(s)")
+            return Err(self.error(DccErrorReason::UnalignedRead));
         }
+
+        Ok(())
     }
 
-    fn stream_four_bytes(&mut self) -> [u8; 4] {
-        self.validate_byte_read();
-        let bytes: [u8; 4] = self.bytes[self.byte_offset..self.byte_offset + 4]
+    fn stream_four_bytes(&mut self) -> Result<[u8; 4], DccError> {
+        self.validate_byte_read()?;
+        let bytes: [u8; 4] = self
+            .bytes
+            .get(self.byte_offset..self.byte_offset + 4)
+            .ok_or_else(|| self.error(DccErrorReason::UnexpectedEnd))?
             .try_into()
-            .expect("slice with incorrect length");
+            .unwrap();
         self.byte_offset += 4;
-        bytes
+        Ok(bytes)
     }
 
     fn has_bit(byte: u8, bit_position: u8) -> bool {
         byte & (1 << bit_position) != 0
     }
 
-    fn get_bits_as_bytes(&mut self, num_bits: u8) -> [u8; 4] {
+    fn get_bits_as_bytes(&mut self, num_bits: u8) -> Result<[u8; 4], DccError> {
         let mut bytes: [u8; 4] = [0; 4];
 
         if num_bits == 0 {
-            return bytes;
+            return Ok(bytes);
         }
 
         if num_bits > 32 {
-            panic!("can't read more than 32 bits")
+            return Err(self.error(DccErrorReason::TooManyBits {
+                num_bits,
+                max_bits: 32,
+            }));
         }
 
         let mut dst_bit: u8 = 0;
         let mut dst_byte: u8 = 0;
 
         for _ in 0..num_bits {
-            let byte = self.bytes[self.byte_offset];
-
-            if Self::has_bit(byte, self.bit_offset) {
+            if self.stream_bit()? {
                 bytes[dst_byte as usize] |= 1 << dst_bit;
             }
 
@@ -141,10 +158,8 @@ impl<'bytes> BitStream<'bytes> {
                 dst_bit = 0;
                 dst_byte += 1;
             }
-
-            self.increment_bit_offset();
         }
 
-        bytes
+        Ok(bytes)
     }
 }
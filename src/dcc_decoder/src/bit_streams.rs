@@ -1,4 +1,4 @@
-use crate::bit_stream::BitStream;
+use crate::{bit_stream::BitStream, error::DccError};
 
 #[derive(Debug)]
 pub struct BitStreamSizes {
@@ -28,18 +28,18 @@ pub struct EncodingTypeRawPixelBitStreams<'dcc_file> {
 }
 
 impl BitStreamSizes {
-    pub fn load(bs: &mut BitStream, compression_flag: u32) -> Self {
+    pub fn load(bs: &mut BitStream, compression_flag: u32) -> Result<Self, DccError> {
         let equal_cell = if compression_flag & 2 != 0 {
-            Some(bs.stream_bits(20))
+            Some(bs.stream_bits(20)?)
         } else {
             None
         };
 
-        let pixel_mask = bs.stream_bits(20);
+        let pixel_mask = bs.stream_bits(20)?;
 
         let encoding_type_raw_pixel = if compression_flag & 1 != 0 {
-            let encoding_type = bs.stream_bits(20);
-            let raw_pixel = bs.stream_bits(20);
+            let encoding_type = bs.stream_bits(20)?;
+            let raw_pixel = bs.stream_bits(20)?;
             Some(EncodingTypeRawPixelBitStreamSizes {
                 encoding_type,
                 raw_pixel,
@@ -48,24 +48,22 @@ impl BitStreamSizes {
             None
         };
 
-        Self {
+        Ok(Self {
             equal_cell,
             pixel_mask,
             encoding_type_raw_pixel,
-        }
+        })
     }
 }
 
 impl<'bytes> BitStreams<'bytes> {
-    pub fn load(bs: &mut BitStream, bs_sizes: &BitStreamSizes, data: &'bytes [u8]) -> Self {
-        let mut bit_offset = (bs.byte_offset * 8) as u32 + u32::from(bs.bit_offset);
+    pub fn load(bs: &BitStream<'bytes>, bs_sizes: &BitStreamSizes) -> Self {
+        let mut bit_offset = bs.byte_offset * 8 + usize::from(bs.bit_offset);
 
+        // the streams follow each other, reads past the end of the data fail when decoding
         let mut create_bitstream = |size: u32| -> BitStream<'bytes> {
-            let bitstream = BitStream::new_with_bit_offset(
-                &data[(bit_offset / 8) as usize..],
-                (bit_offset % 8) as u8,
-            );
-            bit_offset += size;
+            let bitstream = BitStream::new_at(bs.bytes, bit_offset / 8, (bit_offset % 8) as u8);
+            bit_offset += size as usize;
             bitstream
         };
 
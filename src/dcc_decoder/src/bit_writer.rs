@@ -0,0 +1,50 @@
+/// Writes values bit by bit in the order in which `BitStream` reads them
+#[derive(Debug, Default)]
+pub struct BitWriter {
+    pub bytes: Vec<u8>,
+    pub num_bits: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_bit(&mut self, bit: bool) {
+        if self.num_bits.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 1 << (self.num_bits % 8);
+        }
+
+        self.num_bits += 1;
+    }
+
+    pub fn write_bits(&mut self, value: u32, num_bits: u8) {
+        for i in 0..num_bits {
+            self.write_bit(value & (1 << i) != 0);
+        }
+    }
+
+    pub fn write_signed_bits(&mut self, value: i32, num_bits: u8) {
+        self.write_bits(value as u32, num_bits);
+    }
+
+    pub fn write_alligned_bytes(&mut self, bytes: &[u8]) {
+        self.allign();
+        self.bytes.extend(bytes);
+        self.num_bits += bytes.len() * 8;
+    }
+
+    pub fn append(&mut self, other: &BitWriter) {
+        for i in 0..other.num_bits {
+            self.write_bit(other.bytes[i / 8] & (1 << (i % 8)) != 0);
+        }
+    }
+
+    pub fn allign(&mut self) {
+        self.num_bits = self.bytes.len() * 8;
+    }
+}
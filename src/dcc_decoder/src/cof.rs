@@ -0,0 +1,308 @@
+use crate::{
+    dcc_decoder::Direction,
+    error::{CofError, CofErrorReason},
+    frame::{Frame, PointI32, PointU16},
+};
+
+/// The names of the composite types, as used in the paths of the DCC files of the layers
+pub const COMPOSITE_NAMES: [&str; 16] = [
+    "HD", "TR", "LG", "RA", "LA", "RH", "LH", "SH", "S1", "S2", "S3", "S4", "S5", "S6", "S7", "S8",
+];
+
+const HEADER_SIZE: usize = 28;
+const SPEED_OFFSET: usize = 24;
+const LAYER_SIZE: usize = 9;
+
+/// A COF file, it describes how the DCC layers of an animation are put together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cof {
+    pub num_directions: usize,
+    pub frames_per_direction: usize,
+    pub speed: u8,
+    pub layers: Vec<CofLayer>,
+    /// the event triggered at each frame, like the hit of an attack
+    pub frame_triggers: Vec<u8>,
+    // the composite types of each frame of each direction, in the order in which they are drawn
+    draw_order: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CofLayer {
+    /// an index into `COMPOSITE_NAMES`
+    pub composite: u8,
+    pub shadow: bool,
+    pub selectable: bool,
+    pub transparent: bool,
+    pub draw_effect: u8,
+    pub weapon_class: String,
+}
+
+/// Layers drawn on top of each other, with the anchor of the sprite
+#[derive(Debug, Clone)]
+pub struct CompositeFrame {
+    pub frame: Frame,
+    /// the position of the anchor in the frame, it may lie outside of the frame
+    pub anchor: PointI32,
+}
+
+impl Cof {
+    /// Parses a COF file, panics if the file is malformed
+    pub fn new(data: &[u8]) -> Self {
+        Self::try_new(data).unwrap_or_else(|e| panic!("Invalid cof file: {e}"))
+    }
+
+    pub fn try_new(data: &[u8]) -> Result<Self, CofError> {
+        let get_bytes = |offset: usize, len: usize| {
+            data.get(offset..offset + len).ok_or(CofError {
+                byte_offset: data.len(),
+                reason: CofErrorReason::UnexpectedEnd,
+            })
+        };
+
+        let header = get_bytes(0, HEADER_SIZE)?;
+        let num_layers = usize::from(header[0]);
+        let frames_per_direction = usize::from(header[1]);
+        let num_directions = usize::from(header[2]);
+        let speed = header[SPEED_OFFSET];
+
+        let layers_offset = HEADER_SIZE;
+        let layers = get_bytes(layers_offset, num_layers * LAYER_SIZE)?
+            .chunks_exact(LAYER_SIZE)
+            .enumerate()
+            .map(|(i, layer)| CofLayer::new(layer, layers_offset + i * LAYER_SIZE))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let triggers_offset = layers_offset + num_layers * LAYER_SIZE;
+        let frame_triggers = get_bytes(triggers_offset, frames_per_direction)?.to_vec();
+
+        let draw_order_offset = triggers_offset + frames_per_direction;
+        let draw_order = get_bytes(
+            draw_order_offset,
+            num_directions * frames_per_direction * num_layers,
+        )?
+        .to_vec();
+
+        if let Some(i) = draw_order
+            .iter()
+            .position(|&composite| usize::from(composite) >= COMPOSITE_NAMES.len())
+        {
+            return Err(CofError {
+                byte_offset: draw_order_offset + i,
+                reason: CofErrorReason::InvalidComposite(draw_order[i]),
+            });
+        }
+
+        Ok(Self {
+            num_directions,
+            frames_per_direction,
+            speed,
+            layers,
+            frame_triggers,
+            draw_order,
+        })
+    }
+
+    /// Returns the composite types of a frame in the order in which they are drawn, panics
+    /// if the direction or the frame is out of bounds
+    pub fn draw_order(&self, direction: usize, frame: usize) -> &[u8] {
+        let num_layers = self.layers.len();
+        let start = (direction * self.frames_per_direction + frame) * num_layers;
+        &self.draw_order[start..start + num_layers]
+    }
+
+    /// Draws the layers of a frame on top of each other in the draw order. The layers are
+    /// indexed by their composite type and are decoded DCC directions of the direction.
+    /// Missing layers are skipped, the transparent pixels of a layer don't cover the
+    /// layers below.
+    ///
+    /// Panics if the direction or the frame is out of bounds.
+    pub fn compose_frame(
+        &self,
+        direction: usize,
+        frame: usize,
+        layers: &[Option<&Direction>; COMPOSITE_NAMES.len()],
+    ) -> CompositeFrame {
+        let draw_order: Vec<&Direction> = self
+            .draw_order(direction, frame)
+            .iter()
+            .filter_map(|&composite| layers[usize::from(composite)])
+            .filter(|layer| frame < layer.frames.len())
+            .collect();
+
+        let row_min = draw_order
+            .iter()
+            .map(|layer| i32::from(layer.direction_box.row_min))
+            .min()
+            .unwrap_or(0);
+        let col_min = draw_order
+            .iter()
+            .map(|layer| i32::from(layer.direction_box.col_min))
+            .min()
+            .unwrap_or(0);
+        let row_max = draw_order
+            .iter()
+            .map(|layer| i32::from(layer.direction_box.row_max))
+            .max()
+            .unwrap_or(-1);
+        let col_max = draw_order
+            .iter()
+            .map(|layer| i32::from(layer.direction_box.col_max))
+            .max()
+            .unwrap_or(-1);
+
+        let mut composite_frame = Frame::new(PointU16 {
+            row: (row_max - row_min + 1) as u16,
+            col: (col_max - col_min + 1) as u16,
+        });
+
+        for layer in draw_order {
+            let layer_frame = &layer.frames[frame];
+            let row_offset = (i32::from(layer.direction_box.row_min) - row_min) as usize;
+            let col_offset = (i32::from(layer.direction_box.col_min) - col_min) as usize;
+
+            for (row, pixels) in layer_frame
+                .data
+                .chunks(usize::from(layer_frame.dims.col).max(1))
+                .enumerate()
+            {
+                let start = (row_offset + row) * usize::from(composite_frame.dims.col) + col_offset;
+                let target = &mut composite_frame.data[start..start + pixels.len()];
+
+                for (target, &pixel) in target.iter_mut().zip(pixels) {
+                    if pixel != 0 {
+                        *target = pixel;
+                    }
+                }
+            }
+        }
+
+        CompositeFrame {
+            frame: composite_frame,
+            anchor: PointI32 {
+                row: -row_min,
+                col: -col_min,
+            },
+        }
+    }
+}
+
+impl CofLayer {
+    fn new(bytes: &[u8], byte_offset: usize) -> Result<Self, CofError> {
+        let composite = bytes[0];
+        if usize::from(composite) >= COMPOSITE_NAMES.len() {
+            return Err(CofError {
+                byte_offset,
+                reason: CofErrorReason::InvalidComposite(composite),
+            });
+        }
+
+        let weapon_class = bytes[5..9]
+            .iter()
+            .take_while(|&&byte| byte != 0)
+            .map(|&byte| char::from(byte))
+            .collect();
+
+        Ok(Self {
+            composite,
+            shadow: bytes[1] != 0,
+            selectable: bytes[2] != 0,
+            transparent: bytes[3] != 0,
+            draw_effect: bytes[4],
+            weapon_class,
+        })
+    }
+
+    pub fn composite_name(&self) -> &'static str {
+        COMPOSITE_NAMES[usize::from(self.composite)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DccBuilder, DccReader, IndexedFrame};
+
+    // 2 layers, 1 direction of 2 frames, the torso is drawn first in the second frame
+    fn cof_file() -> Vec<u8> {
+        let mut data = vec![2, 2, 1];
+        data.extend([0; 21]);
+        data.push(8);
+        data.extend([0; 3]);
+        data.extend([0, 1, 1, 0, 0, b'H', b'T', b'H', 0]);
+        data.extend([1, 1, 0, 1, 5, b'1', b'H', b'S', 0]);
+        data.extend([0, 1]);
+        data.extend([0, 1, 1, 0]);
+        data
+    }
+
+    fn layer(pixels: Vec<u8>, row_offset: i32) -> Vec<u8> {
+        let frame = IndexedFrame {
+            width: 2,
+            height: 2,
+            col_offset: 0,
+            row_offset,
+            pixels,
+            optional_bytes: Vec::new(),
+        };
+
+        DccBuilder::new()
+            .add_direction(vec![frame.clone(), frame])
+            .to_bytes()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_cof() {
+        let cof = Cof::try_new(&cof_file()).unwrap();
+
+        assert_eq!((cof.num_directions, cof.frames_per_direction), (1, 2));
+        assert_eq!(cof.speed, 8);
+        assert_eq!(cof.layers[0].composite_name(), "HD");
+        assert!(cof.layers[0].selectable && !cof.layers[0].transparent);
+        assert_eq!(cof.layers[1].composite_name(), "TR");
+        assert_eq!(cof.layers[1].weapon_class, "1HS");
+        assert!(cof.layers[1].transparent && !cof.layers[1].selectable);
+        assert_eq!(cof.frame_triggers, [0, 1]);
+        assert_eq!(cof.draw_order(0, 1), [1, 0]);
+
+        let data = cof_file();
+        for len in 0..data.len() {
+            let error = Cof::try_new(&data[..len]).unwrap_err();
+            assert_eq!(error.reason, CofErrorReason::UnexpectedEnd);
+        }
+
+        let mut data = cof_file();
+        data[28 + 9] = 16;
+        let error = Cof::try_new(&data).unwrap_err();
+        assert_eq!(error.byte_offset, 37);
+        assert_eq!(error.reason, CofErrorReason::InvalidComposite(16));
+    }
+
+    #[test]
+    fn test_compose_frame() {
+        let cof = Cof::try_new(&cof_file()).unwrap();
+
+        let head = layer(vec![1, 1, 0, 1], -1);
+        let torso = layer(vec![2, 2, 2, 0], 0);
+        let head = DccReader::try_new(&head)
+            .unwrap()
+            .decode_direction(0)
+            .unwrap();
+        let torso = DccReader::try_new(&torso)
+            .unwrap()
+            .decode_direction(0)
+            .unwrap();
+
+        let mut layers = [None; COMPOSITE_NAMES.len()];
+        layers[0] = Some(&head);
+        layers[1] = Some(&torso);
+
+        // the torso covers the head in the first frame
+        let composite_frame = cof.compose_frame(0, 0, &layers);
+        assert_eq!(composite_frame.anchor, PointI32 { row: 2, col: 0 });
+        assert_eq!(composite_frame.frame.data, [1, 1, 2, 2, 2, 0]);
+
+        let composite_frame = cof.compose_frame(0, 1, &layers);
+        assert_eq!(composite_frame.frame.data, [1, 1, 2, 1, 2, 0]);
+    }
+}
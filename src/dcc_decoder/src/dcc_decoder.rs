@@ -1,12 +1,19 @@
+use std::cell::RefCell;
+
 use crate::{
     bit_stream::BitStream,
     bit_streams::{BitStreamSizes, BitStreams},
-    frame::{Frame, PointU16},
+    error::{DccError, DccErrorReason},
+    frame::{Frame, PointI32, PointU16},
 };
 
-const FILE_SIGNATURE: u8 = 116;
+pub(crate) const FILE_SIGNATURE: u8 = 116;
+pub(crate) const FILE_TAG: u32 = 1;
+// Largest height and width of a direction, far larger than any sprite of the game
+const MAX_DIRECTION_SIZE: i32 = 2048;
 const DEFAULT_CELL_SIZE: u8 = 4;
-const BITS_WIDTH_TABLE: [u8; 16] = [0, 1, 2, 4, 6, 8, 10, 12, 14, 16, 20, 24, 26, 28, 30, 32];
+pub(crate) const BITS_WIDTH_TABLE: [u8; 16] =
+    [0, 1, 2, 4, 6, 8, 10, 12, 14, 16, 20, 24, 26, 28, 30, 32];
 const NUM_PIXEL_TABLE: [u8; 16] = [0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4];
 
 #[derive(Debug)]
@@ -14,6 +21,29 @@ pub struct Dcc {
     pub directions: Vec<Direction>,
 }
 
+/// Parses the header of a DCC file and decodes its directions on demand.
+///
+/// A frame can only be decoded together with the frames before it in its direction,
+/// as the cells which don't change are copied from the earlier frames. The frames of the
+/// last direction decoded by [`DccReader::decode_frame`] are kept, so reading the frames
+/// of a direction one by one decodes it only once.
+///
+/// ```no_run
+/// # let bytes = Vec::new();
+/// let dcc = dcc_decoder::DccReader::try_new(&bytes).unwrap();
+///
+/// for index in 0..dcc.num_directions() {
+///     let direction = dcc.decode_direction(index).unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DccReader<'dcc_file> {
+    data: &'dcc_file [u8],
+    header: DccHeader,
+    /// the index and the frames of the direction decoded last by `decode_frame`
+    decoded_frames: RefCell<Option<(usize, Vec<Frame>)>>,
+}
+
 #[derive(Debug, Clone)]
 struct PixelBufferEntry {
     pixels: [u8; 4],
@@ -29,7 +59,15 @@ struct DccHeader {
 
 #[derive(Debug)]
 pub struct Direction {
+    /// the frames composed into bitmaps of the size of the direction box
     pub frames: Vec<Frame>,
+    pub frame_headers: Vec<FrameHeader>,
+    /// the boxes of the frames relative to the anchor of the sprite
+    pub frame_boxes: Vec<DccBox>,
+    /// the box around all frames relative to the anchor of the sprite
+    pub direction_box: DccBox,
+    /// the optional bytes of each frame
+    pub optional_bytes: Vec<Vec<u8>>,
 }
 
 #[derive(Debug)]
@@ -44,23 +82,29 @@ struct DirectionHeader {
     coded_bytes_bits: u32,
 }
 
-#[derive(Debug)]
-struct FrameHeader {
-    height: u32,
-    width: u32,
-    row_offset: i32,
-    col_offset: i32,
-    optional_data: u32,
-    frame_buttom_up: bool,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub variable0: u32,
+    pub height: u32,
+    pub width: u32,
+    /// row of the bottom edge, or of the top edge for bottom up frames, relative to the anchor
+    pub row_offset: i32,
+    /// column of the left edge relative to the anchor
+    pub col_offset: i32,
+    /// number of optional bytes
+    pub optional_data: u32,
+    pub coded_bytes: u32,
+    pub frame_buttom_up: bool,
 }
 
-#[derive(Debug)]
-struct DccBox {
-    row_min: i16,
-    col_min: i16,
-    row_max: i16,
-    col_max: i16,
-    dims: PointU16,
+/// A box relative to the anchor of the sprite, the anchor is usually at the feet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DccBox {
+    pub row_min: i16,
+    pub col_min: i16,
+    pub row_max: i16,
+    pub col_max: i16,
+    pub dims: PointU16,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -92,88 +136,168 @@ impl Cell {
 }
 
 #[derive(Debug)]
-struct CellMatrix {
-    num_vertical_cells: u16,
-    num_horizontal_cells: u16,
-    cells: Vec<Cell>,
+pub(crate) struct CellMatrix {
+    pub(crate) num_vertical_cells: u16,
+    pub(crate) num_horizontal_cells: u16,
+    pub(crate) cells: Vec<Cell>,
 }
 
 impl<'dcc_file> Dcc {
+    /// Decodes a DCC file, panics if the file is malformed
     pub fn new(data: &'dcc_file [u8]) -> Self {
-        let mut bs = BitStream::new(data);
+        Self::try_new(data).unwrap_or_else(|e| panic!("Invalid dcc file: {e}"))
+    }
 
-        let header = DccHeader::load(&mut bs);
-        let directions = Self::load_directions(&mut bs, &header);
+    pub fn try_new(data: &'dcc_file [u8]) -> Result<Self, DccError> {
+        let reader = DccReader::try_new(data)?;
 
-        Self { directions }
+        let directions = (0..reader.num_directions())
+            .map(|index| reader.decode_direction(index))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { directions })
     }
+}
 
-    fn load_directions(bs: &mut BitStream<'dcc_file>, header: &DccHeader) -> Vec<Direction> {
-        let mut directions = Vec::new();
+impl<'dcc_file> DccReader<'dcc_file> {
+    /// Parses the header and the offsets of the directions, no direction is decoded
+    pub fn try_new(data: &'dcc_file [u8]) -> Result<Self, DccError> {
+        let mut bs = BitStream::new(data);
 
-        for dir_offset in &header.dir_offsets {
-            let mut direction_bit_stream = BitStream::new(&bs.bytes[*dir_offset as usize..]);
+        let header = DccHeader::load(&mut bs)?;
 
-            let direction =
-                Direction::load(&mut direction_bit_stream, header.num_frames_per_direction);
-            directions.push(direction);
+        for dir_offset in &header.dir_offsets {
+            if *dir_offset as usize >= data.len() {
+                return Err(bs.error(DccErrorReason::DirectionOffsetOutOfBounds(*dir_offset)));
+            }
         }
 
-        directions
+        Ok(Self {
+            data,
+            header,
+            decoded_frames: RefCell::new(None),
+        })
+    }
+
+    pub fn num_directions(&self) -> usize {
+        self.header.dir_offsets.len()
+    }
+
+    pub fn num_frames_per_direction(&self) -> usize {
+        self.header.num_frames_per_direction as usize
+    }
+
+    /// Decodes all frames of a direction, panics if the index is out of bounds
+    pub fn decode_direction(&self, index: usize) -> Result<Direction, DccError> {
+        Direction::load(
+            &mut self.direction_bit_stream(index),
+            self.header.num_frames_per_direction,
+        )
+    }
+
+    /// Returns a single frame of a direction, the direction is decoded when it isn't the
+    /// one of the previous call. Panics if an index is out of bounds.
+    pub fn decode_frame(&self, direction: usize, frame: usize) -> Result<Frame, DccError> {
+        assert!(
+            frame < self.num_frames_per_direction(),
+            "frame out of bounds"
+        );
+
+        let mut decoded_frames = self.decoded_frames.borrow_mut();
+
+        let frames = match decoded_frames.take() {
+            Some((index, frames)) if index == direction => frames,
+            _ => self.decode_direction(direction)?.frames,
+        };
+
+        Ok(decoded_frames.insert((direction, frames)).1[frame].clone())
+    }
+
+    fn direction_bit_stream(&self, index: usize) -> BitStream<'dcc_file> {
+        BitStream::new_at(self.data, self.header.dir_offsets[index] as usize, 0)
     }
 }
 
 impl DccHeader {
-    fn load(bs: &mut BitStream) -> Self {
-        let file_signature = bs.stream_alligned_byte();
+    fn load(bs: &mut BitStream) -> Result<Self, DccError> {
+        let file_signature = bs.stream_alligned_byte()?;
 
         if file_signature != FILE_SIGNATURE {
-            panic!("Incorrect signature");
+            return Err(DccError::new(
+                0,
+                0,
+                DccErrorReason::IncorrectSignature(file_signature),
+            ));
         }
 
-        let _version = bs.stream_alligned_byte();
+        let _version = bs.stream_alligned_byte()?;
+
+        let num_directions = bs.stream_alligned_byte()?;
+        let num_frames_per_direction = bs.stream_alligned_uint()? as u8;
 
-        let num_directions = bs.stream_alligned_byte();
-        let num_frames_per_direction = bs.stream_alligned_uint() as u8;
-        let tag = bs.stream_alligned_uint();
+        let tag_offset = bs.byte_offset;
+        let tag = bs.stream_alligned_uint()?;
 
-        let _total_size = bs.stream_alligned_uint();
+        if tag != FILE_TAG {
+            return Err(DccError::new(
+                tag_offset,
+                0,
+                DccErrorReason::UnsupportedTag(tag),
+            ));
+        }
 
-        assert_eq!(tag, 1);
+        let _total_size = bs.stream_alligned_uint()?;
 
         let dir_offsets: Vec<u32> = (0..num_directions)
             .map(|_| bs.stream_alligned_uint())
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        Self {
+        Ok(Self {
             num_frames_per_direction,
             dir_offsets,
-        }
+        })
     }
 }
 
 impl Direction {
-    fn load(bs: &mut BitStream<'_>, num_frames_per_direction: u8) -> Self {
-        let direction_header = DirectionHeader::load(bs);
+    /// Returns the position of the anchor of the sprite in the frame bitmaps, it may lie
+    /// outside of the bitmaps
+    pub fn anchor(&self) -> PointI32 {
+        PointI32 {
+            row: -i32::from(self.direction_box.row_min),
+            col: -i32::from(self.direction_box.col_min),
+        }
+    }
 
-        let frame_headers: Vec<FrameHeader> = (0..num_frames_per_direction)
-            .map(|_| FrameHeader::load(bs, &direction_header))
-            .collect();
-        let frame_boxes: Vec<DccBox> = frame_headers.iter().map(get_frame_box).collect();
+    fn load(bs: &mut BitStream<'_>, num_frames_per_direction: u8) -> Result<Self, DccError> {
+        let direction_header = DirectionHeader::load(bs)?;
+
+        let mut frame_headers = Vec::with_capacity(num_frames_per_direction as usize);
+        let mut frame_boxes = Vec::with_capacity(num_frames_per_direction as usize);
+
+        for frame in 0..num_frames_per_direction as usize {
+            let frame_error = bs.error(DccErrorReason::FrameOutOfBounds { frame });
+            let frame_header = FrameHeader::load(bs, &direction_header)?;
+
+            frame_boxes.push(get_frame_box(&frame_header).ok_or(frame_error)?);
+            frame_headers.push(frame_header);
+        }
+
+        let optional_bytes = Self::get_optional_bytes(bs, &frame_headers)?;
 
-        let _optional_bytes = Self::get_optional_bytes(bs, &frame_headers);
+        let direction_box =
+            Self::get_direction_box(&frame_boxes).map_err(|reason| bs.error(reason))?;
 
-        let direction_box = Self::get_direction_box(&frame_boxes);
-        let bit_stream_sizes = BitStreamSizes::load(bs, direction_header.compression_flag);
-        let pixel_values = Self::get_dcc_pixel_values_key(bs);
-        let mut bit_streams = BitStreams::load(bs, &bit_stream_sizes, bs.bytes);
+        let bit_stream_sizes = BitStreamSizes::load(bs, direction_header.compression_flag)?;
+        let pixel_values = Self::get_dcc_pixel_values_key(bs)?;
+        let mut bit_streams = BitStreams::load(bs, &bit_stream_sizes);
 
         let (pixel_buffer_entries, mut buffer_cells, mut frames_cells) = Self::get_pixel_buffer(
             &mut bit_streams,
             &direction_box,
             &frame_boxes,
             &pixel_values,
-        );
+        )?;
 
         let frame_bitmaps = Self::make_frames(
             &pixel_buffer_entries,
@@ -181,11 +305,15 @@ impl Direction {
             &mut frames_cells,
             &direction_box,
             &mut bit_streams.pixel_code_and_displacment,
-        );
+        )?;
 
-        Self {
+        Ok(Self {
             frames: frame_bitmaps,
-        }
+            frame_headers,
+            frame_boxes,
+            direction_box,
+            optional_bytes,
+        })
     }
 
     fn make_frames(
@@ -194,13 +322,16 @@ impl Direction {
         frames_cells: &mut [CellMatrix],
         dir_box: &DccBox,
         pixel_index_bitstream: &mut BitStream,
-    ) -> Vec<Frame> {
+    ) -> Result<Vec<Frame>, DccError> {
         let mut frame_bitmaps = Vec::with_capacity(frames_cells.len());
 
         let mut dir_bitmap = Frame::new(dir_box.dims);
 
         let mut pbe_iter = pixel_buffer_entries.iter();
-        let mut pbe = pbe_iter.next().unwrap().clone();
+        let mut pbe = pbe_iter
+            .next()
+            .ok_or_else(|| pixel_index_bitstream.error(DccErrorReason::InvalidCell))?
+            .clone();
 
         for (f, frame_cells) in frames_cells.iter().enumerate() {
             let mut frame_bitmap = Frame::new(dir_bitmap.dims);
@@ -209,7 +340,10 @@ impl Direction {
                 let cell_col = frame_cell.offset.col / 4;
 
                 let cell_idx = ((cell_row * buffer_cells.num_horizontal_cells) + cell_col) as usize;
-                let buff_cell = buffer_cells.cells.get_mut(cell_idx).unwrap();
+                let buff_cell = buffer_cells
+                    .cells
+                    .get_mut(cell_idx)
+                    .ok_or_else(|| pixel_index_bitstream.error(DccErrorReason::InvalidCell))?;
 
                 if (pbe.frame_idx == f as u16) && (pbe.frame_cell_idx == c as u16) {
                     if pbe.pixels[0] == pbe.pixels[1] {
@@ -219,9 +353,10 @@ impl Direction {
                             if pbe.pixels[1] == pbe.pixels[2] { 1 } else { 2 };
 
                         dir_bitmap.set_cell(*frame_cell, || {
-                            pbe.pixels[pixel_index_bitstream.stream_byte(num_bits_per_pixel_index)
-                                as usize]
-                        });
+                            let pixel_index =
+                                pixel_index_bitstream.stream_byte(num_bits_per_pixel_index)?;
+                            Ok::<_, DccError>(pbe.pixels[pixel_index as usize])
+                        })?;
                     }
 
                     dir_bitmap.copy_cell_external(&mut frame_bitmap, *frame_cell);
@@ -246,7 +381,7 @@ impl Direction {
             frame_bitmaps.push(frame_bitmap);
         }
 
-        frame_bitmaps
+        Ok(frame_bitmaps)
     }
 
     fn get_pixel_buffer(
@@ -254,7 +389,7 @@ impl Direction {
         direction_box: &DccBox,
         frame_boxes: &[DccBox],
         pixel_values: &[u8; 256],
-    ) -> (Vec<PixelBufferEntry>, CellMatrix, Vec<CellMatrix>) {
+    ) -> Result<(Vec<PixelBufferEntry>, CellMatrix, Vec<CellMatrix>), DccError> {
         let mut pixel_buffer_entries: Vec<PixelBufferEntry> = Vec::new();
 
         let buffer_cells = Self::get_buffer_cells(direction_box);
@@ -275,33 +410,42 @@ impl Direction {
 
             frames_cells.push(frame_cells);
 
-            let cell_row_offset = (frame_box.row_min - direction_box.row_min) / 4;
-            let cell_col_offset = (frame_box.col_min - direction_box.col_min) / 4;
+            let cell_offset = |frame_min: i16, direction_min: i16| {
+                usize::try_from((i32::from(frame_min) - i32::from(direction_min)) / 4).ok()
+            };
+            let cell_row_offset = cell_offset(frame_box.row_min, direction_box.row_min);
+            let cell_col_offset = cell_offset(frame_box.col_min, direction_box.col_min);
+            let buffer_width = usize::from(buffer_cells.num_horizontal_cells);
 
             for row in 0..num_vertical_cells {
-                let current_row_cell = cell_row_offset + row as i16;
                 for col in 0..num_horizontal_cells {
-                    let current_col_cell = cell_col_offset + col as i16;
-                    let current_cell_id = current_col_cell
-                        + (current_row_cell * buffer_cells.num_horizontal_cells as i16);
-
-                    let old_entry_id = cell_buffer[current_cell_id as usize];
+                    let current_col_cell = cell_col_offset
+                        .and_then(|offset| offset.checked_add(usize::from(col)))
+                        .filter(|&col_cell| col_cell < buffer_width);
+                    let current_cell_id = cell_row_offset
+                        .and_then(|offset| offset.checked_add(usize::from(row)))
+                        .and_then(|row_cell| row_cell.checked_mul(buffer_width))
+                        .zip(current_col_cell)
+                        .and_then(|(row_start, col_cell)| row_start.checked_add(col_cell));
+
+                    let Some((current_cell_id, &old_entry_id)) = current_cell_id
+                        .and_then(|id| cell_buffer.get(id).map(|entry_id| (id, entry_id)))
+                    else {
+                        return Err(bit_streams.pixel_mask.error(DccErrorReason::InvalidCell));
+                    };
 
                     if old_entry_id != -1 {
                         let next_cell = match &mut bit_streams.equal_cell {
-                            Some(bs) => match bs.stream_bit() {
-                                true => true,
-                                false => false,
-                            },
+                            Some(bs) => bs.stream_bit()?,
                             None => false,
                         };
 
                         if !next_cell {
-                            let pixel_mask = bit_streams.pixel_mask.stream_byte(4);
+                            let pixel_mask = bit_streams.pixel_mask.stream_byte(4)?;
                             let num_pixels = NUM_PIXEL_TABLE[pixel_mask as usize];
 
                             let (pixel_indices, num_decoded_pixels) =
-                                stream_pixel_indices(num_pixels, bit_streams);
+                                stream_pixel_indices(num_pixels, bit_streams)?;
 
                             let mut curr_idx = num_decoded_pixels - 1;
 
@@ -318,8 +462,7 @@ impl Direction {
                                 }
                             }
 
-                            cell_buffer[current_cell_id as usize] =
-                                pixel_buffer_entries.len() as i32;
+                            cell_buffer[current_cell_id] = pixel_buffer_entries.len() as i32;
                             pixel_buffer_entries.push(PixelBufferEntry {
                                 pixels: val,
                                 frame_idx: f as u16,
@@ -328,7 +471,7 @@ impl Direction {
                         }
                     } else {
                         let (pixel_indices, num_decoded_pixels) =
-                            stream_pixel_indices(4, bit_streams);
+                            stream_pixel_indices(4, bit_streams)?;
 
                         let mut curr_idx = num_decoded_pixels - 1;
 
@@ -341,7 +484,7 @@ impl Direction {
                             }
                         }
 
-                        cell_buffer[current_cell_id as usize] = pixel_buffer_entries.len() as i32;
+                        cell_buffer[current_cell_id] = pixel_buffer_entries.len() as i32;
                         pixel_buffer_entries.push(PixelBufferEntry {
                             pixels: val,
                             frame_idx: f as u16,
@@ -359,10 +502,10 @@ impl Direction {
             }
         }
 
-        (pixel_buffer_entries, buffer_cells, frames_cells)
+        Ok((pixel_buffer_entries, buffer_cells, frames_cells))
     }
 
-    fn get_frame_cells(dir_box: &DccBox, frame_box: &DccBox) -> CellMatrix {
+    pub(crate) fn get_frame_cells(dir_box: &DccBox, frame_box: &DccBox) -> CellMatrix {
         let cells_height =
             Self::get_frame_cell_sizes(frame_box.dims.row, frame_box.row_min, dir_box.row_min);
         let cells_width =
@@ -423,14 +566,14 @@ impl Direction {
         } else {
             let tmp = frame_size - u16::from(first_dimension_cell_pixel_size) - 1;
             let mut num_frame_cells = 2 + (tmp / u16::from(DEFAULT_CELL_SIZE));
-            if tmp % u16::from(DEFAULT_CELL_SIZE) == 0 {
+            if tmp.is_multiple_of(u16::from(DEFAULT_CELL_SIZE)) {
                 num_frame_cells -= 1;
             }
             num_frame_cells
         }
     }
 
-    fn get_buffer_cells(direction_box: &DccBox) -> CellMatrix {
+    pub(crate) fn get_buffer_cells(direction_box: &DccBox) -> CellMatrix {
         let num_vertical_cells =
             1 + ((direction_box.dims.row as i16 - 1) / i16::from(DEFAULT_CELL_SIZE)) as u16;
         let num_horizontal_cells =
@@ -451,22 +594,25 @@ impl Direction {
         }
     }
 
-    fn get_dcc_pixel_values_key(bs: &mut BitStream) -> [u8; 256] {
+    fn get_dcc_pixel_values_key(bs: &mut BitStream) -> Result<[u8; 256], DccError> {
         let mut pixel_values = [0; 256];
 
         let mut c = 0;
         for i in 0..256 {
-            let has_pixel = bs.stream_bit();
+            let has_pixel = bs.stream_bit()?;
             if has_pixel {
                 pixel_values[c] = i as u8;
                 c += 1;
             }
         }
 
-        pixel_values
+        Ok(pixel_values)
     }
 
-    fn get_optional_bytes(bs: &mut BitStream, frame_headers: &Vec<FrameHeader>) -> Vec<Vec<u8>> {
+    fn get_optional_bytes(
+        bs: &mut BitStream,
+        frame_headers: &Vec<FrameHeader>,
+    ) -> Result<Vec<Vec<u8>>, DccError> {
         let mut has_optional_data = false;
         for frame_header in frame_headers {
             if frame_header.optional_data > 0 {
@@ -476,7 +622,7 @@ impl Direction {
         }
 
         if !has_optional_data {
-            return Vec::new();
+            return Ok(vec![Vec::new(); frame_headers.len()]);
         }
 
         if bs.bit_offset != 0 {
@@ -490,12 +636,10 @@ impl Direction {
             .collect()
     }
 
-    fn get_direction_box(frame_boxes: &[DccBox]) -> DccBox {
-        let row_min = frame_boxes
-            .iter()
-            .map(|frame_box| frame_box.row_min)
-            .min()
-            .unwrap();
+    pub(crate) fn get_direction_box(frame_boxes: &[DccBox]) -> Result<DccBox, DccErrorReason> {
+        let Some(row_min) = frame_boxes.iter().map(|frame_box| frame_box.row_min).min() else {
+            return Err(DccErrorReason::NoFrames);
+        };
         let col_min = frame_boxes
             .iter()
             .map(|frame_box| frame_box.col_min)
@@ -512,59 +656,80 @@ impl Direction {
             .max()
             .unwrap();
 
-        let height = (row_max - row_min + 1) as u16;
-        let width = (col_max - col_min + 1) as u16;
+        let height = i32::from(row_max) - i32::from(row_min) + 1;
+        let width = i32::from(col_max) - i32::from(col_min) + 1;
+
+        if height > MAX_DIRECTION_SIZE || width > MAX_DIRECTION_SIZE {
+            let frame = frame_boxes
+                .iter()
+                .position(|frame_box| {
+                    i32::from(frame_box.row_max) - i32::from(row_min) >= MAX_DIRECTION_SIZE
+                        || i32::from(frame_box.col_max) - i32::from(col_min) >= MAX_DIRECTION_SIZE
+                })
+                .unwrap_or(0);
+
+            return Err(DccErrorReason::FrameOutOfBounds { frame });
+        }
+
         let dims = PointU16 {
-            row: height,
-            col: width,
+            row: height as u16,
+            col: width as u16,
         };
 
-        DccBox {
+        Ok(DccBox {
             row_min,
             col_min,
             row_max,
             col_max,
             dims,
-        }
+        })
     }
 }
 
-fn stream_pixel_indices(num_pixels: u8, bit_streams: &mut BitStreams) -> ([u8; 4], i32) {
+fn stream_pixel_indices(
+    num_pixels: u8,
+    bit_streams: &mut BitStreams,
+) -> Result<([u8; 4], i32), DccError> {
     let (pixel_indices, num_decoded_pixels) = if num_pixels != 0 {
         match &mut bit_streams.encoding_type_raw_pixel {
             Some(encoding_type_raw_pixel_bitstream) => {
-                let is_raw_encoding = encoding_type_raw_pixel_bitstream.encoding_type.stream_bit();
+                let is_raw_encoding = encoding_type_raw_pixel_bitstream
+                    .encoding_type
+                    .stream_bit()?;
                 if is_raw_encoding {
                     stream_pixels_indices_raw_encoding(
                         num_pixels,
                         &mut encoding_type_raw_pixel_bitstream.raw_pixel,
-                    )
+                    )?
                 } else {
                     stream_pixels_indices_encoded(
                         num_pixels,
                         &mut bit_streams.pixel_code_and_displacment,
-                    )
+                    )?
                 }
             }
             None => stream_pixels_indices_encoded(
                 num_pixels,
                 &mut bit_streams.pixel_code_and_displacment,
-            ),
+            )?,
         }
     } else {
         ([0; 4], 0)
     };
-    (pixel_indices, num_decoded_pixels)
+    Ok((pixel_indices, num_decoded_pixels))
 }
 
-fn stream_pixels_indices_raw_encoding(num_pixels: u8, bs: &mut BitStream) -> ([u8; 4], i32) {
-    let mut indices = [0; 4];
+fn stream_pixels_indices_raw_encoding(
+    num_pixels: u8,
+    bs: &mut BitStream,
+) -> Result<([u8; 4], i32), DccError> {
+    let mut indices: [u8; 4] = [0; 4];
 
     let mut last_idx = 0;
     let mut c = 0;
 
     for idx in indices.iter_mut().take(num_pixels as usize) {
-        *idx = bs.stream_byte(8);
+        *idx = bs.stream_byte(8)?;
 
         if *idx == last_idx {
             *idx = 0;
@@ -575,11 +740,14 @@ fn stream_pixels_indices_raw_encoding(num_pixels: u8, bs: &mut BitStream) -> ([u
         }
     }
 
-    (indices, c)
+    Ok((indices, c))
 }
 
-fn stream_pixels_indices_encoded(num_pixels: u8, bs: &mut BitStream) -> ([u8; 4], i32) {
-    let mut indices = [0; 4];
+fn stream_pixels_indices_encoded(
+    num_pixels: u8,
+    bs: &mut BitStream,
+) -> Result<([u8; 4], i32), DccError> {
+    let mut indices: [u8; 4] = [0; 4];
 
     let mut last_idx = 0;
     let mut c = 0;
@@ -587,12 +755,12 @@ fn stream_pixels_indices_encoded(num_pixels: u8, bs: &mut BitStream) -> ([u8; 4]
     for idx in indices.iter_mut().take(num_pixels as usize) {
         *idx = last_idx;
 
-        let mut displ = bs.stream_byte(4);
-        *idx += displ;
+        let mut displ = bs.stream_byte(4)?;
+        *idx = idx.wrapping_add(displ);
 
         while displ == 15 {
-            displ = bs.stream_byte(4);
-            *idx += displ;
+            displ = bs.stream_byte(4)?;
+            *idx = idx.wrapping_add(displ);
         }
 
         if *idx == last_idx {
@@ -605,23 +773,23 @@ fn stream_pixels_indices_encoded(num_pixels: u8, bs: &mut BitStream) -> ([u8; 4]
         }
     }
 
-    (indices, c)
+    Ok((indices, c))
 }
 
 impl DirectionHeader {
-    fn load(bs: &mut BitStream) -> Self {
-        let _outsize_coded = bs.stream_bits(32);
-
-        let compression_flag = u32::from(bs.stream_byte(2));
-        let variable0_bits = u32::from(bs.stream_byte(4));
-        let width_bits = u32::from(bs.stream_byte(4));
-        let height_bits = u32::from(bs.stream_byte(4));
-        let col_offset_bits = u32::from(bs.stream_byte(4));
-        let row_offset_bits = u32::from(bs.stream_byte(4));
-        let optional_data_bits = u32::from(bs.stream_byte(4));
-        let coded_bytes_bits = u32::from(bs.stream_byte(4));
-
-        Self {
+    fn load(bs: &mut BitStream) -> Result<Self, DccError> {
+        let _outsize_coded = bs.stream_bits(32)?;
+
+        let compression_flag = u32::from(bs.stream_byte(2)?);
+        let variable0_bits = u32::from(bs.stream_byte(4)?);
+        let width_bits = u32::from(bs.stream_byte(4)?);
+        let height_bits = u32::from(bs.stream_byte(4)?);
+        let col_offset_bits = u32::from(bs.stream_byte(4)?);
+        let row_offset_bits = u32::from(bs.stream_byte(4)?);
+        let optional_data_bits = u32::from(bs.stream_byte(4)?);
+        let coded_bytes_bits = u32::from(bs.stream_byte(4)?);
+
+        Ok(Self {
             compression_flag,
             variable0_bits,
             width_bits,
@@ -630,76 +798,299 @@ impl DirectionHeader {
             row_offset_bits,
             optional_data_bits,
             coded_bytes_bits,
-        }
+        })
     }
 }
 
-fn get_optional_bytes(bs: &mut BitStream, header: &FrameHeader) -> Vec<u8> {
+fn get_optional_bytes(bs: &mut BitStream, header: &FrameHeader) -> Result<Vec<u8>, DccError> {
     let mut optional_bytes = Vec::new();
 
     for _ in 0..header.optional_data {
-        let byte = bs.stream_alligned_byte();
+        let byte = bs.stream_alligned_byte()?;
         optional_bytes.push(byte);
     }
 
-    optional_bytes
+    Ok(optional_bytes)
 }
 
-fn get_frame_box(header: &FrameHeader) -> DccBox {
+/// Returns `None` if the frame doesn't fit in a direction box of the maximal size
+pub(crate) fn get_frame_box(header: &FrameHeader) -> Option<DccBox> {
+    let height = i32::try_from(header.height).ok()?;
+    let width = i32::try_from(header.width).ok()?;
+
+    if height > MAX_DIRECTION_SIZE || width > MAX_DIRECTION_SIZE {
+        return None;
+    }
+
     let (row_min, row_max) = if header.frame_buttom_up {
-        let row_min = header.row_offset as i16;
-        let row_max = row_min + (header.height as i16) - 1;
+        let row_min = header.row_offset;
+        let row_max = row_min.checked_add(height)? - 1;
         (row_min, row_max)
     } else {
-        let row_max = header.row_offset as i16;
-        let row_min = row_max - (header.height as i16) + 1;
+        let row_max = header.row_offset;
+        let row_min = row_max.checked_sub(height)? + 1;
         (row_min, row_max)
     };
 
-    let col_min = header.col_offset as i16;
-    let col_max = col_min + (header.width as i16) - 1;
+    let col_min = header.col_offset;
+    let col_max = col_min.checked_add(width)? - 1;
 
-    let height = (row_max - row_min + 1) as u16;
-    let width = (col_max - col_min + 1) as u16;
     let dims = PointU16 {
-        row: height,
-        col: width,
+        row: height as u16,
+        col: width as u16,
     };
 
-    DccBox {
-        row_min,
-        col_min,
-        row_max,
-        col_max,
+    Some(DccBox {
+        row_min: i16::try_from(row_min).ok()?,
+        col_min: i16::try_from(col_min).ok()?,
+        row_max: i16::try_from(row_max).ok()?,
+        col_max: i16::try_from(col_max).ok()?,
         dims,
-    }
+    })
 }
 
 impl FrameHeader {
-    fn load(bs: &mut BitStream, direction_header: &DirectionHeader) -> Self {
-        let _variable0 = bs.stream_bits(BITS_WIDTH_TABLE[direction_header.variable0_bits as usize]);
+    fn load(bs: &mut BitStream, direction_header: &DirectionHeader) -> Result<Self, DccError> {
+        let variable0 =
+            bs.stream_bits(BITS_WIDTH_TABLE[direction_header.variable0_bits as usize])?;
 
-        let width = bs.stream_bits(BITS_WIDTH_TABLE[direction_header.width_bits as usize]);
-        let height = bs.stream_bits(BITS_WIDTH_TABLE[direction_header.height_bits as usize]);
+        let width = bs.stream_bits(BITS_WIDTH_TABLE[direction_header.width_bits as usize])?;
+        let height = bs.stream_bits(BITS_WIDTH_TABLE[direction_header.height_bits as usize])?;
         let col_offset =
-            bs.stream_signed_bits(BITS_WIDTH_TABLE[direction_header.col_offset_bits as usize]);
+            bs.stream_signed_bits(BITS_WIDTH_TABLE[direction_header.col_offset_bits as usize])?;
         let row_offset =
-            bs.stream_signed_bits(BITS_WIDTH_TABLE[direction_header.row_offset_bits as usize]);
+            bs.stream_signed_bits(BITS_WIDTH_TABLE[direction_header.row_offset_bits as usize])?;
         let optional_data =
-            bs.stream_bits(BITS_WIDTH_TABLE[direction_header.optional_data_bits as usize]);
+            bs.stream_bits(BITS_WIDTH_TABLE[direction_header.optional_data_bits as usize])?;
 
-        let _coded_bytes =
-            bs.stream_bits(BITS_WIDTH_TABLE[direction_header.coded_bytes_bits as usize]);
+        let coded_bytes =
+            bs.stream_bits(BITS_WIDTH_TABLE[direction_header.coded_bytes_bits as usize])?;
 
-        let frame_buttom_up = bs.stream_bit();
+        let frame_buttom_up = bs.stream_bit()?;
 
-        Self {
+        Ok(Self {
+            variable0,
             height,
             width,
             row_offset,
             col_offset,
             optional_data,
+            coded_bytes,
             frame_buttom_up,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DccBuilder, IndexedFrame};
+
+    fn header(tag: u32, dir_offset: u32) -> Vec<u8> {
+        let mut data = vec![FILE_SIGNATURE, 6, 1];
+        data.extend(1_u32.to_le_bytes());
+        data.extend(tag.to_le_bytes());
+        data.extend(0_u32.to_le_bytes());
+        data.extend(dir_offset.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_malformed_header() {
+        let error = Dcc::try_new(&[0; 32]).unwrap_err();
+        assert_eq!(error.reason, DccErrorReason::IncorrectSignature(0));
+
+        let error = Dcc::try_new(&header(2, 19)).unwrap_err();
+        assert_eq!((error.byte_offset, error.bit_offset), (7, 0));
+        assert_eq!(error.reason, DccErrorReason::UnsupportedTag(2));
+
+        let error = Dcc::try_new(&header(1, 19)[..17]).unwrap_err();
+        assert_eq!(error.reason, DccErrorReason::UnexpectedEnd);
+
+        let error = Dcc::try_new(&header(1, 100)).unwrap_err();
+        assert_eq!(
+            error.reason,
+            DccErrorReason::DirectionOffsetOutOfBounds(100)
+        );
+    }
+
+    #[test]
+    fn test_decode_on_demand() {
+        let frames: Vec<IndexedFrame> = (0..4)
+            .map(|i| IndexedFrame {
+                width: 5 + i,
+                height: 6,
+                col_offset: -(i as i32),
+                row_offset: i as i32,
+                pixels: (0..(5 + i) * 6)
+                    .map(|p| [0, 7, 9][(p % 3) as usize])
+                    .collect(),
+                optional_bytes: Vec::new(),
+            })
+            .collect();
+
+        let bytes = DccBuilder::new()
+            .add_direction(frames.clone())
+            .add_direction(frames[1..].iter().chain(&frames[..1]).cloned().collect())
+            .to_bytes()
+            .unwrap();
+
+        let reader = DccReader::try_new(&bytes).unwrap();
+        assert_eq!(reader.num_directions(), 2);
+        assert_eq!(reader.num_frames_per_direction(), 4);
+
+        let dcc = Dcc::try_new(&bytes).unwrap();
+
+        for (d, direction) in dcc.directions.iter().enumerate() {
+            assert_eq!(
+                reader.decode_direction(d).unwrap().frames[3].data,
+                direction.frames[3].data
+            );
+
+            for (f, frame) in direction.frames.iter().enumerate() {
+                assert_eq!(reader.decode_frame(d, f).unwrap().data, frame.data);
+            }
         }
+
+        // the frames of the last direction are kept until another direction is read
+        let decoded_direction = || reader.decoded_frames.borrow().as_ref().map(|(d, _)| *d);
+        assert_eq!(decoded_direction(), Some(1));
+        assert_eq!(
+            reader.decode_frame(0, 2).unwrap().data,
+            dcc.directions[0].frames[2].data
+        );
+        assert_eq!(decoded_direction(), Some(0));
+    }
+
+    #[test]
+    fn test_frame_geometry() {
+        let frames = vec![
+            IndexedFrame {
+                width: 4,
+                height: 3,
+                col_offset: -2,
+                row_offset: 0,
+                pixels: vec![5; 12],
+                optional_bytes: Vec::new(),
+            },
+            IndexedFrame {
+                width: 2,
+                height: 2,
+                col_offset: 1,
+                row_offset: 5,
+                pixels: vec![6; 4],
+                optional_bytes: vec![1, 2],
+            },
+        ];
+
+        let bytes = DccBuilder::new().add_direction(frames).to_bytes().unwrap();
+        let direction = DccReader::try_new(&bytes)
+            .unwrap()
+            .decode_direction(0)
+            .unwrap();
+
+        assert_eq!(direction.frame_headers[1].width, 2);
+        assert_eq!(direction.frame_headers[1].row_offset, 5);
+        assert_eq!(direction.optional_bytes, vec![vec![], vec![1, 2]]);
+
+        assert_eq!(direction.frame_boxes[0].row_min, -2);
+        assert_eq!(direction.frame_boxes[1].col_max, 2);
+        assert_eq!(
+            direction.direction_box,
+            DccBox {
+                row_min: -2,
+                col_min: -2,
+                row_max: 5,
+                col_max: 2,
+                dims: PointU16 { row: 8, col: 5 },
+            }
+        );
+
+        // the first frame covers the anchor
+        let anchor = direction.anchor();
+        assert_eq!(anchor, PointI32 { row: 2, col: 2 });
+        let frame = &direction.frames[0];
+        let index = anchor.row as usize * usize::from(frame.dims.col) + anchor.col as usize;
+        assert_eq!(frame.data[index], 5);
+    }
+
+    #[test]
+    fn test_random_directions_do_not_panic() {
+        let mut seed: u32 = 1;
+        let mut random_byte = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as u8
+        };
+
+        for len in 0..400 {
+            let mut data = header(1, 19);
+            data.extend((0..len).map(|_| random_byte()));
+
+            let _ = Dcc::try_new(&data);
+        }
+    }
+
+    fn decode_all(bytes: &[u8]) {
+        let _ = Dcc::try_new(bytes);
+
+        if let Ok(reader) = DccReader::try_new(bytes) {
+            for d in 0..reader.num_directions() {
+                for f in 0..reader.num_frames_per_direction() {
+                    let _ = reader.decode_frame(d, f);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mutated_directions_do_not_panic() {
+        let frames: Vec<IndexedFrame> = (0..3)
+            .map(|i| IndexedFrame {
+                width: 6 + 3 * i,
+                height: 5 + i,
+                col_offset: -(2 * i as i32),
+                row_offset: 3 * i as i32,
+                pixels: (0..(6 + 3 * i) * (5 + i))
+                    .map(|p| [0, 3, 3, 8, 200][(p % 5) as usize])
+                    .collect(),
+                optional_bytes: Vec::new(),
+            })
+            .collect();
+
+        let bytes = DccBuilder::new()
+            .add_direction(frames.clone())
+            .add_direction(frames.into_iter().rev().collect())
+            .to_bytes()
+            .unwrap();
+
+        for len in 0..bytes.len() {
+            decode_all(&bytes[..len]);
+        }
+
+        for i in 0..bytes.len() {
+            for mask in [0x01, 0x10, 0x80, 0xff] {
+                let mut mutated = bytes.clone();
+                mutated[i] ^= mask;
+                decode_all(&mutated);
+            }
+        }
+    }
+
+    #[test]
+    fn test_too_many_bits_reports_the_limit() {
+        let mut bs = BitStream::new(&[0; 8]);
+
+        let error = bs.stream_byte(9).unwrap_err();
+        assert_eq!(
+            error.reason,
+            DccErrorReason::TooManyBits {
+                num_bits: 9,
+                max_bits: 8
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "can't read 9 bits, at most 8 bits can be read at byte 0, bit 0"
+        );
     }
 }
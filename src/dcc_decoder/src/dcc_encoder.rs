@@ -0,0 +1,670 @@
+use crate::{
+    bit_writer::BitWriter,
+    dcc_decoder::{
+        get_frame_box, Cell, DccBox, Direction, FrameHeader, BITS_WIDTH_TABLE, FILE_SIGNATURE,
+        FILE_TAG,
+    },
+    error::{DccEncodeError, DccErrorReason},
+    frame::{Frame, PointU16},
+};
+
+const FILE_VERSION: u8 = 6;
+// The sizes of the streams of a direction are stored in 20 bits
+const MAX_STREAM_SIZE: usize = (1 << 20) - 1;
+
+/// A palette indexed frame, color 0 is transparent
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexedFrame {
+    pub width: u32,
+    pub height: u32,
+    /// column of the left edge of the frame relative to the anchor of the sprite
+    pub col_offset: i32,
+    /// row of the bottom edge of the frame relative to the anchor of the sprite
+    pub row_offset: i32,
+    /// the rows of the frame from top to bottom
+    pub pixels: Vec<u8>,
+    pub optional_bytes: Vec<u8>,
+}
+
+/// Builds a DCC file from palette indexed frames.
+///
+/// Every direction must have the same number of frames. A cell of 4x4 pixels of the
+/// direction can hold at most 4 colors, the transparent color included.
+///
+/// ```
+/// use dcc_decoder::{Dcc, DccBuilder, IndexedFrame};
+///
+/// let frame = IndexedFrame {
+///     width: 2,
+///     height: 2,
+///     col_offset: -1,
+///     row_offset: 0,
+///     pixels: vec![1, 2, 0, 1],
+///     optional_bytes: Vec::new(),
+/// };
+///
+/// let bytes = DccBuilder::new().add_direction(vec![frame]).to_bytes().unwrap();
+/// let dcc = Dcc::try_new(&bytes).unwrap();
+///
+/// assert_eq!(dcc.directions[0].frames[0].data, [1, 2, 0, 1]);
+/// ```
+#[derive(Debug, Default)]
+pub struct DccBuilder {
+    directions: Vec<Vec<IndexedFrame>>,
+}
+
+#[derive(Debug, Default)]
+struct Streams {
+    equal_cell: BitWriter,
+    pixel_mask: BitWriter,
+    encoding_type: BitWriter,
+    raw_pixel: BitWriter,
+    // the decoder reads the pixel codes after all displacements from the same stream
+    displacement: BitWriter,
+    pixel_code: BitWriter,
+}
+
+impl DccBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_direction(&mut self, frames: Vec<IndexedFrame>) -> &mut Self {
+        self.directions.push(frames);
+        self
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DccEncodeError> {
+        if self.directions.len() > usize::from(u8::MAX) {
+            return Err(DccEncodeError::TooManyDirections(self.directions.len()));
+        }
+
+        let num_frames = self.directions.first().map_or(0, Vec::len);
+
+        for (direction, frames) in self.directions.iter().enumerate() {
+            if frames.is_empty() || frames.len() != num_frames || num_frames > usize::from(u8::MAX)
+            {
+                return Err(DccEncodeError::InvalidFrameCount { direction });
+            }
+        }
+
+        let directions = self
+            .directions
+            .iter()
+            .enumerate()
+            .map(|(direction, frames)| encode_direction(direction, frames))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let header_size = 15 + 4 * directions.len();
+        let total_size = header_size + directions.iter().map(Vec::len).sum::<usize>();
+
+        let mut bytes = Vec::with_capacity(total_size);
+        bytes.push(FILE_SIGNATURE);
+        bytes.push(FILE_VERSION);
+        bytes.push(directions.len() as u8);
+        bytes.extend((num_frames as u32).to_le_bytes());
+        bytes.extend(FILE_TAG.to_le_bytes());
+        bytes.extend((total_size as u32).to_le_bytes());
+
+        let mut dir_offset = header_size;
+        for direction in &directions {
+            bytes.extend((dir_offset as u32).to_le_bytes());
+            dir_offset += direction.len();
+        }
+
+        for direction in &directions {
+            bytes.extend(direction);
+        }
+
+        Ok(bytes)
+    }
+}
+
+fn encode_direction(direction: usize, frames: &[IndexedFrame]) -> Result<Vec<u8>, DccEncodeError> {
+    let invalid_frame = |frame: usize| DccEncodeError::InvalidFrame { direction, frame };
+
+    let frame_headers: Vec<FrameHeader> = frames
+        .iter()
+        .map(|frame| FrameHeader {
+            variable0: 0,
+            height: frame.height,
+            width: frame.width,
+            row_offset: frame.row_offset,
+            col_offset: frame.col_offset,
+            optional_data: frame.optional_bytes.len() as u32,
+            coded_bytes: 0,
+            frame_buttom_up: false,
+        })
+        .collect();
+
+    let mut frame_boxes = Vec::with_capacity(frames.len());
+    for (i, (frame, header)) in frames.iter().zip(&frame_headers).enumerate() {
+        if frame.pixels.len() as u64 != u64::from(frame.width) * u64::from(frame.height) {
+            return Err(invalid_frame(i));
+        }
+
+        frame_boxes.push(get_frame_box(header).ok_or_else(|| invalid_frame(i))?);
+    }
+
+    let direction_box =
+        Direction::get_direction_box(&frame_boxes).map_err(|reason| match reason {
+            DccErrorReason::FrameOutOfBounds { frame } => invalid_frame(frame),
+            _ => invalid_frame(0),
+        })?;
+
+    // the transparent color is always in the key so that index 0 stays transparent
+    let mut used_colors = [false; 256];
+    used_colors[0] = true;
+    for frame in frames {
+        for pixel in &frame.pixels {
+            used_colors[*pixel as usize] = true;
+        }
+    }
+
+    let pixel_values: Vec<u8> = (0..=255).filter(|&c| used_colors[c as usize]).collect();
+    let mut color_to_index = [0; 256];
+    for (i, color) in pixel_values.iter().enumerate() {
+        color_to_index[*color as usize] = i as u8;
+    }
+
+    let mut streams = Streams::default();
+    let mut cell_encoder = CellEncoder::new(&direction_box, &pixel_values, &color_to_index);
+
+    for (f, (frame, frame_box)) in frames.iter().zip(&frame_boxes).enumerate() {
+        cell_encoder
+            .encode_frame(&mut streams, frame, frame_box)
+            .map_err(|cell| DccEncodeError::TooManyColors {
+                direction,
+                frame: f,
+                row: cell.offset.row,
+                col: cell.offset.col,
+            })?;
+    }
+
+    write_direction(direction, frames, &frame_headers, &used_colors, &streams)
+}
+
+fn write_direction(
+    direction: usize,
+    frames: &[IndexedFrame],
+    frame_headers: &[FrameHeader],
+    used_colors: &[bool; 256],
+    streams: &Streams,
+) -> Result<Vec<u8>, DccEncodeError> {
+    // streams without a set bit are left out, the decoder then assumes cleared bits
+    let has_equal_cell = streams.equal_cell.bytes.iter().any(|b| *b != 0);
+    let has_raw_pixel = streams.encoding_type.bytes.iter().any(|b| *b != 0);
+    let compression_flag = (u32::from(has_equal_cell) << 1) | u32::from(has_raw_pixel);
+
+    let width_bits = unsigned_bits_index(frame_headers.iter().map(|h| h.width));
+    let height_bits = unsigned_bits_index(frame_headers.iter().map(|h| h.height));
+    let col_offset_bits = signed_bits_index(frame_headers.iter().map(|h| h.col_offset));
+    let row_offset_bits = signed_bits_index(frame_headers.iter().map(|h| h.row_offset));
+    let optional_data_bits = unsigned_bits_index(frame_headers.iter().map(|h| h.optional_data));
+
+    let mut bw = BitWriter::new();
+
+    // the size of the direction, filled in at the end
+    bw.write_bits(0, 32);
+
+    bw.write_bits(compression_flag, 2);
+    for bits in [
+        0,
+        width_bits,
+        height_bits,
+        col_offset_bits,
+        row_offset_bits,
+        optional_data_bits,
+        0,
+    ] {
+        bw.write_bits(u32::from(bits), 4);
+    }
+
+    let num_bits = |index: u8| BITS_WIDTH_TABLE[index as usize];
+
+    for header in frame_headers {
+        bw.write_bits(header.width, num_bits(width_bits));
+        bw.write_bits(header.height, num_bits(height_bits));
+        bw.write_signed_bits(header.col_offset, num_bits(col_offset_bits));
+        bw.write_signed_bits(header.row_offset, num_bits(row_offset_bits));
+        bw.write_bits(header.optional_data, num_bits(optional_data_bits));
+        bw.write_bit(header.frame_buttom_up);
+    }
+
+    if frames.iter().any(|frame| !frame.optional_bytes.is_empty()) {
+        let optional_bytes: Vec<u8> = frames
+            .iter()
+            .flat_map(|frame| frame.optional_bytes.iter().copied())
+            .collect();
+        bw.write_alligned_bytes(&optional_bytes);
+    }
+
+    let mut sizes = Vec::new();
+    if has_equal_cell {
+        sizes.push(&streams.equal_cell);
+    }
+    sizes.push(&streams.pixel_mask);
+    if has_raw_pixel {
+        sizes.push(&streams.encoding_type);
+        sizes.push(&streams.raw_pixel);
+    }
+
+    for stream in &sizes {
+        if stream.num_bits > MAX_STREAM_SIZE {
+            return Err(DccEncodeError::DirectionTooLarge { direction });
+        }
+
+        bw.write_bits(stream.num_bits as u32, 20);
+    }
+
+    for used in used_colors {
+        bw.write_bit(*used);
+    }
+
+    for stream in sizes {
+        bw.append(stream);
+    }
+    bw.append(&streams.displacement);
+    bw.append(&streams.pixel_code);
+
+    let size = bw.bytes.len() as u32;
+    bw.bytes[..4].copy_from_slice(&size.to_le_bytes());
+
+    Ok(bw.bytes)
+}
+
+/// Encodes the cells of the frames of a direction, while keeping the state the decoder
+/// will have so that unchanged cells can be taken from earlier frames
+struct CellEncoder<'a> {
+    buffer_cells: Vec<Cell>,
+    num_horizontal_cells: u16,
+    // pixel indices of the last pixel buffer entry of each buffer cell
+    entries: Vec<Option<[u8; 4]>>,
+    dir_bitmap: Frame,
+    direction_box: &'a DccBox,
+    pixel_values: &'a [u8],
+    color_to_index: &'a [u8; 256],
+}
+
+impl<'a> CellEncoder<'a> {
+    fn new(
+        direction_box: &'a DccBox,
+        pixel_values: &'a [u8],
+        color_to_index: &'a [u8; 256],
+    ) -> Self {
+        let buffer_cells = Direction::get_buffer_cells(direction_box);
+
+        Self {
+            entries: vec![None; buffer_cells.cells.len()],
+            buffer_cells: buffer_cells.cells,
+            num_horizontal_cells: buffer_cells.num_horizontal_cells,
+            dir_bitmap: Frame::new(direction_box.dims),
+            direction_box,
+            pixel_values,
+            color_to_index,
+        }
+    }
+
+    /// Returns the cell with too many colors on failure
+    fn encode_frame(
+        &mut self,
+        streams: &mut Streams,
+        frame: &IndexedFrame,
+        frame_box: &DccBox,
+    ) -> Result<(), Cell> {
+        let frame_cells = Direction::get_frame_cells(self.direction_box, frame_box);
+
+        let row_start = (frame_box.row_min - self.direction_box.row_min) as usize;
+        let col_start = (frame_box.col_min - self.direction_box.col_min) as usize;
+
+        for cell in &frame_cells.cells {
+            let mut target = Vec::with_capacity(cell.size.row as usize * cell.size.col as usize);
+            for row in cell_range(cell.offset.row, cell.size.row) {
+                for col in cell_range(cell.offset.col, cell.size.col) {
+                    let idx = (row - row_start) * frame.width as usize + (col - col_start);
+                    target.push(frame.pixels[idx]);
+                }
+            }
+
+            self.encode_cell(streams, *cell, &target)?;
+        }
+
+        Ok(())
+    }
+
+    fn encode_cell(
+        &mut self,
+        streams: &mut Streams,
+        cell: Cell,
+        target: &[u8],
+    ) -> Result<(), Cell> {
+        let buffer_idx =
+            (cell.offset.row / 4 * self.num_horizontal_cells + cell.offset.col / 4) as usize;
+        let buff_cell = self.buffer_cells[buffer_idx];
+        let old_entry = self.entries[buffer_idx];
+
+        if old_entry.is_some() {
+            let same_size = buff_cell.size == cell.size;
+
+            let is_equal = if same_size {
+                predict_copy(&self.dir_bitmap, buff_cell.offset, cell.offset, cell.size) == target
+            } else {
+                target.iter().all(|pixel| *pixel == 0)
+            };
+
+            streams.equal_cell.write_bit(is_equal);
+
+            if is_equal {
+                if same_size {
+                    self.dir_bitmap
+                        .copy_cell_internal(buff_cell.offset, cell.offset, cell.size);
+                } else {
+                    self.dir_bitmap.set_cell_to_color(cell, 0);
+                }
+
+                self.buffer_cells[buffer_idx] = cell;
+                return Ok(());
+            }
+        }
+
+        let pixels = self.cell_pixel_indices(target).ok_or(cell)?;
+
+        let mask = match old_entry {
+            Some(old_pixels) => {
+                let mask = (0..4)
+                    .filter(|&i| pixels[i] != old_pixels[i])
+                    .fold(0, |mask, i| mask | (1 << i));
+                streams.pixel_mask.write_bits(mask, 4);
+                mask
+            }
+            None => 0xF,
+        };
+
+        // the decoder assigns the masked pixels from the last decoded index to the first
+        let indices: Vec<u8> = (0..4)
+            .filter(|&i| mask & (1 << i) != 0)
+            .map(|i| pixels[i])
+            .filter(|&index| index != 0)
+            .rev()
+            .collect();
+
+        if mask != 0 {
+            write_pixel_indices(streams, &indices, mask.count_ones() as usize);
+        }
+
+        self.entries[buffer_idx] = Some(pixels);
+
+        let colors = pixels.map(|index| self.pixel_values[index as usize]);
+
+        if colors[0] != colors[1] {
+            let num_bits = if colors[1] == colors[2] { 1 } else { 2 };
+
+            for pixel in target {
+                let code = colors.iter().position(|color| color == pixel).unwrap();
+                streams.pixel_code.write_bits(code as u32, num_bits);
+            }
+        }
+
+        let mut target_pixels = target.iter();
+        self.dir_bitmap
+            .set_cell(cell, || Ok::<_, ()>(*target_pixels.next().unwrap()))
+            .unwrap();
+
+        self.buffer_cells[buffer_idx] = cell;
+
+        Ok(())
+    }
+
+    /// Returns the pixel indices of a new pixel buffer entry for the colors of a cell, in
+    /// the descending order the decoder produces, or `None` if there are too many colors
+    fn cell_pixel_indices(&self, target: &[u8]) -> Option<[u8; 4]> {
+        let mut used = [false; 256];
+        for pixel in target {
+            used[self.color_to_index[*pixel as usize] as usize] = true;
+        }
+
+        let indices: Vec<u8> = (1..=255).rev().filter(|&i| used[i as usize]).collect();
+
+        if indices.len() > 4 || (indices.len() == 4 && used[0]) {
+            return None;
+        }
+
+        let mut pixels = [0; 4];
+        pixels[..indices.len()].copy_from_slice(&indices);
+
+        Some(pixels)
+    }
+}
+
+/// Writes the ascending pixel indices either raw or as displacements, whichever is shorter
+fn write_pixel_indices(streams: &mut Streams, indices: &[u8], num_pixels: usize) {
+    // a repeated index ends the list early
+    let terminated = indices.len() < num_pixels;
+    let last_index = indices.last().copied().unwrap_or(0);
+
+    let mut encoded_bits = if terminated { 4 } else { 0 };
+    let mut last = 0;
+    for index in indices {
+        encoded_bits += 4 * (usize::from(index - last) / 15 + 1);
+        last = *index;
+    }
+
+    let raw_bits = 8 * (indices.len() + usize::from(terminated));
+
+    if raw_bits < encoded_bits {
+        streams.encoding_type.write_bit(true);
+
+        for index in indices {
+            streams.raw_pixel.write_bits(u32::from(*index), 8);
+        }
+
+        if terminated {
+            streams.raw_pixel.write_bits(u32::from(last_index), 8);
+        }
+    } else {
+        streams.encoding_type.write_bit(false);
+
+        let mut last = 0;
+        for index in indices {
+            let mut displacement = index - last;
+            while displacement >= 15 {
+                streams.displacement.write_bits(15, 4);
+                displacement -= 15;
+            }
+            streams.displacement.write_bits(u32::from(displacement), 4);
+            last = *index;
+        }
+
+        if terminated {
+            streams.displacement.write_bits(0, 4);
+        }
+    }
+}
+
+/// Returns the pixels the decoder produces by copying a cell of the direction bitmap,
+/// the copy is done in place so overlapping pixels are read after they're overwritten
+fn predict_copy(bitmap: &Frame, src: PointU16, dst: PointU16, size: PointU16) -> Vec<u8> {
+    let (rows, cols) = (size.row as usize, size.col as usize);
+    let mut pixels = Vec::with_capacity(rows * cols);
+
+    for row in 0..rows {
+        for col in 0..cols {
+            let src_row = src.row as usize + row;
+            let src_col = src.col as usize + col;
+
+            let copied = match (
+                src_row.checked_sub(dst.row as usize),
+                src_col.checked_sub(dst.col as usize),
+            ) {
+                (Some(r), Some(c)) if r < rows && c < cols && r * cols + c < pixels.len() => {
+                    Some(pixels[r * cols + c])
+                }
+                _ => None,
+            };
+
+            pixels
+                .push(copied.unwrap_or(bitmap.data[src_row * bitmap.dims.col as usize + src_col]));
+        }
+    }
+
+    pixels
+}
+
+fn cell_range(offset: u16, size: u16) -> std::ops::Range<usize> {
+    offset as usize..(offset + size) as usize
+}
+
+fn unsigned_bits_index(values: impl Iterator<Item = u32>) -> u8 {
+    let needed_bits = 32 - values.max().unwrap_or(0).leading_zeros();
+
+    BITS_WIDTH_TABLE
+        .iter()
+        .position(|bits| u32::from(*bits) >= needed_bits)
+        .unwrap() as u8
+}
+
+fn signed_bits_index(values: impl Iterator<Item = i32> + Clone) -> u8 {
+    let fits = |value: i32, bits: u8| match bits {
+        0 => value == 0,
+        32 => true,
+        // a single bit is read as 0 or -1
+        bits => {
+            let half = 1_i64 << (bits - 1);
+            (-half..half).contains(&i64::from(value))
+        }
+    };
+
+    BITS_WIDTH_TABLE
+        .iter()
+        .position(|bits| values.clone().all(|value| fits(value, *bits)))
+        .unwrap() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dcc;
+
+    fn frame(width: u32, height: u32, col_offset: i32, row_offset: i32, seed: u32) -> IndexedFrame {
+        // 3 colors and transparency per frame, so no cell has more than 4 colors
+        let colors = [0, 10 + seed as u8, 100 + seed as u8 * 7, 250 - seed as u8];
+        let pixels = (0..width * height)
+            .map(|i| colors[((i * 7 + i / width * 3 + seed) % 11 % 4) as usize])
+            .collect();
+
+        IndexedFrame {
+            width,
+            height,
+            col_offset,
+            row_offset,
+            pixels,
+            optional_bytes: Vec::new(),
+        }
+    }
+
+    // a different color in each cell, so that the pixel indices get large
+    fn many_colors_frame(seed: u32) -> IndexedFrame {
+        let pixels = (0..32 * 32_u32)
+            .map(|i| {
+                let (row, col) = (i / 32, i % 32);
+                match (row + col + seed) % 3 {
+                    0 => 0,
+                    1 => (1 + (row / 4 * 8 + col / 4) * 3 + seed) as u8,
+                    _ => 255 - seed as u8,
+                }
+            })
+            .collect();
+
+        IndexedFrame {
+            width: 32,
+            height: 32,
+            col_offset: -16,
+            row_offset: 15,
+            pixels,
+            optional_bytes: Vec::new(),
+        }
+    }
+
+    fn placed(frame: &IndexedFrame, dir_row_min: i32, dir_col_min: i32, dims: PointU16) -> Vec<u8> {
+        let mut data = vec![0; dims.row as usize * dims.col as usize];
+        let row_start = (frame.row_offset - frame.height as i32 + 1 - dir_row_min) as usize;
+        let col_start = (frame.col_offset - dir_col_min) as usize;
+
+        for row in 0..frame.height as usize {
+            for col in 0..frame.width as usize {
+                data[(row_start + row) * dims.col as usize + col_start + col] =
+                    frame.pixels[row * frame.width as usize + col];
+            }
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let still = frame(9, 13, -4, 2, 3);
+        let mut moved = still.clone();
+        moved.col_offset += 1;
+        let mut optional = frame(6, 5, 3, -5, 4);
+        optional.optional_bytes = vec![1, 2, 3];
+
+        let directions = vec![
+            vec![
+                frame(13, 17, -6, 5, 0),
+                frame(11, 19, -7, 8, 1),
+                frame(1, 1, 0, 0, 2),
+            ],
+            vec![still.clone(), still.clone(), moved],
+            vec![frame(7, 7, 20, -30, 5), optional, frame(23, 2, -40, 40, 6)],
+            vec![
+                many_colors_frame(0),
+                many_colors_frame(1),
+                many_colors_frame(1),
+            ],
+        ];
+
+        let mut builder = DccBuilder::new();
+        for frames in &directions {
+            builder.add_direction(frames.clone());
+        }
+
+        let bytes = builder.to_bytes().unwrap();
+        let dcc = Dcc::try_new(&bytes).unwrap();
+
+        assert_eq!(dcc.directions.len(), directions.len());
+
+        for (direction, frames) in dcc.directions.iter().zip(&directions) {
+            let row_min = frames
+                .iter()
+                .map(|f| f.row_offset - f.height as i32 + 1)
+                .min()
+                .unwrap();
+            let col_min = frames.iter().map(|f| f.col_offset).min().unwrap();
+
+            for (decoded, frame) in direction.frames.iter().zip(frames) {
+                assert_eq!(decoded.data, placed(frame, row_min, col_min, decoded.dims));
+            }
+        }
+    }
+
+    #[test]
+    fn test_too_many_colors() {
+        let mut frame = frame(4, 4, 0, 3, 0);
+        frame.pixels[..5].copy_from_slice(&[1, 2, 3, 4, 5]);
+
+        let error = DccBuilder::new()
+            .add_direction(vec![frame])
+            .to_bytes()
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            DccEncodeError::TooManyColors {
+                direction: 0,
+                frame: 0,
+                row: 0,
+                col: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+
+/// Why a DCC file couldn't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DccErrorReason {
+    /// a stream read past the end of the file
+    UnexpectedEnd,
+    /// a byte aligned value was read in the middle of a byte
+    UnalignedRead,
+    /// more bits were read into a single value than it can hold
+    TooManyBits {
+        num_bits: u8,
+        max_bits: u8,
+    },
+    IncorrectSignature(u8),
+    UnsupportedTag(u32),
+    DirectionOffsetOutOfBounds(u32),
+    NoFrames,
+    /// the frames of a direction don't fit in a direction box of the maximal size
+    FrameOutOfBounds {
+        frame: usize,
+    },
+    /// the pixel code stream references a cell which doesn't exist
+    InvalidCell,
+}
+
+/// A malformed DCC file, with the position in the file where the problem was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DccError {
+    pub byte_offset: usize,
+    pub bit_offset: u8,
+    pub reason: DccErrorReason,
+}
+
+impl DccError {
+    pub(crate) fn new(byte_offset: usize, bit_offset: u8, reason: DccErrorReason) -> Self {
+        Self {
+            byte_offset,
+            bit_offset,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for DccErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DccErrorReason::UnexpectedEnd => write!(f, "unexpected end of data"),
+            DccErrorReason::UnalignedRead => write!(f, "unaligned byte read"),
+            DccErrorReason::TooManyBits { num_bits, max_bits } => {
+                write!(
+                    f,
+                    "can't read {num_bits} bits, at most {max_bits} bits can be read"
+                )
+            }
+            DccErrorReason::IncorrectSignature(signature) => {
+                write!(f, "incorrect signature {signature}")
+            }
+            DccErrorReason::UnsupportedTag(tag) => write!(f, "unsupported tag {tag}"),
+            DccErrorReason::DirectionOffsetOutOfBounds(offset) => {
+                write!(f, "direction offset {offset} is out of bounds")
+            }
+            DccErrorReason::NoFrames => write!(f, "direction has no frames"),
+            DccErrorReason::FrameOutOfBounds { frame } => {
+                write!(f, "frame {frame} is out of bounds")
+            }
+            DccErrorReason::InvalidCell => write!(f, "invalid cell"),
+        }
+    }
+}
+
+impl fmt::Display for DccError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at byte {}, bit {}",
+            self.reason, self.byte_offset, self.bit_offset
+        )
+    }
+}
+
+impl std::error::Error for DccError {}
+
+/// Why frames couldn't be encoded into a DCC file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DccEncodeError {
+    TooManyDirections(usize),
+    /// the directions have different numbers of frames, or more than 255 frames
+    InvalidFrameCount {
+        direction: usize,
+    },
+    /// the pixels don't match the size of the frame, or the frame is too large
+    InvalidFrame {
+        direction: usize,
+        frame: usize,
+    },
+    /// a cell of the frame has more than 4 colors
+    TooManyColors {
+        direction: usize,
+        frame: usize,
+        row: u16,
+        col: u16,
+    },
+    /// the data of a direction doesn't fit in its 20 bit stream sizes
+    DirectionTooLarge {
+        direction: usize,
+    },
+}
+
+impl fmt::Display for DccEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DccEncodeError::TooManyDirections(num_directions) => {
+                write!(f, "{num_directions} directions, at most 255 are supported")
+            }
+            DccEncodeError::InvalidFrameCount { direction } => {
+                write!(f, "invalid number of frames in direction {direction}")
+            }
+            DccEncodeError::InvalidFrame { direction, frame } => {
+                write!(f, "invalid frame {frame} in direction {direction}")
+            }
+            DccEncodeError::TooManyColors {
+                direction,
+                frame,
+                row,
+                col,
+            } => write!(
+                f,
+                "cell at row {row}, column {col} of frame {frame} in direction {direction} has more than 4 colors"
+            ),
+            DccEncodeError::DirectionTooLarge { direction } => {
+                write!(f, "direction {direction} is too large")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DccEncodeError {}
+
+/// Why a COF file couldn't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CofErrorReason {
+    /// a read past the end of the file
+    UnexpectedEnd,
+    /// a layer or the draw order refers to a composite type which doesn't exist
+    InvalidComposite(u8),
+}
+
+/// A malformed COF file, with the position in the file where the problem was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CofError {
+    pub byte_offset: usize,
+    pub reason: CofErrorReason,
+}
+
+impl fmt::Display for CofErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CofErrorReason::UnexpectedEnd => write!(f, "unexpected end of data"),
+            CofErrorReason::InvalidComposite(composite) => {
+                write!(f, "invalid composite type {composite}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for CofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.reason, self.byte_offset)
+    }
+}
+
+impl std::error::Error for CofError {}
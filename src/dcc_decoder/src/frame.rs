@@ -12,6 +12,12 @@ pub struct PointU16 {
     pub col: u16,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointI32 {
+    pub row: i32,
+    pub col: i32,
+}
+
 impl Frame {
     pub fn new(dims: PointU16) -> Self {
         let data = vec![0; dims.row as usize * dims.col as usize];
@@ -64,16 +70,18 @@ impl Frame {
         }
     }
 
-    pub fn set_cell<F>(&mut self, cell: Cell, mut get_next_byte: F)
+    pub fn set_cell<F, E>(&mut self, cell: Cell, mut get_next_byte: F) -> Result<(), E>
     where
-        F: FnMut() -> u8,
+        F: FnMut() -> Result<u8, E>,
     {
         for row in cell.offset.row..cell.offset.row + cell.size.row {
             for col in cell.offset.col..cell.offset.col + cell.size.col {
                 let idx = (u32::from(row) * u32::from(self.dims.col) + u32::from(col)) as usize;
 
-                self.data[idx] = get_next_byte();
+                self.data[idx] = get_next_byte()?;
             }
         }
+
+        Ok(())
     }
 }
@@ -1,6 +1,14 @@
 mod bit_stream;
 mod bit_streams;
+mod bit_writer;
+mod cof;
 mod dcc_decoder;
+mod dcc_encoder;
+mod error;
 mod frame;
 
-pub use crate::dcc_decoder::Dcc;
+pub use crate::cof::{Cof, CofLayer, CompositeFrame, COMPOSITE_NAMES};
+pub use crate::dcc_decoder::{Dcc, DccBox, DccReader, Direction, FrameHeader};
+pub use crate::dcc_encoder::{DccBuilder, IndexedFrame};
+pub use crate::error::{CofError, CofErrorReason, DccEncodeError, DccError, DccErrorReason};
+pub use crate::frame::{Frame, PointI32, PointU16};
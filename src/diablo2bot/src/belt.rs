@@ -743,7 +743,7 @@ mod tests {
 
     fn test_update_belt(belt: &mut Belt, file_io: &FileIo) {
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let font_dc6_bytes = archives.extract_font_16_bytes().unwrap();
         let font_dc6_file = font_dc6_bytes.parse();
@@ -755,8 +755,7 @@ mod tests {
         let pixel_palette = act_palette_bytes.get_pixel_palette();
         let palette_transformer = act_palette_bytes.get_palette_transformer(&pixel_palette);
 
-        let consumable_item_matcher =
-            ConsumableItemsTableMatcher::new(&mut archives, &font_char_map);
+        let consumable_item_matcher = ConsumableItemsTableMatcher::new(&archives, &font_char_map);
 
         let image_path = file_io
             .root
@@ -2,6 +2,8 @@ use crate::{
     enums::{click_type, game_interface_element::GameInterfaceElement},
     game::Game,
     game_interface_element_controller::GameInterfaceElementController,
+    item_rules::ItemProperties,
+    item_tooltip::ItemTooltip,
     loot::{is_item_potion, take_loot_screenshot},
     point_u16::PointU16,
     structs::Item,
@@ -22,22 +24,25 @@ pub fn drop_unwanted_items(g: &mut Game) {
 
     let inventory_table_meta_data = g.table_inventory_empty_matcher.get_table_meta_data();
 
+    // The kept items which are still in the inventory are added again
+    g.runeword_targets.clear_kept_items();
+
     for row in 0..inventory_table_meta_data.table_size.row as usize {
         for col in 0..inventory_table_meta_data.table_size.col as usize {
             if g.inventory_table_reserved_cells.cells[row][col].is_none()
                 && inventory.cells[row][col].is_some()
             {
-                let item_screen_point = inventory_table_meta_data
-                    .get_point(PointU16::new(row as u16, col as u16))
-                    + PointU16::new(5, 5);
+                let cell = PointU16::new(row as u16, col as u16);
+                let item_screen_point =
+                    inventory_table_meta_data.get_point(cell) + PointU16::new(5, 5);
 
                 g.output_controller.move_mouse(item_screen_point);
                 sleep_frames(Frames(2));
 
                 let matrix = take_loot_screenshot(g);
-                let items = g.font_symbol_matcher.match_image_items(&matrix);
+                let items = g.tooltip_font_matcher.match_image_items(&matrix);
 
-                if !keep_item(&items) {
+                if !keep_item(g, &items, cell) {
                     drop_item(g, item_screen_point);
                 }
 
@@ -73,13 +78,50 @@ fn drop_item(g: &mut Game, item_screen_point: PointU16) {
     sleep_frames(Frames(4));
 }
 
-fn keep_item(items: &[Item]) -> bool {
-    // TODO Expand with more granular filtering.
+fn keep_item(g: &mut Game, items: &[Item], cell: PointU16) -> bool {
     for item in items.iter() {
         if is_item_potion(item) {
             return false;
         }
     }
 
-    true
+    let tooltip = ItemTooltip::new(items);
+
+    // The stats of an item which couldn't be identified aren't shown
+    let identified = !tooltip.lines.iter().any(|line| line.text == "Unidentified");
+
+    let Some(name_line) = tooltip.lines.first() else {
+        return true;
+    };
+
+    // The base name is on the second line of rare, set and unique items
+    let base_name = tooltip
+        .lines
+        .iter()
+        .take(2)
+        .map(|line| line.text.as_str())
+        .find(|text| g.item_bases.get(text).is_some())
+        .or_else(|| g.item_bases.find_base_name(&name_line.text))
+        .unwrap_or(&name_line.text);
+
+    let item_properties = ItemProperties {
+        name: &name_line.text,
+        base_name,
+        quality: name_line.quality,
+        base: g.item_bases.get(base_name),
+        gold: None,
+        ethereal: Some(tooltip.ethereal),
+        sockets: Some(tooltip.sockets),
+        stats: identified.then_some(tooltip.stats.as_slice()),
+    };
+
+    let keep = g
+        .runeword_targets
+        .keep_item(&item_properties, &g.item_bases, &g.item_filter);
+
+    if keep {
+        g.runeword_targets.add_kept_item(cell, &item_properties);
+    }
+
+    keep
 }
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// The table an item base is from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemClass {
+    Weapon,
+    Armor,
+    Misc,
+}
@@ -7,6 +7,8 @@ pub mod errors;
 pub mod game_difficulty;
 pub mod game_interface_element;
 pub mod healing_potion_type;
+pub mod item_class;
+pub mod item_tier;
 pub mod mana_potion_type;
 pub mod mode;
 pub mod monster_matcher_type;
@@ -16,6 +18,7 @@ pub mod potion_points_type;
 pub mod quality;
 pub mod rejuvenation_potion_type;
 pub mod route;
+pub mod stat_type;
 pub mod state;
 pub mod table_type;
 pub mod waypoint_zone;
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// The stats which are read from the tooltips of items
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatType {
+    AllSkills,
+    ClassSkills,
+    FasterCastRate,
+    IncreasedAttackSpeed,
+    FasterHitRecovery,
+    FasterRunWalk,
+    Life,
+    Mana,
+    Strength,
+    Dexterity,
+    Vitality,
+    Energy,
+    AllResistances,
+    FireResist,
+    ColdResist,
+    LightningResist,
+    PoisonResist,
+    EnhancedDefense,
+    EnhancedDamage,
+    MagicFind,
+    GoldFind,
+    LifeStolen,
+    ManaStolen,
+    Defense,
+}
@@ -2,7 +2,7 @@ use std::{
     env,
     ffi::OsStr,
     fs::{self, File},
-    io::{self, BufReader, Read},
+    io::{self, BufReader, Read, Write},
     path::{Path, PathBuf},
 };
 
@@ -10,9 +10,9 @@ use serde::de::DeserializeOwned;
 
 use crate::{
     bot_settings::BotSettings,
+    item_rules::{ItemFilterFile, ItemRules},
     pattern_matcher_monsters::{TreeCacheFiles, TreeCacheFilesBorrowed},
     profile::{Profile, SystemSettings},
-    structs::ItemsFilter,
 };
 
 const SETTINGS: &str = "settings";
@@ -49,7 +49,7 @@ impl FileIo {
         read_toml(&self.root.join(SETTINGS).join("profiles").join(profile_name))
     }
 
-    pub fn load_items_filter(&self, item_filter_name: &str) -> io::Result<ItemsFilter> {
+    pub fn load_items_filter(&self, item_filter_name: &str) -> io::Result<ItemFilterFile> {
         read_toml(
             &self
                 .root
@@ -59,6 +59,28 @@ impl FileIo {
         )
     }
 
+    pub fn save_item_rules(
+        &self,
+        item_filter_name: &str,
+        item_rules: &ItemRules,
+    ) -> io::Result<()> {
+        let file_path = get_toml_file_path(
+            &self
+                .root
+                .join(SETTINGS)
+                .join("item_filters")
+                .join(item_filter_name),
+        );
+
+        // an existing item filter is never overwritten
+        let mut file = File::options()
+            .write(true)
+            .create_new(true)
+            .open(file_path)?;
+
+        file.write_all(toml::to_string(item_rules).unwrap().as_bytes())
+    }
+
     pub fn has_monster_matcher_cache_folder(&self, name: &str) -> bool {
         self.get_monster_matcher_cache_folder_path(name).exists()
     }
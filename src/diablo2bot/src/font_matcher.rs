@@ -12,37 +12,55 @@ pub struct FontMatcher {
     pattern_matcher: PatternMatcher2<Character>,
 }
 
+const ITEM_NAME_CHARS: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ':/";
+// The tooltips also contain the stats of the items, like "+20% Faster Cast Rate"
+const TOOLTIP_CHARS: &str =
+    "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ':/+-%(),";
+
 impl FontMatcher {
     pub fn new(quality_palettes: &[QualityPalette], font_char_map: &HashMap<char, Matrix>) -> Self {
+        Self::new_with_chars(quality_palettes, font_char_map, ITEM_NAME_CHARS)
+    }
+
+    pub fn new_tooltip_matcher(
+        quality_palettes: &[QualityPalette],
+        font_char_map: &HashMap<char, Matrix>,
+    ) -> Self {
+        Self::new_with_chars(quality_palettes, font_char_map, TOOLTIP_CHARS)
+    }
+
+    fn new_with_chars(
+        quality_palettes: &[QualityPalette],
+        font_char_map: &HashMap<char, Matrix>,
+        chars: &str,
+    ) -> Self {
         let mut characters = Vec::new();
 
-        "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ':/"
-            .chars()
-            .for_each(|char| {
-                let matrix = &font_char_map[&char];
-                let width = matrix.get_non_zero_width() as u8;
-
-                quality_palettes.iter().for_each(|palette| {
-                    let matrix = matrix.palette_transform(&palette.palette);
-                    let point_values = matrix.get_non_zero_point_values();
-
-                    let matrix_and_points = MatrixAndPoints {
-                        matrix,
-                        point_values,
-                    };
-
-                    let character = Character {
-                        char: QualityCharacter {
-                            char,
-                            width,
-                            quality: palette.color,
-                        },
-                        matrix_and_points,
-                    };
-
-                    characters.push(character);
-                })
-            });
+        chars.chars().for_each(|char| {
+            let matrix = &font_char_map[&char];
+            let width = matrix.get_non_zero_width() as u8;
+
+            quality_palettes.iter().for_each(|palette| {
+                let matrix = matrix.palette_transform(&palette.palette);
+                let point_values = matrix.get_non_zero_point_values();
+
+                let matrix_and_points = MatrixAndPoints {
+                    matrix,
+                    point_values,
+                };
+
+                let character = Character {
+                    char: QualityCharacter {
+                        char,
+                        width,
+                        quality: palette.color,
+                    },
+                    matrix_and_points,
+                };
+
+                characters.push(character);
+            })
+        });
 
         let pattern_matcher = pattern_matcher2::PatternMatcher2::new(characters);
 
@@ -129,8 +147,8 @@ mod tests {
 
     use crate::{
         enums::act::Act, file_io::FileIo, font_char_map::get_non_control_ascii_char_font_map,
-        image::Image, mpq_archives::archives::Archives, structs::Item,
-        test_utils::test_utils::read_json,
+        image::Image, matrix::Matrix, mpq_archives::archives::Archives, point_u16::PointU16,
+        structs::Item, test_utils::test_utils::read_json,
     };
 
     use super::FontMatcher;
@@ -139,7 +157,7 @@ mod tests {
     fn test_font_matcher() {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let font_dc6_bytes = archives.extract_font_16_bytes().unwrap();
         let font_dc6_file = font_dc6_bytes.parse();
@@ -188,4 +206,53 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_tooltip_matcher() {
+        let file_io = FileIo::new();
+        let system_settings = file_io.load_system_settings().unwrap();
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
+
+        let font_dc6_bytes = archives.extract_font_16_bytes().unwrap();
+        let font_dc6_file = font_dc6_bytes.parse();
+        let font_char_map = get_non_control_ascii_char_font_map(&font_dc6_file);
+
+        let pal_pl2_bytes = archives.extract_pal_pl2_bytes(Act::Act5.into()).unwrap();
+        let quality_palettes = pal_pl2_bytes
+            .extract_font_quality_palette_bytes()
+            .get_palettes();
+        let palette = &quality_palettes[0];
+
+        let font_matcher = FontMatcher::new_tooltip_matcher(&quality_palettes, &font_char_map);
+
+        // the glyphs of the stats are drawn far enough apart to be read as separate texts
+        let chars = "+-%(),";
+        let glyphs: Vec<Matrix> = chars
+            .chars()
+            .map(|char| font_char_map[&char].palette_transform(&palette.palette))
+            .collect();
+        let cell_width = glyphs.iter().map(|glyph| glyph.dims.col).max().unwrap() + 16;
+        let height = glyphs.iter().map(|glyph| glyph.dims.row).max().unwrap() + 8;
+
+        let mut matrix = Matrix::new_empty(PointU16::new(height, cell_width * glyphs.len() as u16));
+        for (i, glyph) in glyphs.iter().enumerate() {
+            matrix.insert_sub_matrix(PointU16::new(4, 8 + i as u16 * cell_width), glyph);
+        }
+
+        let names: Vec<String> = font_matcher
+            .match_image_items(&matrix)
+            .into_iter()
+            .filter(|item| item.quality == palette.color)
+            .map(|item| item.name)
+            .collect();
+
+        assert_eq!(names, ["+", "-", "%", "(", ")", ","]);
+
+        // the matcher of the item names doesn't know these glyphs
+        let name_matcher = FontMatcher::new(&quality_palettes, &font_char_map);
+        assert!(name_matcher
+            .match_image_items(&matrix)
+            .iter()
+            .all(|item| !item.name.contains(['+', '%', '(', ')', ','])));
+    }
 }
@@ -12,18 +12,22 @@ use crate::{
     game_interface_element_controller::GameInterfaceElementController,
     game_screenshotter::GameScreenshotter,
     inventory::TableEmptyMatcher,
+    item_bases::ItemBases,
+    item_rules::{ItemFilterFile, ItemRules},
     logger::Logger,
     map_matcher::MapMatcher,
     mpq_archives::archives::Archives,
+    named_items::NamedItems,
     output_controller::OutputController,
     pal_pl2::{PaletteTransformer, PixelPalette},
     pattern_matcher_monsters::{get_monster_tree, MonsterMatcherConfig, Tree},
     pre_cache_connected_areas::pre_cache_connected_areas,
     profile::Profile,
+    runeword_targets::RunewordTargets,
+    runewords::Runewords,
     skill_icon_getter::SkillIconGetter,
     spell_caster::SpellCaster,
     string_tables::{StringTables, ZoneNameConverter},
-    structs::ItemsFilter,
     table::Table,
     table_matcher::ConsumableItemsTableMatcher,
     weapon_swapper::WeaponSwapper,
@@ -35,12 +39,17 @@ pub struct Game {
     pub archives: Archives,
     pub file_io: FileIo,
     pub font_symbol_matcher: FontMatcher,
+    pub tooltip_font_matcher: FontMatcher,
     pub table_inventory_empty_matcher: TableEmptyMatcher,
     pub table_stash_empty_matcher: TableEmptyMatcher,
     pub consumable_items_table_matcher: ConsumableItemsTableMatcher,
     pub monster_matcher: Tree,
     pub profile: Profile,
-    pub item_filter: ItemsFilter,
+    pub item_filter: ItemRules,
+    pub item_bases: ItemBases,
+    pub named_items: NamedItems,
+    pub runewords: Runewords,
+    pub runeword_targets: RunewordTargets,
     pub bot_settings: BotSettings,
     pub palette_transformer: PaletteTransformer,
     pub buffs: Buffs,
@@ -62,15 +71,13 @@ pub struct Game {
 
 impl Game {
     pub fn new(
-        mut archives: Archives,
+        archives: Archives,
         file_io: FileIo,
         profile: Profile,
         bot_settings: BotSettings,
         game_screenshotter: GameScreenshotter,
         output_controller: OutputController,
     ) -> io::Result<Self> {
-        let item_filter = file_io.load_items_filter(&profile.item_filter).unwrap();
-
         let act = profile.zone_to_farm.to_act();
 
         let font_dc6_bytes = archives.extract_font_16_bytes().unwrap();
@@ -86,14 +93,15 @@ impl Game {
             .get_palettes();
 
         let font_symbol_matcher = FontMatcher::new(&quality_palettes, &font_char_map);
-        let table_inventory_empty_matcher =
-            TableEmptyMatcher::new(&mut archives, TableType::Inventory);
-        let table_stash_empty_matcher = TableEmptyMatcher::new(&mut archives, TableType::Stash);
+        let tooltip_font_matcher =
+            FontMatcher::new_tooltip_matcher(&quality_palettes, &font_char_map);
+        let table_inventory_empty_matcher = TableEmptyMatcher::new(&archives, TableType::Inventory);
+        let table_stash_empty_matcher = TableEmptyMatcher::new(&archives, TableType::Stash);
         let consumable_items_table_matcher =
-            ConsumableItemsTableMatcher::new(&mut archives, &font_char_map);
+            ConsumableItemsTableMatcher::new(&archives, &font_char_map);
 
         let skill_names = &Self::get_skill_names(&profile);
-        let skill_icon_getter = SkillIconGetter::new(&mut archives, skill_names);
+        let skill_icon_getter = SkillIconGetter::new(&archives, skill_names);
         let buffs = Buffs::new(&profile.buffs, bot_settings.clone());
 
         let faster_cast_rate = WeaponSetData::new(
@@ -109,8 +117,24 @@ impl Game {
 
         let level_name = &profile.zone_to_farm.to_string();
 
-        let string_tables = StringTables::new(&mut archives);
+        let string_tables = StringTables::new(&archives);
         let zone_name_converter = ZoneNameConverter::new(&string_tables);
+        let item_bases = ItemBases::new(&archives, &string_tables);
+        let named_items = NamedItems::new(&archives, &string_tables, &item_bases);
+
+        let item_filter = match file_io.load_items_filter(&profile.item_filter).unwrap() {
+            ItemFilterFile::Rules(item_rules) => item_rules,
+            ItemFilterFile::Items(items_filter) => {
+                ItemRules::from_items_filter(&items_filter, profile.min_gold_to_pickup, &item_bases)
+            }
+        };
+        item_filter
+            .validate(&item_bases)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let runewords = Runewords::new(&archives, &string_tables, &item_bases);
+        let runeword_targets = RunewordTargets::new(&profile.target_runewords, &runewords)
+            .unwrap_or_else(|e| panic!("{e}"));
 
         let npc_matcher_config =
             MonsterMatcherConfig::new_npc_matcher_config(act, profile.game_difficulty);
@@ -122,7 +146,7 @@ impl Game {
         );
 
         let potion_seller_matcher = get_monster_tree(
-            &mut archives,
+            &archives,
             &file_io,
             &act.get_potion_seller_name(),
             &npc_matcher_config,
@@ -130,7 +154,7 @@ impl Game {
         )?;
 
         let deckard_cain_matcher = get_monster_tree(
-            &mut archives,
+            &archives,
             &file_io,
             &act.get_deckard_cain_monster_id(),
             &npc_matcher_config,
@@ -138,7 +162,7 @@ impl Game {
         )?;
 
         let monster_matcher = get_monster_tree(
-            &mut archives,
+            &archives,
             &file_io,
             level_name,
             &level_monsters_matcher_config,
@@ -180,18 +204,23 @@ impl Game {
 
         let area = zone_to_area(&profile.zone_to_farm.to_string());
 
-        let map_sprite_matcher = MapMatcher::new(&area, &mut archives);
+        let map_sprite_matcher = MapMatcher::new(&area, &archives);
 
         Ok(Self {
             archives,
             file_io,
             font_symbol_matcher,
+            tooltip_font_matcher,
             table_inventory_empty_matcher,
             table_stash_empty_matcher,
             consumable_items_table_matcher,
             monster_matcher,
             profile,
             item_filter,
+            item_bases,
+            named_items,
+            runewords,
+            runeword_targets,
             palette_transformer,
             buffs,
             spell_caster,
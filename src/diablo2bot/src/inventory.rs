@@ -14,7 +14,7 @@ pub struct TableEmptyMatcher {
 }
 
 impl TableEmptyMatcher {
-    pub fn new(archives: &mut Archives, table_type: TableType) -> Self {
+    pub fn new(archives: &Archives, table_type: TableType) -> Self {
         let empty_cells_matrix = Self::get_empty_cells(archives, table_type);
 
         let table_meta_data = Self::_get_table_meta_data(table_type);
@@ -23,7 +23,7 @@ impl TableEmptyMatcher {
         Self { empty_table }
     }
 
-    fn get_empty_cells(archives: &mut Archives, table_type: TableType) -> Matrix {
+    fn get_empty_cells(archives: &Archives, table_type: TableType) -> Matrix {
         match table_type {
             TableType::Inventory => {
                 let inventory = archives
@@ -102,7 +102,7 @@ mod tests {
     use super::TableEmptyMatcher;
 
     fn test_empty_matcher(
-        archives: &mut Archives,
+        archives: &Archives,
         table_type: super::TableType,
         palette_transformer: &PaletteTransformer,
         test_data_path: &PathBuf,
@@ -154,7 +154,7 @@ mod tests {
     fn test_inventory_matcher() {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let act = Act::Act1;
         let pal_pl2_bytes = archives.extract_pal_pl2_bytes(act.into()).unwrap();
@@ -163,7 +163,7 @@ mod tests {
         let palette_transformer = act_palette_bytes.get_palette_transformer(&pixel_palette);
 
         test_empty_matcher(
-            &mut archives,
+            &archives,
             super::TableType::Inventory,
             &palette_transformer,
             &file_io.root.join("test_data").join("table_cells"),
@@ -171,7 +171,7 @@ mod tests {
         );
 
         test_empty_matcher(
-            &mut archives,
+            &archives,
             super::TableType::Stash,
             &palette_transformer,
             &file_io.root.join("test_data").join("table_cells"),
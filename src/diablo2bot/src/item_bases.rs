@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    enums::{item_class::ItemClass, item_tier::ItemTier},
+    mpq_archives::{archives::Archives, excel_items::ExcelItems},
+    point_u16::PointU16,
+    string_tables::StringTables,
+};
+
+/// A base item from weapons.txt, armor.txt or misc.txt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemBase {
+    pub code: String,
+    pub class: ItemClass,
+    /// the category of the item, like "helm" or "rune"
+    pub item_type: String,
+    /// misc items don't have a tier
+    pub tier: Option<ItemTier>,
+    /// the number of inventory cells the item fills
+    pub size: PointU16,
+    pub max_sockets: u8,
+    /// the rank of a rune, 1 for El and 33 for Zod
+    pub rune_rank: Option<u8>,
+}
+
+/// The base items by their displayed names
+pub struct ItemBases {
+    items: HashMap<String, ItemBase>,
+    code_to_name: HashMap<String, String>,
+    sizes: HashSet<PointU16>,
+}
+
+impl ItemBases {
+    pub fn new(archives: &Archives, string_tables: &StringTables) -> Self {
+        let raw_texts = [
+            (
+                ItemClass::Weapon,
+                archives.extract_excel_weapons_raw_text().unwrap(),
+            ),
+            (
+                ItemClass::Armor,
+                archives.extract_excel_armor_raw_text().unwrap(),
+            ),
+            (
+                ItemClass::Misc,
+                archives.extract_excel_misc_raw_text().unwrap(),
+            ),
+        ];
+
+        let excel_items: Vec<(ItemClass, ExcelItems)> = raw_texts
+            .iter()
+            .map(|(class, raw_text)| (*class, raw_text.parse()))
+            .collect();
+
+        Self::from_excel_items(&excel_items, string_tables)
+    }
+
+    pub fn from_excel_items(
+        excel_items: &[(ItemClass, ExcelItems)],
+        string_tables: &StringTables,
+    ) -> Self {
+        let mut item_bases = Self {
+            items: HashMap::new(),
+            code_to_name: HashMap::new(),
+            sizes: HashSet::new(),
+        };
+
+        for (class, excel_items) in excel_items {
+            item_bases.add_items(*class, excel_items, string_tables);
+        }
+
+        item_bases
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ItemBase> {
+        self.items.get(name)
+    }
+
+    /// The base items with their displayed names
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ItemBase)> {
+        self.items.iter().map(|(name, base)| (name.as_str(), base))
+    }
+
+    /// Returns the displayed name of the base item with the code
+    pub fn get_name(&self, code: &str) -> Option<&str> {
+        self.code_to_name.get(code).map(|name| name.as_str())
+    }
+
+    /// Returns the longest base item name in the text, like "Cap" in "Jagged Cap of Life".
+    /// The name has to be made of whole words of the text, "Cap" isn't found in "Capacity".
+    pub fn find_base_name(&self, text: &str) -> Option<&str> {
+        self.items
+            .keys()
+            .filter(|name| contains_words(text, name))
+            .max_by_key(|name| name.len())
+            .map(|name| name.as_str())
+    }
+
+    /// Whether any item fills this many inventory cells
+    pub fn is_item_size(&self, size: PointU16) -> bool {
+        self.sizes.contains(&size)
+    }
+
+    fn add_items(
+        &mut self,
+        class: ItemClass,
+        excel_items: &ExcelItems,
+        string_tables: &StringTables,
+    ) {
+        for row in &excel_items.rows {
+            let name = match string_tables.table.get(row.name_string_id) {
+                Some(name) => name.as_str(),
+                None => row.name,
+            };
+
+            let size = PointU16::new(
+                u16::from(row.inventory_height),
+                u16::from(row.inventory_width),
+            );
+
+            self.sizes.insert(size);
+            self.code_to_name
+                .insert(row.code.to_string(), name.to_string());
+
+            // Some items share their name, like the quest items. The first one is used.
+            self.items
+                .entry(name.to_string())
+                .or_insert_with(|| ItemBase {
+                    code: row.code.to_string(),
+                    class,
+                    item_type: row.item_type.to_string(),
+                    tier: row.get_tier(),
+                    size,
+                    max_sockets: row.max_sockets.unwrap_or(0),
+                    rune_rank: get_rune_rank(row.item_type, row.code),
+                });
+        }
+    }
+}
+
+// The codes of the runes are "r01" to "r33"
+fn get_rune_rank(item_type: &str, code: &str) -> Option<u8> {
+    if item_type != "rune" {
+        return None;
+    }
+
+    code.strip_prefix('r')?.parse().ok()
+}
+
+fn contains_words(text: &str, words: &str) -> bool {
+    text.match_indices(words).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + words.len()..].chars().next();
+
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Builds the item bases of the tests from the texts of the item tables and the displayed names
+/// of the string tables
+#[cfg(test)]
+pub fn item_bases_from_text(
+    excel_items: &[(ItemClass, &str)],
+    names: &[(&str, &str)],
+) -> ItemBases {
+    let string_tables = StringTables {
+        table: names
+            .iter()
+            .map(|(id, name)| (id.to_string(), name.to_string()))
+            .collect(),
+    };
+    let excel_items: Vec<(ItemClass, ExcelItems)> = excel_items
+        .iter()
+        .map(|(class, text)| (*class, ExcelItems::new(text)))
+        .collect();
+
+    ItemBases::from_excel_items(&excel_items, &string_tables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_bases() {
+        let item_bases = item_bases_from_text(
+            &[(
+                ItemClass::Weapon,
+                "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\tnormcode\tubercode\tultracode\r\n\
+                 Shako\tuap\tuap\thelm\t2\t2\t2\tcap\txap\tuap\r\n\
+                 Long Sword\tlsd\tlsd\tswor\t2\t3\t4\tlsd\t9ls\t7ls\r\n",
+            )],
+            &[("uap", "Shako")],
+        );
+
+        let shako = item_bases.get("Shako").unwrap();
+        assert_eq!(shako.tier, Some(ItemTier::Elite));
+        assert_eq!(shako.item_type, "helm");
+        assert_eq!(item_bases.get_name("lsd"), Some("Long Sword"));
+        assert_eq!(
+            item_bases.find_base_name("Jagged Long Sword of Life"),
+            Some("Long Sword")
+        );
+        assert_eq!(item_bases.find_base_name("Long Swordsman's Shakos"), None);
+        assert_eq!(
+            item_bases.find_base_name("Shako, Long Sword"),
+            Some("Long Sword")
+        );
+
+        assert_eq!(
+            item_bases.get("Long Sword").unwrap().size,
+            PointU16::new(3, 2)
+        );
+        assert!(item_bases.is_item_size(PointU16::new(2, 2)));
+        assert!(!item_bases.is_item_size(PointU16::new(4, 4)));
+    }
+}
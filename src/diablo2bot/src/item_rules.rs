@@ -0,0 +1,577 @@
+use std::cmp::Reverse;
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    enums::{item_class::ItemClass, item_tier::ItemTier, quality::Quality, stat_type::StatType},
+    item_bases::{ItemBase, ItemBases},
+    item_tooltip::ItemStat,
+    point_u16::PointU16,
+    structs::ItemsFilter,
+};
+
+const QUALITIES: [Quality; 7] = [
+    Quality::Grey,
+    Quality::Common,
+    Quality::Magic,
+    Quality::Rare,
+    Quality::Set,
+    Quality::Unique,
+    Quality::Rune,
+];
+
+/// A loot filter file, either a list of rules or a list of items with a flag per quality
+#[derive(Debug)]
+pub enum ItemFilterFile {
+    Rules(ItemRules),
+    Items(ItemsFilter),
+}
+
+impl<'de> Deserialize<'de> for ItemFilterFile {
+    // A file with a `rules` key is read as rules, so that a mistake in a rule is reported
+    // instead of the file not matching either format
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let table = toml::Table::deserialize(deserializer)?;
+
+        if table.contains_key("rules") {
+            table.try_into().map(Self::Rules).map_err(D::Error::custom)
+        } else {
+            table.try_into().map(Self::Items).map_err(D::Error::custom)
+        }
+    }
+}
+
+/// Rules which decide whether an item is picked up. The rule with the highest priority
+/// which matches an item is used, of the rules with the same priority the first one in the
+/// file. Items which don't match any rule are left on the ground.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ItemRules {
+    pub rules: Vec<ItemRule>,
+}
+
+/// The conditions of a rule. A rule matches an item when all of its conditions hold,
+/// a rule without conditions matches every item.
+///
+/// The ethereal status, the socket count and the stats are only known after the tooltip of an
+/// identified item has been read, rules with these conditions don't match items which are on
+/// the ground. The same rules decide which of the identified items are kept.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ItemRule {
+    pub pickup: bool,
+    #[serde(default)]
+    pub priority: i32,
+    /// The displayed name or the base name of the item, `*` matches any text and `?` matches
+    /// any single character, like "* Rune" or "*Potion"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qualities: Option<Vec<Quality>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tiers: Option<Vec<ItemTier>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<ItemClass>,
+    /// The categories of the items, like "helm" or "amul"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_types: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ethereal: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_sockets: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_sockets: Option<u8>,
+    /// The lowest rune which matches, like "Lem" or "Lem Rune"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_rune: Option<String>,
+    /// Only gold piles of at least this amount match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_gold: Option<u32>,
+    /// Whether the tooltip of the identified item has been read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identified: Option<bool>,
+    /// The lowest values of the stats, like `[{ stat = "FasterCastRate", min = 20 }]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Vec<StatRequirement>>,
+    /// The inventory size of the item, only needed for items which aren't in the item tables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_size: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub col_size: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct StatRequirement {
+    pub stat: StatType,
+    pub min: i32,
+}
+
+/// What is known about an item when the rules are checked
+pub struct ItemProperties<'a> {
+    /// the displayed name without the prefixes, or the name of an identified unique or set item
+    pub name: &'a str,
+    pub base_name: &'a str,
+    pub quality: Quality,
+    pub base: Option<&'a ItemBase>,
+    pub gold: Option<u32>,
+    pub ethereal: Option<bool>,
+    pub sockets: Option<u8>,
+    /// the stats read from the tooltip of the identified item
+    pub stats: Option<&'a [ItemStat]>,
+}
+
+impl ItemRules {
+    /// Converts a filter with a flag per quality. The entries of identified unique and set
+    /// items are given a higher priority than the entries of base items, so that they are
+    /// used over the entries of their base items. Gold piles of at least `min_gold_to_pickup`
+    /// are picked up.
+    pub fn from_items_filter(
+        items_filter: &ItemsFilter,
+        min_gold_to_pickup: u32,
+        item_bases: &ItemBases,
+    ) -> Self {
+        let mut names: Vec<&String> = items_filter.items.keys().collect();
+        names.sort();
+
+        let mut rules = vec![ItemRule {
+            pickup: true,
+            min_gold: Some(min_gold_to_pickup),
+            ..Default::default()
+        }];
+
+        for name in names {
+            let item_filter = &items_filter.items[name];
+
+            let priority = match item_bases.get(name) {
+                Some(_) => 0,
+                None => 1,
+            };
+
+            let (wanted, unwanted): (Vec<Quality>, Vec<Quality>) =
+                QUALITIES.into_iter().partition(|&quality| match quality {
+                    Quality::Grey => item_filter.grey,
+                    Quality::Common => item_filter.common,
+                    Quality::Magic => item_filter.magic,
+                    Quality::Rare => item_filter.rare,
+                    Quality::Set => item_filter.set,
+                    Quality::Unique => item_filter.unique,
+                    Quality::Rune => item_filter.rune,
+                });
+
+            for (pickup, qualities) in [(true, wanted), (false, unwanted)] {
+                if !qualities.is_empty() {
+                    rules.push(ItemRule {
+                        pickup,
+                        priority,
+                        name: Some(name.clone()),
+                        qualities: Some(qualities),
+                        row_size: item_filter.row_size,
+                        col_size: item_filter.col_size,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+
+        Self { rules }
+    }
+
+    /// Returns an error for the first rune in the rules which isn't in the item tables
+    pub fn validate(&self, item_bases: &ItemBases) -> Result<(), String> {
+        for rule in &self.rules {
+            if let Some(min_rune) = &rule.min_rune {
+                if get_rune_rank(min_rune, item_bases).is_none() {
+                    return Err(format!("Unknown rune in the item filter: {min_rune}"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn find_rule(&self, item: &ItemProperties, item_bases: &ItemBases) -> Option<&ItemRule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(item, item_bases))
+            .min_by_key(|rule| Reverse(rule.priority))
+    }
+}
+
+impl ItemRule {
+    pub fn matches(&self, item: &ItemProperties, item_bases: &ItemBases) -> bool {
+        if let Some(name) = &self.name {
+            if !matches_wildcard(name, item.name) && !matches_wildcard(name, item.base_name) {
+                return false;
+            }
+        }
+
+        if let Some(qualities) = &self.qualities {
+            if !qualities.contains(&item.quality) {
+                return false;
+            }
+        }
+
+        if let Some(tiers) = &self.tiers {
+            match item.base.and_then(|base| base.tier) {
+                Some(tier) if tiers.contains(&tier) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(class) = self.class {
+            if item.base.map(|base| base.class) != Some(class) {
+                return false;
+            }
+        }
+
+        if let Some(item_types) = &self.item_types {
+            match item.base {
+                Some(base) if item_types.contains(&base.item_type) => {}
+                _ => return false,
+            }
+        }
+
+        if self.ethereal.is_some() && self.ethereal != item.ethereal {
+            return false;
+        }
+
+        if let Some(min_sockets) = self.min_sockets {
+            if item.sockets.is_none_or(|sockets| sockets < min_sockets) {
+                return false;
+            }
+        }
+
+        if let Some(max_sockets) = self.max_sockets {
+            if item.sockets.is_none_or(|sockets| sockets > max_sockets) {
+                return false;
+            }
+        }
+
+        if let Some(min_rune) = &self.min_rune {
+            let rune_rank = item.base.and_then(|base| base.rune_rank);
+
+            match (rune_rank, get_rune_rank(min_rune, item_bases)) {
+                (Some(rune_rank), Some(min_rune_rank)) if rune_rank >= min_rune_rank => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(min_gold) = self.min_gold {
+            if item.gold.is_none_or(|gold| gold < min_gold) {
+                return false;
+            }
+        }
+
+        if self.identified.is_some() && self.identified != Some(item.stats.is_some()) {
+            return false;
+        }
+
+        if let Some(stat_requirements) = &self.stats {
+            let Some(stats) = item.stats else {
+                return false;
+            };
+
+            for stat_requirement in stat_requirements {
+                // An item can have several lines of the same stat, like a set bonus
+                let value: i32 = stats
+                    .iter()
+                    .filter(|stat| stat.stat_type == stat_requirement.stat)
+                    .map(|stat| stat.value)
+                    .sum();
+
+                if value < stat_requirement.min {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Returns the size from the rule, or from the item tables
+    pub fn get_size(&self, item: &ItemProperties) -> Option<PointU16> {
+        match (self.row_size, self.col_size) {
+            (Some(row_size), Some(col_size)) => {
+                Some(PointU16::new(u16::from(row_size), u16::from(col_size)))
+            }
+            _ => item.base.map(|base| base.size),
+        }
+    }
+}
+
+fn get_rune_rank(rune_name: &str, item_bases: &ItemBases) -> Option<u8> {
+    item_bases
+        .get(rune_name)
+        .or_else(|| item_bases.get(&format!("{rune_name} Rune")))
+        .and_then(|base| base.rune_rank)
+}
+
+pub fn matches_wildcard(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // The positions after the last `*` in the pattern and in the text, to backtrack to
+    let mut backtrack = None;
+    let mut p = 0;
+    let mut t = 0;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            p += 1;
+            backtrack = Some((p, t));
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p;
+            t = star_t + 1;
+            backtrack = Some((star_p, t));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{item_bases::item_bases_from_text, structs::ItemFilter};
+
+    fn item_bases() -> ItemBases {
+        item_bases_from_text(
+            &[
+                (
+                    ItemClass::Armor,
+                    "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\tnormcode\tubercode\tultracode\r\n\
+                     Cap\tcap\tcap\thelm\t2\t2\t2\tcap\txap\tuap\r\n\
+                     Shako\tuap\tuap\thelm\t2\t2\t2\tcap\txap\tuap\r\n",
+                ),
+                (
+                    ItemClass::Misc,
+                    "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\r\n\
+                     Tal Rune\tr07\tr07\trune\t1\t1\t\r\n\
+                     Lem Rune\tr20\tr20\trune\t1\t1\t\r\n\
+                     Ist Rune\tr24\tr24\trune\t1\t1\t\r\n",
+                ),
+            ],
+            &[],
+        )
+    }
+
+    fn item<'a>(name: &'a str, quality: Quality, item_bases: &'a ItemBases) -> ItemProperties<'a> {
+        ItemProperties {
+            name,
+            base_name: name,
+            quality,
+            base: item_bases.get(name),
+            gold: None,
+            ethereal: None,
+            sockets: None,
+            stats: None,
+        }
+    }
+
+    #[test]
+    fn test_item_rules() {
+        let item_bases = item_bases();
+
+        let item_rules: ItemRules = toml::from_str(
+            r#"
+            [[rules]]
+            pickup = true
+            class = "Armor"
+            tiers = ["Elite"]
+
+            [[rules]]
+            pickup = true
+            name = "* Rune"
+            min_rune = "Lem"
+
+            [[rules]]
+            pickup = false
+            priority = 1
+            name = "Ist Rune"
+
+            [[rules]]
+            pickup = true
+            min_gold = 5000
+            "#,
+        )
+        .unwrap();
+        assert_eq!(item_rules.validate(&item_bases), Ok(()));
+
+        let is_picked_up = |item: &ItemProperties| {
+            item_rules
+                .find_rule(item, &item_bases)
+                .is_some_and(|rule| rule.pickup)
+        };
+
+        assert!(is_picked_up(&item("Shako", Quality::Common, &item_bases)));
+        assert!(!is_picked_up(&item("Cap", Quality::Common, &item_bases)));
+        assert!(is_picked_up(&item("Lem Rune", Quality::Rune, &item_bases)));
+        assert!(!is_picked_up(&item("Tal Rune", Quality::Rune, &item_bases)));
+        assert!(!is_picked_up(&item("Ist Rune", Quality::Rune, &item_bases)));
+
+        let mut gold = item("6000 Gold", Quality::Common, &item_bases);
+        gold.gold = Some(6000);
+        assert!(is_picked_up(&gold));
+        gold.gold = Some(4000);
+        assert!(!is_picked_up(&gold));
+
+        let mut sockets_rule = ItemRule {
+            pickup: true,
+            min_sockets: Some(2),
+            ..Default::default()
+        };
+        let mut shako = item("Shako", Quality::Grey, &item_bases);
+        assert!(!sockets_rule.matches(&shako, &item_bases));
+        shako.sockets = Some(2);
+        assert!(sockets_rule.matches(&shako, &item_bases));
+        sockets_rule.ethereal = Some(true);
+        assert!(!sockets_rule.matches(&shako, &item_bases));
+
+        let stats_rule = ItemRule {
+            pickup: true,
+            stats: Some(vec![StatRequirement {
+                stat: StatType::AllSkills,
+                min: 2,
+            }]),
+            ..Default::default()
+        };
+        let identified_rule = ItemRule {
+            pickup: false,
+            identified: Some(true),
+            ..Default::default()
+        };
+        assert!(!stats_rule.matches(&shako, &item_bases));
+        assert!(!identified_rule.matches(&shako, &item_bases));
+        let stats = [ItemStat {
+            stat_type: StatType::AllSkills,
+            value: 2,
+        }];
+        shako.stats = Some(&stats);
+        assert!(stats_rule.matches(&shako, &item_bases));
+        assert!(identified_rule.matches(&shako, &item_bases));
+        shako.stats = Some(&[]);
+        assert!(!stats_rule.matches(&shako, &item_bases));
+
+        let invalid_rules = ItemRules {
+            rules: vec![ItemRule {
+                min_rune: Some("Foo".to_string()),
+                ..Default::default()
+            }],
+        };
+        assert!(invalid_rules.validate(&item_bases).is_err());
+    }
+
+    #[test]
+    fn test_from_items_filter() {
+        let item_bases = item_bases();
+
+        let item_filter = |unique| ItemFilter {
+            grey: false,
+            common: false,
+            magic: false,
+            rare: false,
+            set: false,
+            unique,
+            rune: false,
+            row_size: None,
+            col_size: None,
+        };
+        let items_filter = ItemsFilter {
+            items: HashMap::from([
+                ("Cap".to_string(), item_filter(true)),
+                ("Biggin's Bonnet".to_string(), item_filter(false)),
+            ]),
+        };
+
+        let item_rules = ItemRules::from_items_filter(&items_filter, 1000, &item_bases);
+
+        let cap = item("Cap", Quality::Unique, &item_bases);
+        let biggins_bonnet = ItemProperties {
+            name: "Biggin's Bonnet",
+            ..item("Cap", Quality::Unique, &item_bases)
+        };
+        let find_rule = |item: &ItemProperties| {
+            item_rules
+                .find_rule(item, &item_bases)
+                .map(|rule| rule.pickup)
+        };
+
+        assert_eq!(find_rule(&cap), Some(true));
+        assert_eq!(find_rule(&biggins_bonnet), Some(false));
+        assert_eq!(
+            find_rule(&item("Cap", Quality::Magic, &item_bases)),
+            Some(false)
+        );
+        assert_eq!(
+            find_rule(&item("Shako", Quality::Unique, &item_bases)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_item_filter_file() {
+        let file: ItemFilterFile = toml::from_str(
+            r#"
+            [[rules]]
+            pickup = true
+            stats = [{ stat = "FasterCastRate", min = 20 }]
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(file, ItemFilterFile::Rules(rules) if rules.rules.len() == 1));
+
+        let file: ItemFilterFile = toml::from_str(
+            r#"
+            [items."Cap"]
+            grey = false
+            common = false
+            magic = false
+            rare = false
+            set = false
+            unique = true
+            rune = false
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(file, ItemFilterFile::Items(items) if items.items.contains_key("Cap")));
+
+        // the mistake in the rule is reported, not that the file is no items filter
+        let error = toml::from_str::<ItemFilterFile>(
+            r#"
+            [[rules]]
+            pikup = true
+            "#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown field `pikup`"));
+
+        let error = toml::from_str::<ItemFilterFile>(
+            r#"
+            [[rules]]
+            pickup = true
+            stats = [{ stat = "FasterCastRate", minimum = 20 }]
+            "#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown field `minimum`"));
+    }
+
+    #[test]
+    fn test_matches_wildcard() {
+        assert!(matches_wildcard("* Rune", "Lem Rune"));
+        assert!(matches_wildcard("*Potion", "Super Healing Potion"));
+        assert!(matches_wildcard("?ap", "Cap"));
+        assert!(matches_wildcard("*", ""));
+        assert!(matches_wildcard("a*b*c", "aXbYbZc"));
+        assert!(!matches_wildcard("* Rune", "Lem"));
+        assert!(!matches_wildcard("Cap", "Caps"));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{
+    enums::{quality::Quality, stat_type::StatType},
+    item_rules::matches_wildcard,
+    structs::Item,
+};
+
+/// The texts of the stats, `#` is the value of the stat and `*` matches any text. The texts
+/// are compared without spaces and case, as the spaces of the matched text aren't reliable.
+const STAT_TEXTS: [(StatType, &str); 25] = [
+    (StatType::AllSkills, "+# to All Skills"),
+    (StatType::ClassSkills, "+# to * Skill Levels"),
+    (StatType::FasterCastRate, "+#% Faster Cast Rate"),
+    (StatType::FasterCastRate, "Faster Cast Rate #%"),
+    (StatType::IncreasedAttackSpeed, "+#% Increased Attack Speed"),
+    (StatType::FasterHitRecovery, "+#% Faster Hit Recovery"),
+    (StatType::FasterRunWalk, "+#% Faster Run/Walk"),
+    (StatType::Life, "+# to Life"),
+    (StatType::Mana, "+# to Mana"),
+    (StatType::Strength, "+# to Strength"),
+    (StatType::Dexterity, "+# to Dexterity"),
+    (StatType::Vitality, "+# to Vitality"),
+    (StatType::Energy, "+# to Energy"),
+    (StatType::AllResistances, "All Resistances +#"),
+    (StatType::FireResist, "Fire Resist +#%"),
+    (StatType::ColdResist, "Cold Resist +#%"),
+    (StatType::LightningResist, "Lightning Resist +#%"),
+    (StatType::PoisonResist, "Poison Resist +#%"),
+    (StatType::EnhancedDefense, "+#% Enhanced Defense"),
+    (StatType::EnhancedDamage, "+#% Enhanced Damage"),
+    (
+        StatType::MagicFind,
+        "#% Better Chance of Getting Magic Items",
+    ),
+    (StatType::GoldFind, "#% Extra Gold from Monsters"),
+    (StatType::LifeStolen, "#% Life Stolen per Hit"),
+    (StatType::ManaStolen, "#% Mana Stolen per Hit"),
+    (StatType::Defense, "Defense: #"),
+];
+
+const SOCKETED_TEXT: &str = "*Socketed (#)";
+const ETHEREAL_TEXT: &str = "ethereal";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemStat {
+    pub stat_type: StatType,
+    pub value: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TooltipLine {
+    pub text: String,
+    pub quality: Quality,
+}
+
+/// The text of the tooltip of an item, read line by line from the top
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemTooltip {
+    pub lines: Vec<TooltipLine>,
+    pub stats: Vec<ItemStat>,
+    pub ethereal: bool,
+    pub sockets: u8,
+}
+
+impl ItemTooltip {
+    /// Puts the matched texts which are on the same row together into lines
+    pub fn new(items: &[Item]) -> Self {
+        let mut items: Vec<&Item> = items.iter().collect();
+        items.sort_by_key(|item| item.point);
+
+        let mut lines: Vec<TooltipLine> = Vec::new();
+        let mut previous_row = None;
+
+        for item in items {
+            match lines.last_mut() {
+                Some(line) if previous_row == Some(item.point.row) => {
+                    line.text.push(' ');
+                    line.text.push_str(&item.name);
+                }
+                _ => lines.push(TooltipLine {
+                    text: item.name.clone(),
+                    quality: item.quality,
+                }),
+            }
+
+            previous_row = Some(item.point.row);
+        }
+
+        let stats = lines
+            .iter()
+            .filter_map(|line| parse_stat(&line.text))
+            .collect();
+
+        let ethereal = lines
+            .iter()
+            .any(|line| line.text.to_lowercase().contains(ETHEREAL_TEXT));
+
+        let sockets = lines
+            .iter()
+            .find_map(|line| match_stat_text(SOCKETED_TEXT, &line.text))
+            .map_or(0, |sockets| sockets as u8);
+
+        Self {
+            lines,
+            stats,
+            ethereal,
+            sockets,
+        }
+    }
+}
+
+pub fn parse_stat(text: &str) -> Option<ItemStat> {
+    STAT_TEXTS.iter().find_map(|&(stat_type, stat_text)| {
+        match_stat_text(stat_text, text).map(|value| ItemStat { stat_type, value })
+    })
+}
+
+// Replaces the first number of the text with `#` and compares it with the stat text
+fn match_stat_text(stat_text: &str, text: &str) -> Option<i32> {
+    let text = normalize(text);
+
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let end = text[start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(text.len(), |len| start + len);
+
+    let value = text[start..end].parse().ok()?;
+    let text = format!("{}#{}", &text[..start], &text[end..]);
+
+    if matches_wildcard(&normalize(stat_text), &text) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_u16::PointU16;
+
+    fn item(name: &str, quality: Quality, row: u16, col: u16) -> Item {
+        Item {
+            name: name.to_string(),
+            quality,
+            point: PointU16::new(row, col),
+        }
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = |stat_type, value| Some(ItemStat { stat_type, value });
+
+        assert_eq!(parse_stat("+2 to All Skills"), stat(StatType::AllSkills, 2));
+        assert_eq!(
+            parse_stat("+20% Faster Cast Rate"),
+            stat(StatType::FasterCastRate, 20)
+        );
+        assert_eq!(
+            parse_stat("Faster Cast Rate 20%"),
+            stat(StatType::FasterCastRate, 20)
+        );
+        assert_eq!(
+            parse_stat("+1 to Sorceress Skill Levels"),
+            stat(StatType::ClassSkills, 1)
+        );
+        assert_eq!(
+            parse_stat("35% Better Chance of Getting Magic Items"),
+            stat(StatType::MagicFind, 35)
+        );
+        assert_eq!(parse_stat("+30to Life"), stat(StatType::Life, 30));
+        assert_eq!(parse_stat("Durability: 12 of 12"), None);
+        assert_eq!(parse_stat("Required Level: 5"), None);
+    }
+
+    #[test]
+    fn test_item_tooltip() {
+        let tooltip = ItemTooltip::new(&[
+            item("Harlequin Crest", Quality::Unique, 10, 40),
+            item("Shako", Quality::Unique, 26, 60),
+            item("Defense:", Quality::Common, 42, 30),
+            item("141", Quality::Common, 42, 90),
+            item("+2 to All Skills", Quality::Magic, 58, 40),
+            item(
+                "Ethereal (Cannot be Repaired), Socketed (1)",
+                Quality::Magic,
+                74,
+                10,
+            ),
+        ]);
+
+        assert_eq!(tooltip.lines.len(), 5);
+        assert_eq!(tooltip.lines[0].text, "Harlequin Crest");
+        assert_eq!(tooltip.lines[2].text, "Defense: 141");
+        assert_eq!(
+            tooltip.stats,
+            [
+                ItemStat {
+                    stat_type: StatType::Defense,
+                    value: 141
+                },
+                ItemStat {
+                    stat_type: StatType::AllSkills,
+                    value: 2
+                },
+            ]
+        );
+        assert!(tooltip.ethereal);
+        assert_eq!(tooltip.sockets, 1);
+    }
+}
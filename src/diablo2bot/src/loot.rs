@@ -11,9 +11,13 @@ use crate::{
     },
     game::Game,
     game_interface_element_controller::GameInterfaceElementController,
+    item_bases::ItemBases,
+    item_rules::{ItemProperties, ItemRules},
     matrix::Matrix,
+    named_items::NamedItems,
     point_u16::PointU16,
-    structs::{Item, ItemsFilter},
+    runeword_targets::RunewordTargets,
+    structs::Item,
     table::Table,
     utils::sleep_frames,
     wait_while_moving,
@@ -103,47 +107,76 @@ fn get_potion_sort_value(name: &str) -> usize {
 
 fn filter_items(
     items: &[Item],
-    min_gold_to_pickup: u32,
-    items_filter: &ItemsFilter,
+    item_rules: &ItemRules,
+    runeword_targets: &RunewordTargets,
+    item_bases: &ItemBases,
+    named_items: &NamedItems,
     inventory: &Table,
 ) -> FilteredItems {
     let mut gold_piles = Vec::new();
     let mut potions = Vec::new();
     let mut other_items = Vec::new();
+    let mut runeword_items = Vec::new();
 
     for item in items {
-        if let Some(gold_item) = parse_gold_pile(item) {
-            if gold_item.amount >= min_gold_to_pickup {
-                gold_piles.push(gold_item);
+        let gold_pile = parse_gold_pile(item);
+
+        let (item_name, base_name) = match named_items.get(&item.name, item.quality) {
+            Some(named_item) => (item.name.clone(), named_item.base_name.clone()),
+            None => {
+                let item_name = remove_prefixes_from_item_name(&item.name);
+                (item_name.clone(), item_name)
+            }
+        };
+
+        let item_properties = ItemProperties {
+            name: &item_name,
+            base_name: &base_name,
+            quality: item.quality,
+            base: item_bases.get(&base_name),
+            gold: gold_pile.as_ref().map(|gold_pile| gold_pile.amount),
+            ethereal: None,
+            sockets: None,
+            stats: None,
+        };
+
+        let runeword_rule = runeword_targets.find_rule(&item_properties, item_bases);
+
+        let rule = runeword_rule.or_else(|| item_rules.find_rule(&item_properties, item_bases));
+
+        let rule = match rule {
+            Some(rule) => rule,
+            None => {
+                if gold_pile.is_none() {
+                    println!("Item: {item_name} does not match any rule of the item filter");
+                }
+                continue;
             }
+        };
+
+        if !rule.pickup {
+            continue;
+        }
+
+        if let Some(gold_pile) = gold_pile {
+            gold_piles.push(gold_pile);
+        } else if is_item_potion(item) {
+            potions.push(item.clone())
         } else {
-            let item_name = remove_prefixes_from_item_name(&item.name);
-
-            match items_filter.items.get(&item_name) {
-                Some(item_filter) => {
-                    let is_item_wanted = match item.quality {
-                        Quality::Grey => item_filter.grey,
-                        Quality::Common => item_filter.common,
-                        Quality::Magic => item_filter.magic,
-                        Quality::Rare => item_filter.rare,
-                        Quality::Set => item_filter.set,
-                        Quality::Unique => item_filter.unique,
-                        Quality::Rune => item_filter.rune,
-                    };
-
-                    if is_item_wanted {
-                        if is_item_potion(item) {
-                            potions.push(item.clone())
-                        } else if inventory.has_space_for_item(
-                            u32::from(item_filter.row_size),
-                            u32::from(item_filter.col_size),
-                        ) {
-                            other_items.push(item.clone());
-                        }
+            match rule.get_size(&item_properties) {
+                Some(size) => {
+                    if !inventory.has_space_for_item(u32::from(size.row), u32::from(size.col)) {
+                        continue;
+                    }
+
+                    if runeword_rule.is_some() {
+                        runeword_items.push(item.clone());
+                    } else {
+                        other_items.push(item.clone());
                     }
                 }
                 None => {
-                    println!("Item: {item_name} is not in the filter_items dictionary");
+                    println!("Item: {item_name} has no inventory size");
                 }
             }
         }
@@ -152,6 +185,10 @@ fn filter_items(
     gold_piles.sort_by(|a, b| b.amount.cmp(&a.amount));
     potions.sort_by_key(|p| get_potion_sort_value(&p.name));
 
+    // The runes and bases of the runewords are picked up first
+    runeword_items.extend(other_items);
+    let other_items = runeword_items;
+
     FilteredItems {
         gold_piles,
         potions,
@@ -375,8 +412,10 @@ fn get_filtered_items(g: &mut Game) -> FilteredItems {
 
     filter_items(
         &items,
-        g.profile.min_gold_to_pickup,
         &g.item_filter,
+        &g.runeword_targets,
+        &g.item_bases,
+        &g.named_items,
         &g.inventory,
     )
 }
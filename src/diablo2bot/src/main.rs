@@ -29,6 +29,8 @@ use health_mana::get_points_and_drink_potions_if_points_under_soft_limit;
 use identify_items::identify_items_at_deckard_cain;
 use identify_starting_act::identify_starting_act;
 use image::Image;
+use item_bases::ItemBases;
+use item_rules::{ItemFilterFile, ItemRules};
 use loot::get_inventory_table;
 use match_text_with_palette::{get_font_char_map, match_unique_text_with_palette};
 use matrix::Matrix;
@@ -38,6 +40,7 @@ use options::{enter_single_player_menu, open_game_menu, set_automap_options, set
 use output_controller::OutputController;
 use pattern_matcher_monsters::MonsterMatcherConfig;
 use point_u16::PointU16;
+use profile::SystemSettings;
 use route_walker::{is_last_element, walk_enum_route_with_end_state};
 use run::run;
 use screenshotter::Screenshotter;
@@ -46,7 +49,9 @@ use state_validator::{
     ValidationPixel,
 };
 use std::env;
+use std::io;
 use std::process::exit;
+use string_tables::StringTables;
 use toogle_health_and_mana_text::ensure_health_and_mana_text_is_toggled_on;
 use units::Milliseconds;
 use utils::{sleep_frame, sleep_millis};
@@ -82,6 +87,9 @@ mod identify_items;
 mod identify_starting_act;
 mod image;
 mod inventory;
+mod item_bases;
+mod item_rules;
+mod item_tooltip;
 mod level_name;
 mod logger;
 mod loot;
@@ -91,6 +99,7 @@ mod matrix;
 mod mouse_movement_program_stopper;
 mod move_items_to_stash;
 mod mpq_archives;
+mod named_items;
 mod options;
 mod output_controller;
 mod pal_pl2;
@@ -106,6 +115,8 @@ mod profile;
 mod quality_palette;
 mod route_walker;
 mod run;
+mod runeword_targets;
+mod runewords;
 mod screenshotter;
 mod skill_icon_getter;
 mod spell_caster;
@@ -191,12 +202,56 @@ fn exit_and_enter_new_game(g: &mut Game) -> Result<(), WrongGameStateError> {
     Ok(())
 }
 
+const USAGE: &str = "\
+Usage: diablo2bot <profile>
+       diablo2bot --convert-item-filter <profile> <new item filter>";
+
+/// Opens the archives of the game, with the files of the override folder used over them
+fn open_archives(system_settings: &SystemSettings) -> Archives {
+    let archives = Archives::new(&system_settings.diablo2_folder_path);
+
+    match &system_settings.mpq_override_folder_path {
+        Some(override_folder_path) => archives.with_override_folder(override_folder_path),
+        None => archives,
+    }
+}
+
+/// Writes the rules of an item filter with a flag per quality to a new item filter file
+fn convert_item_filter(profile_name: &str, item_rules_name: &str) {
+    let file_io = FileIo::new();
+    let profile = file_io.load_profile(profile_name).unwrap();
+    let system_settings = file_io.load_system_settings().unwrap();
+    let archives = open_archives(&system_settings);
+
+    let items_filter = match file_io.load_items_filter(&profile.item_filter).unwrap() {
+        ItemFilterFile::Items(items_filter) => items_filter,
+        ItemFilterFile::Rules(_) => {
+            println!("The item filter {} already has rules", profile.item_filter);
+            return;
+        }
+    };
+
+    let string_tables = StringTables::new(&archives);
+    let item_bases = ItemBases::new(&archives, &string_tables);
+
+    let item_rules =
+        ItemRules::from_items_filter(&items_filter, profile.min_gold_to_pickup, &item_bases);
+
+    match file_io.save_item_rules(item_rules_name, &item_rules) {
+        Ok(()) => println!("Wrote the item filter {item_rules_name}"),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            println!("The item filter {item_rules_name} already exists");
+        }
+        Err(e) => panic!("Couldn't write the item filter {item_rules_name}: {e}"),
+    }
+}
+
 fn start(profile_name: &str) -> Result<(), BotError> {
     let file_io = FileIo::new();
     let profile = file_io.load_profile(profile_name).unwrap();
     let system_settings = file_io.load_system_settings().unwrap();
     let bot_settings = file_io.load_bot_settings().unwrap();
-    let archives = Archives::new(&system_settings.diablo2_folder_path);
+    let archives = open_archives(&system_settings);
 
     let (game_screenshotter, output_controller) =
         start_diablo2(&bot_settings, &system_settings.diablo2_folder_path);
@@ -718,7 +773,7 @@ fn change_level(g: &mut Game, level: &str) -> Result<(), MovedToTownZoneError> {
         );
 
         g.monster_matcher = get_monster_tree(
-            &mut g.archives,
+            &g.archives,
             &g.file_io,
             level,
             &level_monsters_matcher_config,
@@ -731,9 +786,19 @@ fn change_level(g: &mut Game, level: &str) -> Result<(), MovedToTownZoneError> {
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    let profile_name = &args[1];
+    let profile_name = match &args[..] {
+        [flag, profile_name, item_rules_name] if flag == "--convert-item-filter" => {
+            convert_item_filter(profile_name, item_rules_name);
+            return;
+        }
+        [profile_name] if !profile_name.starts_with("--") => profile_name,
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    };
 
     match start(profile_name.as_str()) {
         Ok(_) => println!("Finishing bot run"),
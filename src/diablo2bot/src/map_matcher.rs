@@ -18,7 +18,7 @@ pub struct MapMatcher {
 }
 
 impl MapMatcher {
-    pub fn new(area_name: &str, archives: &mut Archives) -> Self {
+    pub fn new(area_name: &str, archives: &Archives) -> Self {
         const STEP_SIZE: PointU16 = PointU16 { row: 4, col: 8 };
 
         let excel_automap_raw_text = archives.extract_excel_automap_raw_text().unwrap();
@@ -30,21 +30,24 @@ impl MapMatcher {
 
         let mut sprites = FastHashSet::new();
 
+        // the sprites are decoded into the same matrix, only the new ones are copied
+        let mut sprite = Matrix::new_empty(PointU16 { row: 0, col: 0 });
+
         let matrices: Vec<MatrixAndPoints2> = area_map_sprite_ids
             .iter()
             .filter_map(|sprite_id| {
-                let matrix = Matrix::from_dc6_encoded_frame(
+                sprite.decode_dc6_frame(
                     &map_sprites_dc6_file.directions[0].encoded_frames[*sprite_id as usize],
                 );
 
-                let is_new_sprite = sprites.insert(&matrix.data);
+                let is_new_sprite = sprites.insert(&sprite.data);
 
                 if is_new_sprite {
-                    let point_values = matrix.get_non_zero_point_values();
+                    let point_values = sprite.get_non_zero_point_values();
 
                     Some(MatrixAndPoints2 {
                         sprite_id: *sprite_id,
-                        matrix,
+                        matrix: sprite.clone(),
                         point_values,
                     })
                 } else {
@@ -239,7 +242,7 @@ mod tests {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
 
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let folder_path = file_io.root.join("test_data").join("map").join("palette");
 
@@ -266,7 +269,7 @@ mod tests {
                 let folder_path2 = folder_path.clone();
                 let area_folder_name = folder_path2.file_name().unwrap().to_str().unwrap();
 
-                let map_matcher = MapMatcher::new(area_folder_name, &mut archives);
+                let map_matcher = MapMatcher::new(area_folder_name, &archives);
 
                 fs::read_dir(folder_path).unwrap().for_each(|folder| {
                     let folder = folder.unwrap();
@@ -36,15 +36,22 @@ impl Matrix {
     }
 
     pub fn from_dc6_encoded_frame(encoded_frame: &EncodedFrame) -> Self {
-        let decoded_frame = encoded_frame.decode();
+        let mut matrix = Self::new_empty(PointU16 { row: 0, col: 0 });
+        matrix.decode_dc6_frame(encoded_frame);
+        matrix
+    }
 
-        Self::new(
-            PointU16 {
-                row: decoded_frame.meta_data.height as u16,
-                col: decoded_frame.meta_data.width as u16,
-            },
-            decoded_frame.decoded_bytes,
-        )
+    /// Decodes a frame into the matrix, reusing its buffer, the matrix gets the size of the frame
+    pub fn decode_dc6_frame(&mut self, encoded_frame: &EncodedFrame) {
+        self.dims = PointU16 {
+            row: encoded_frame.meta_data.height as u16,
+            col: encoded_frame.meta_data.width as u16,
+        };
+
+        self.data.clear();
+        self.data.resize(self.dims.len() as usize, 0);
+
+        encoded_frame.decode_into(&mut self.data, u32::from(self.dims.col), 0, 0);
     }
 
     pub fn from_dc6_decoded_frame(decoded_frame: DecodedFrame) -> Self {
@@ -72,6 +72,13 @@ fn place_items_in_stash(g: &mut Game) -> Result<(), LowStashSpaceError> {
                     exit(1);
                 }
 
+                if !g.item_bases.is_item_size(item_size) {
+                    println!(
+                        "The measured item size {}x{} doesn't match the size of any item",
+                        item_size.row, item_size.col
+                    );
+                }
+
                 // Find where we can place the item in the stash
                 let stash_placement = stash.find_item_placement_in_cell_area(
                     u32::from(item_size.row),
@@ -109,6 +116,11 @@ fn place_items_in_stash(g: &mut Game) -> Result<(), LowStashSpaceError> {
                         inventory_before_item_pickup =
                             g.table_inventory_empty_matcher.match_from_matrix(&matrix);
                         stash = g.table_stash_empty_matcher.match_from_matrix(&matrix);
+
+                        if inventory_before_item_pickup.cells[row][col].is_none() {
+                            g.runeword_targets
+                                .stash_item(PointU16::new(row as u16, col as u16), &g.runewords);
+                        }
                     }
                     None => {
                         println!("No more space in stash");
@@ -3,28 +3,41 @@ use mpq_reader::Archive;
 
 use crate::{
     enums::{
-        act::Act,
         character_class::CharacterClass,
         errors::{ArchiveError, MpqFileFromUtf8Error},
         palette::Palette,
     },
     pal_pl2::PalPl2Bytes,
 };
-use std::{fmt, io::Error};
+use std::{
+    fmt, fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use super::{
     dc6_inventory_raw_bytes::Dc6InventoryRawBytes, dc6_raw_bytes::Dc6RawBytes,
     dc6_stash_raw_bytes::Dc6StashRawBytes, dcc_bytes::DccBytes, excel_automap::ExcelAutomapRawText,
-    excel_levels::ExcelLevelsRawText, excel_monstats::ExcelMonstatsRawText,
-    excel_monstats2::ExcelMonstats2RawText, excel_skills::ExcelSkillsRawText,
-    rand_transform_palettes::RandTransformRawBytes, strings_table::StringsTableRaw,
+    excel_gems::ExcelGemsRawText, excel_item_types::ExcelItemTypesRawText,
+    excel_items::ExcelItemsRawText, excel_levels::ExcelLevelsRawText,
+    excel_monstats::ExcelMonstatsRawText, excel_monstats2::ExcelMonstats2RawText,
+    excel_named_items::ExcelNamedItemsRawText, excel_runes::ExcelRunesRawText,
+    excel_skills::ExcelSkillsRawText, rand_transform_palettes::RandTransformRawBytes,
+    strings_table::StringsTableRaw,
 };
 
+/// The game archives, clones share the opened archives which are read through `&self`
+/// so they can be used from multiple threads.
+///
+/// A file is read from the override folder when it exists there, otherwise from the first
+/// archive containing it in the order patch_d2.mpq, d2exp.mpq, d2data.mpq as the game does.
 #[derive(Clone)]
 pub struct Archives {
-    data: Archive,
-    expansion: Archive,
-    patch: Archive,
+    data: Arc<Archive>,
+    expansion: Arc<Archive>,
+    patch: Arc<Archive>,
+    override_folder: Option<PathBuf>,
 }
 
 enum Utf8ExcelFileType {
@@ -32,12 +45,14 @@ enum Utf8ExcelFileType {
     Monstats2,
     AutoMap,
     Levels,
-}
-
-enum ArchiveType {
-    Data,
-    Expansion,
-    Patch,
+    Weapons,
+    Armor,
+    Misc,
+    UniqueItems,
+    SetItems,
+    Runes,
+    Gems,
+    ItemTypes,
 }
 
 pub enum StringTableType {
@@ -46,16 +61,6 @@ pub enum StringTableType {
     Patch,
 }
 
-impl StringTableType {
-    fn to_archive_type(&self) -> ArchiveType {
-        match self {
-            Self::Data => ArchiveType::Data,
-            Self::Expansion => ArchiveType::Expansion,
-            Self::Patch => ArchiveType::Patch,
-        }
-    }
-}
-
 impl fmt::Display for Utf8ExcelFileType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s = match self {
@@ -63,6 +68,14 @@ impl fmt::Display for Utf8ExcelFileType {
             Self::Monstats2 => "monstats2",
             Self::AutoMap => "AutoMap",
             Self::Levels => "Levels",
+            Self::Weapons => "weapons",
+            Self::Armor => "armor",
+            Self::Misc => "misc",
+            Self::UniqueItems => "UniqueItems",
+            Self::SetItems => "SetItems",
+            Self::Runes => "Runes",
+            Self::Gems => "gems",
+            Self::ItemTypes => "ItemTypes",
         };
 
         write!(f, "{s}")
@@ -88,22 +101,21 @@ impl Archives {
         let patch = Archive::open(format!("{folder_path}/patch_d2.mpq")).unwrap(); // TODO Throw error
 
         Self {
-            data,
-            expansion,
-            patch,
+            data: Arc::new(data),
+            expansion: Arc::new(expansion),
+            patch: Arc::new(patch),
+            override_folder: None,
         }
     }
 
-    pub fn extract_pal_pl2_bytes(&mut self, palette: Palette) -> Result<PalPl2Bytes, Error> {
-        let archive_type = match palette {
-            Palette::Act(Act::Act5) => ArchiveType::Expansion,
-            _ => ArchiveType::Data,
-        };
+    /// Files in the folder, like `data/global/excel/levels.txt`, replace the ones of the archives
+    pub fn with_override_folder<P: AsRef<Path>>(mut self, folder_path: P) -> Self {
+        self.override_folder = Some(folder_path.as_ref().to_path_buf());
+        self
+    }
 
-        let bytes = self.read_mpq_file(
-            archive_type,
-            &format!("data/global/palette/{palette}/Pal.PL2"),
-        );
+    pub fn extract_pal_pl2_bytes(&self, palette: Palette) -> Result<PalPl2Bytes, Error> {
+        let bytes = self.read_mpq_file(&format!("data/global/palette/{palette}/Pal.PL2"));
 
         match bytes {
             Ok(bytes) => Ok(PalPl2Bytes::new(bytes)),
@@ -111,9 +123,8 @@ impl Archives {
         }
     }
 
-    pub fn extract_rand_transform_palettes(&mut self) -> Result<RandTransformRawBytes, Error> {
-        let bytes =
-            self.read_mpq_file(ArchiveType::Data, "data/global/monsters/RandTransforms.dat");
+    pub fn extract_rand_transform_palettes(&self) -> Result<RandTransformRawBytes, Error> {
+        let bytes = self.read_mpq_file("data/global/monsters/RandTransforms.dat");
 
         match bytes {
             Ok(bytes) => Ok(RandTransformRawBytes::new(bytes)),
@@ -121,12 +132,12 @@ impl Archives {
         }
     }
 
-    pub fn extract_font_16_bytes(&mut self) -> Result<Dc6RawBytes, Error> {
-        self.extract_dc6_file_bytes(ArchiveType::Data, "data/local/font/latin/font16.DC6")
+    pub fn extract_font_16_bytes(&self) -> Result<Dc6RawBytes, Error> {
+        self.extract_dc6_file_bytes("data/local/font/latin/font16.DC6")
     }
 
-    pub fn extract_inventory_dc6_bytes(&mut self) -> Result<Dc6InventoryRawBytes, Error> {
-        let bytes = self.read_mpq_file(ArchiveType::Expansion, "data/global/ui/PANEL/invchar6.DC6");
+    pub fn extract_inventory_dc6_bytes(&self) -> Result<Dc6InventoryRawBytes, Error> {
+        let bytes = self.read_mpq_file("data/global/ui/PANEL/invchar6.DC6");
 
         match bytes {
             Ok(bytes) => Ok(Dc6InventoryRawBytes::new(Dc6RawBytes::new(bytes))),
@@ -134,11 +145,8 @@ impl Archives {
         }
     }
 
-    pub fn extract_stash_dc6_bytes(&mut self) -> Result<Dc6StashRawBytes, Error> {
-        let bytes = self.read_mpq_file(
-            ArchiveType::Expansion,
-            "data/global/ui/PANEL/TradeStash.DC6",
-        );
+    pub fn extract_stash_dc6_bytes(&self) -> Result<Dc6StashRawBytes, Error> {
+        let bytes = self.read_mpq_file("data/global/ui/PANEL/TradeStash.DC6");
 
         match bytes {
             Ok(bytes) => Ok(Dc6StashRawBytes::new(Dc6RawBytes::new(bytes))),
@@ -146,8 +154,8 @@ impl Archives {
         }
     }
 
-    pub fn extract_excel_skills_raw_text(&mut self) -> Result<ExcelSkillsRawText, Error> {
-        let bytes = self.read_mpq_file(ArchiveType::Expansion, "data/global/excel/skills.txt")?;
+    pub fn extract_excel_skills_raw_text(&self) -> Result<ExcelSkillsRawText, Error> {
+        let bytes = self.read_mpq_file("data/global/excel/skills.txt")?;
 
         let (decoded, _, had_errors) = WINDOWS_1250.decode(&bytes);
 
@@ -163,118 +171,136 @@ impl Archives {
     }
 
     pub fn extract_string_table(
-        &mut self,
+        &self,
         string_table_type: StringTableType,
     ) -> Result<StringsTableRaw, Error> {
         let file_path = format!("data/local/lng/eng/{}.tbl", string_table_type);
-        let bytes = self.read_mpq_file(string_table_type.to_archive_type(), &file_path)?;
+        let bytes = self.read_mpq_file(&file_path)?;
 
         Ok(StringsTableRaw::new(bytes))
     }
 
-    pub fn extract_excel_monstats_raw_text(
-        &mut self,
-    ) -> Result<ExcelMonstatsRawText, ArchiveError> {
+    pub fn extract_excel_monstats_raw_text(&self) -> Result<ExcelMonstatsRawText, ArchiveError> {
         Ok(ExcelMonstatsRawText::new(
             self.extract_excel_file_raw_text(Utf8ExcelFileType::Monstats)?,
         ))
     }
 
-    pub fn extract_excel_monstats2_raw_text(
-        &mut self,
-    ) -> Result<ExcelMonstats2RawText, ArchiveError> {
+    pub fn extract_excel_monstats2_raw_text(&self) -> Result<ExcelMonstats2RawText, ArchiveError> {
         Ok(ExcelMonstats2RawText::new(
             self.extract_excel_file_raw_text(Utf8ExcelFileType::Monstats2)?,
         ))
     }
 
-    pub fn extract_excel_automap_raw_text(&mut self) -> Result<ExcelAutomapRawText, ArchiveError> {
+    pub fn extract_excel_automap_raw_text(&self) -> Result<ExcelAutomapRawText, ArchiveError> {
         Ok(ExcelAutomapRawText::new(
             self.extract_excel_file_raw_text(Utf8ExcelFileType::AutoMap)?,
         ))
     }
 
-    pub fn extract_excel_levels_raw_text(&mut self) -> Result<ExcelLevelsRawText, ArchiveError> {
+    pub fn extract_excel_levels_raw_text(&self) -> Result<ExcelLevelsRawText, ArchiveError> {
         Ok(ExcelLevelsRawText::new(
             self.extract_excel_file_raw_text(Utf8ExcelFileType::Levels)?,
         ))
     }
 
-    fn get_excel_filetype_archive(&mut self, excel_file_type: Utf8ExcelFileType) -> ArchiveType {
-        // TODO I think we should try d2_patch first, then d2exp and then d2data
-        match excel_file_type {
-            Utf8ExcelFileType::AutoMap => ArchiveType::Expansion,
-            Utf8ExcelFileType::Monstats
-            | Utf8ExcelFileType::Monstats2
-            | Utf8ExcelFileType::Levels => ArchiveType::Patch,
-        }
+    pub fn extract_excel_weapons_raw_text(&self) -> Result<ExcelItemsRawText, ArchiveError> {
+        Ok(ExcelItemsRawText::new(
+            self.extract_excel_file_raw_text(Utf8ExcelFileType::Weapons)?,
+        ))
+    }
+
+    pub fn extract_excel_armor_raw_text(&self) -> Result<ExcelItemsRawText, ArchiveError> {
+        Ok(ExcelItemsRawText::new(
+            self.extract_excel_file_raw_text(Utf8ExcelFileType::Armor)?,
+        ))
+    }
+
+    pub fn extract_excel_misc_raw_text(&self) -> Result<ExcelItemsRawText, ArchiveError> {
+        Ok(ExcelItemsRawText::new(
+            self.extract_excel_file_raw_text(Utf8ExcelFileType::Misc)?,
+        ))
+    }
+
+    pub fn extract_excel_unique_items_raw_text(
+        &self,
+    ) -> Result<ExcelNamedItemsRawText, ArchiveError> {
+        Ok(ExcelNamedItemsRawText::new(
+            self.extract_excel_file_raw_text(Utf8ExcelFileType::UniqueItems)?,
+        ))
+    }
+
+    pub fn extract_excel_set_items_raw_text(&self) -> Result<ExcelNamedItemsRawText, ArchiveError> {
+        Ok(ExcelNamedItemsRawText::new(
+            self.extract_excel_file_raw_text(Utf8ExcelFileType::SetItems)?,
+        ))
+    }
+
+    pub fn extract_excel_runes_raw_text(&self) -> Result<ExcelRunesRawText, ArchiveError> {
+        Ok(ExcelRunesRawText::new(
+            self.extract_excel_file_raw_text(Utf8ExcelFileType::Runes)?,
+        ))
+    }
+
+    pub fn extract_excel_gems_raw_text(&self) -> Result<ExcelGemsRawText, ArchiveError> {
+        Ok(ExcelGemsRawText::new(
+            self.extract_excel_file_raw_text(Utf8ExcelFileType::Gems)?,
+        ))
+    }
+
+    pub fn extract_excel_item_types_raw_text(&self) -> Result<ExcelItemTypesRawText, ArchiveError> {
+        Ok(ExcelItemTypesRawText::new(
+            self.extract_excel_file_raw_text(Utf8ExcelFileType::ItemTypes)?,
+        ))
     }
 
     fn extract_excel_file_raw_text(
-        &mut self,
+        &self,
         excel_file_type: Utf8ExcelFileType,
     ) -> Result<String, ArchiveError> {
         let file_path = format!("data/global/excel/{}.txt", excel_file_type);
 
-        let archive = self.get_excel_filetype_archive(excel_file_type);
-
-        let bytes = self.read_mpq_file(archive, &file_path)?;
+        let bytes = self.read_mpq_file(&file_path)?;
 
         String::from_utf8(bytes).map_err(|e| {
             ArchiveError::MpqFileFromUtf8Error(MpqFileFromUtf8Error::new(&file_path, e))
         })
     }
 
-    pub fn extract_palshift_palettes_bytes(
-        &mut self,
-        monster_code: &str,
-    ) -> Result<Vec<u8>, Error> {
-        self.read_mpq_file(
-            ArchiveType::Data,
-            &format!("data/global/monsters/{monster_code}/COF/palshift.dat",),
-        )
+    pub fn extract_palshift_palettes_bytes(&self, monster_code: &str) -> Result<Vec<u8>, Error> {
+        self.read_mpq_file(&format!(
+            "data/global/monsters/{monster_code}/COF/palshift.dat",
+        ))
     }
 
     pub fn extract_item_inventory_sprite(
-        &mut self,
+        &self,
         item_file_name: &str,
     ) -> Result<Dc6RawBytes, Error> {
-        self.extract_dc6_file_bytes(
-            ArchiveType::Data,
-            &format!("data/global/items/{item_file_name}.DC6"),
-        )
+        self.extract_dc6_file_bytes(&format!("data/global/items/{item_file_name}.DC6"))
     }
 
-    pub fn extract_map_sprites(&mut self) -> Result<Dc6RawBytes, Error> {
-        self.extract_dc6_file_bytes(ArchiveType::Expansion, "data/global/ui/AUTOMAP/MaxiMap.dc6")
+    pub fn extract_map_sprites(&self) -> Result<Dc6RawBytes, Error> {
+        self.extract_dc6_file_bytes("data/global/ui/AUTOMAP/MaxiMap.dc6")
     }
 
-    pub fn extract_general_skill_icon_dc6_bytes(&mut self) -> Result<Dc6RawBytes, Error> {
-        self.extract_dc6_file_bytes(ArchiveType::Data, "data/global/ui/SPELLS/Skillicon.DC6")
+    pub fn extract_general_skill_icon_dc6_bytes(&self) -> Result<Dc6RawBytes, Error> {
+        self.extract_dc6_file_bytes("data/global/ui/SPELLS/Skillicon.DC6")
     }
 
     pub fn extract_class_skill_icon_dc6_bytes(
-        &mut self,
+        &self,
         class: CharacterClass,
     ) -> Result<Dc6RawBytes, Error> {
-        let archive_type = match class {
-            CharacterClass::Assassin | CharacterClass::Druid => ArchiveType::Expansion,
-            _ => ArchiveType::Data,
-        };
-
         let class_code = class.get_class_code();
 
         let file_path = format!("data/global/ui/SPELLS/{class_code}Skillicon.dc6");
 
-        self.extract_dc6_file_bytes(archive_type, &file_path)
+        self.extract_dc6_file_bytes(&file_path)
     }
 
-    fn extract_dc6_file_bytes(
-        &mut self,
-        archive_type: ArchiveType,
-        file_path: &str,
-    ) -> Result<Dc6RawBytes, Error> {
-        let bytes = self.read_mpq_file(archive_type, file_path);
+    fn extract_dc6_file_bytes(&self, file_path: &str) -> Result<Dc6RawBytes, Error> {
+        let bytes = self.read_mpq_file(file_path);
 
         match bytes {
             Ok(bytes) => Ok(Dc6RawBytes::new(bytes)),
@@ -282,19 +308,8 @@ impl Archives {
         }
     }
 
-    pub fn extract_dcc_file_bytes(&mut self, file_path: &str) -> Result<DccBytes, Error> {
-        match self._extract_dcc_file_bytes(ArchiveType::Data, file_path) {
-            Ok(bytes) => Ok(bytes),
-            Err(_) => self._extract_dcc_file_bytes(ArchiveType::Expansion, file_path),
-        }
-    }
-
-    fn _extract_dcc_file_bytes(
-        &mut self,
-        archive_type: ArchiveType,
-        file_path: &str,
-    ) -> Result<DccBytes, Error> {
-        let bytes = self.read_mpq_file(archive_type, file_path);
+    pub fn extract_dcc_file_bytes(&self, file_path: &str) -> Result<DccBytes, Error> {
+        let bytes = self.read_mpq_file(file_path);
 
         match bytes {
             Ok(bytes) => Ok(DccBytes::new(bytes)),
@@ -302,25 +317,128 @@ impl Archives {
         }
     }
 
-    fn read_mpq_file(
-        &mut self,
-        archive_type: ArchiveType,
-        file_name: &str,
-    ) -> Result<Vec<u8>, Error> {
-        let archive = self.get_archive(archive_type);
+    fn read_mpq_file(&self, file_name: &str) -> Result<Vec<u8>, Error> {
+        if let Some(path) = self.find_override_file(file_name) {
+            return fs::read(path);
+        }
 
-        let file = archive.open_file(file_name)?;
-        let mut buf: Vec<u8> = vec![0; file.size() as usize];
-        file.read(archive, &mut buf)?;
+        for archive in [&self.patch, &self.expansion, &self.data] {
+            match archive.read_file(file_name) {
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                result => return result,
+            }
+        }
 
-        Ok(buf)
+        Err(Error::new(ErrorKind::NotFound, file_name))
     }
 
-    fn get_archive(&mut self, archive_type: ArchiveType) -> &mut Archive {
-        match archive_type {
-            ArchiveType::Data => &mut self.data,
-            ArchiveType::Expansion => &mut self.expansion,
-            ArchiveType::Patch => &mut self.patch,
+    // the archive paths are case insensitive, so are the ones of the override folder
+    fn find_override_file(&self, file_name: &str) -> Option<PathBuf> {
+        let mut path = self.override_folder.clone()?;
+
+        for component in file_name.split(['/', '\\']).filter(|c| !c.is_empty()) {
+            let exact = path.join(component);
+
+            path = match exact.exists() {
+                true => exact,
+                false => fs::read_dir(&path)
+                    .ok()?
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .find(|entry| {
+                        entry
+                            .file_name()
+                            .is_some_and(|name| name.eq_ignore_ascii_case(component))
+                    })?,
+            };
         }
+
+        path.is_file().then_some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use mpq_reader::{ArchiveBuilder, FileOptions};
+
+    use super::Archives;
+
+    #[test]
+    fn test_layered_resolution() {
+        let folder = std::env::temp_dir().join(format!("archives_{}", std::process::id()));
+        let override_folder = folder.join("override");
+        fs::create_dir_all(override_folder.join("data/global/excel")).unwrap();
+
+        let archive_files = [
+            (
+                "d2data.mpq",
+                vec!["levels.txt", "skills.txt", "monstats.txt"],
+            ),
+            ("d2exp.mpq", vec!["levels.txt", "skills.txt"]),
+            ("patch_d2.mpq", vec!["levels.txt"]),
+        ];
+
+        for (archive_name, files) in archive_files {
+            let mut builder = ArchiveBuilder::new();
+
+            for file in files {
+                builder.add_file(
+                    &format!("data\\global\\excel\\{file}"),
+                    archive_name.as_bytes(),
+                    FileOptions::default(),
+                );
+            }
+
+            builder.write(folder.join(archive_name)).unwrap();
+        }
+
+        fs::write(
+            override_folder.join("data/global/excel/MonStats.txt"),
+            "override",
+        )
+        .unwrap();
+
+        let archives = Archives::new(folder.to_str().unwrap());
+
+        assert_eq!(
+            archives
+                .read_mpq_file("data/global/excel/levels.txt")
+                .unwrap(),
+            b"patch_d2.mpq"
+        );
+        assert_eq!(
+            archives
+                .read_mpq_file("data/global/excel/skills.txt")
+                .unwrap(),
+            b"d2exp.mpq"
+        );
+        assert_eq!(
+            archives
+                .read_mpq_file("data/global/excel/monstats.txt")
+                .unwrap(),
+            b"d2data.mpq"
+        );
+        assert!(archives
+            .read_mpq_file("data/global/excel/missiles.txt")
+            .is_err());
+
+        let archives = archives.with_override_folder(&override_folder);
+
+        assert_eq!(
+            archives
+                .read_mpq_file("data\\global\\excel\\monstats.txt")
+                .unwrap(),
+            b"override"
+        );
+        assert_eq!(
+            archives
+                .read_mpq_file("data/global/excel/levels.txt")
+                .unwrap(),
+            b"patch_d2.mpq"
+        );
+
+        fs::remove_dir_all(folder).unwrap();
     }
 }
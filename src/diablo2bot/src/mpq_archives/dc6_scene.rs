@@ -1,52 +1,20 @@
-use dc6_decoder::DecodedFrame;
+use dc6_decoder::{Dc6, DecodedFrame};
 
-use crate::{matrix::Matrix, point_u16::PointU16};
+use crate::matrix::Matrix;
 
+/// A panel split into the top left, top right, bottom left and bottom right frames
 pub struct Dc6Scene {
-    pub top_left: DecodedFrame,
-    pub top_right: DecodedFrame,
-    pub bottom_left: DecodedFrame,
-    pub bottom_right: DecodedFrame,
+    frame: DecodedFrame,
 }
 
 impl Dc6Scene {
-    pub fn convert_to_matrix(self) -> Matrix {
-        let dims = PointU16 {
-            row: (self.top_left.meta_data.height + self.bottom_left.meta_data.height) as u16,
-            col: (self.top_left.meta_data.width + self.top_right.meta_data.width) as u16,
-        };
-
-        let mut matrix = Matrix::new_empty(dims);
-
-        matrix.insert_sub_matrix(
-            PointU16 {
-                row: 0,
-                col: self.top_left.meta_data.width as u16,
-            },
-            &Matrix::from_dc6_decoded_frame(self.top_right),
-        );
-
-        matrix.insert_sub_matrix(
-            PointU16 {
-                row: self.top_left.meta_data.height as u16,
-                col: 0,
-            },
-            &Matrix::from_dc6_decoded_frame(self.bottom_left),
-        );
-
-        matrix.insert_sub_matrix(
-            PointU16 {
-                row: self.top_left.meta_data.height as u16,
-                col: self.top_left.meta_data.width as u16,
-            },
-            &Matrix::from_dc6_decoded_frame(self.bottom_right),
-        );
-
-        matrix.insert_sub_matrix(
-            PointU16 { row: 0, col: 0 },
-            &Matrix::from_dc6_decoded_frame(self.top_left),
-        );
+    pub fn new(dc6: &Dc6, first_frame: usize) -> Self {
+        Self {
+            frame: dc6.directions[0].compose_grid(first_frame..first_frame + 4, 2),
+        }
+    }
 
-        matrix
+    pub fn convert_to_matrix(self) -> Matrix {
+        Matrix::from_dc6_decoded_frame(self.frame)
     }
 }
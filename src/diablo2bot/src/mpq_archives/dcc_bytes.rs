@@ -1,4 +1,4 @@
-use dcc_decoder::Dcc;
+use dcc_decoder::{DccError, DccReader};
 
 pub struct DccBytes {
     bytes: Vec<u8>,
@@ -9,7 +9,8 @@ impl DccBytes {
         Self { bytes }
     }
 
-    pub fn parse(&self) -> Dcc {
-        Dcc::new(&self.bytes)
+    /// Parses the header, the directions are decoded on demand
+    pub fn parse(&self) -> Result<DccReader<'_>, DccError> {
+        DccReader::try_new(&self.bytes)
     }
 }
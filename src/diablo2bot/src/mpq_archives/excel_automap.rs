@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use serde::Deserialize;
+
+use super::excel_table::ExcelTable;
 
 pub struct ExcelAutomapRawText {
     text: String,
@@ -14,9 +16,29 @@ impl ExcelAutomapRawText {
     }
 }
 
+#[derive(Deserialize)]
 struct Row<'raw_text> {
+    #[serde(rename = "LevelName")]
     level_name: &'raw_text str,
-    cells: [Option<u32>; 4],
+    #[serde(rename = "Cel1")]
+    cell1: Option<i32>,
+    #[serde(rename = "Cel2")]
+    cell2: Option<i32>,
+    #[serde(rename = "Cel3")]
+    cell3: Option<i32>,
+    #[serde(rename = "Cel4")]
+    cell4: Option<i32>,
+}
+
+impl Row<'_> {
+    /// The sprite ids of the cells, -1 means that the cell has no sprite
+    fn get_cells(&self) -> impl Iterator<Item = u32> {
+        [self.cell1, self.cell2, self.cell3, self.cell4]
+            .into_iter()
+            .flatten()
+            .filter(|&id| id > 0)
+            .map(|id| id as u32)
+    }
 }
 
 pub struct ExcelAutomap<'raw_text> {
@@ -25,63 +47,11 @@ pub struct ExcelAutomap<'raw_text> {
 
 impl<'raw_text> ExcelAutomap<'raw_text> {
     pub fn new(text: &'raw_text str) -> Self {
-        let separator = '\t';
-
-        let mut line_iter = text.split("\r\n");
-
-        let mut column_headers = HashMap::new();
-        let mut num_columns = 0;
-
-        if let Some(header_line) = line_iter.next() {
-            for (i, header) in header_line.split(separator).enumerate() {
-                num_columns += 1;
-                column_headers.insert(header, i);
-            }
-        }
-
-        let level_name_col_id = column_headers["LevelName"];
-
-        let cell_col_ids = [
-            column_headers["Cel1"],
-            column_headers["Cel2"],
-            column_headers["Cel3"],
-            column_headers["Cel4"],
-        ];
-
-        let mut row: Vec<&str> = vec![""; num_columns];
-
-        let mut parsed_rows = Vec::new();
-
-        for line in line_iter {
-            for (i, column) in line.split(separator).enumerate() {
-                row[i] = column;
-            }
-
-            let level_name = row[level_name_col_id];
-
-            if level_name == "Expansion" {
-                continue;
-            }
-
-            let mut cells = [None; 4];
-
-            for (i, cell_col_id) in cell_col_ids.iter().enumerate() {
-                cells[i] = match row[*cell_col_id].parse::<i32>() {
-                    Ok(number) => {
-                        if number > 0 {
-                            Some(number as u32)
-                        } else {
-                            None
-                        }
-                    }
-                    Err(_) => None,
-                };
-            }
-
-            parsed_rows.push(Row { level_name, cells });
-        }
+        let rows = ExcelTable::new(text)
+            .deserialize()
+            .unwrap_or_else(|e| panic!("Invalid automap.txt: {e}"));
 
-        Self { rows: parsed_rows }
+        Self { rows }
     }
 
     pub fn get_map_sprite_ids_for_area(&self, area_name: &str) -> Vec<u32> {
@@ -89,9 +59,7 @@ impl<'raw_text> ExcelAutomap<'raw_text> {
 
         for row in &self.rows {
             if row.level_name == area_name {
-                for id in row.cells.into_iter().flatten() {
-                    ids.push(id);
-                }
+                ids.extend(row.get_cells());
             }
         }
 
@@ -112,7 +80,7 @@ mod tests {
     fn test_excel_automap() {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let excel_automap_raw_text = archives.extract_excel_automap_raw_text().unwrap();
         let now = Instant::now();
@@ -0,0 +1,39 @@
+use serde::Deserialize;
+
+use super::excel_table::ExcelTable;
+
+/// The text of gems.txt, which contains the gems and the runes
+pub struct ExcelGemsRawText {
+    text: String,
+}
+
+impl ExcelGemsRawText {
+    pub fn new(text: String) -> Self {
+        Self { text }
+    }
+
+    pub fn parse(&self) -> ExcelGems<'_> {
+        ExcelGems::new(&self.text)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Row<'raw_text> {
+    pub code: &'raw_text str,
+    /// the letters which the rune adds to the name of a socketed item, like "Tal"
+    pub letter: Option<&'raw_text str>,
+}
+
+pub struct ExcelGems<'raw_text> {
+    pub rows: Vec<Row<'raw_text>>,
+}
+
+impl<'raw_text> ExcelGems<'raw_text> {
+    pub fn new(text: &'raw_text str) -> Self {
+        let rows = ExcelTable::new(text)
+            .deserialize()
+            .unwrap_or_else(|e| panic!("Invalid gems.txt: {e}"));
+
+        Self { rows }
+    }
+}
@@ -0,0 +1,49 @@
+use serde::Deserialize;
+
+use super::excel_table::ExcelTable;
+
+/// The text of itemtypes.txt
+pub struct ExcelItemTypesRawText {
+    text: String,
+}
+
+impl ExcelItemTypesRawText {
+    pub fn new(text: String) -> Self {
+        Self { text }
+    }
+
+    pub fn parse(&self) -> ExcelItemTypes<'_> {
+        ExcelItemTypes::new(&self.text)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Row<'raw_text> {
+    #[serde(rename = "Code")]
+    pub code: &'raw_text str,
+    #[serde(rename = "Equiv1")]
+    equiv1: Option<&'raw_text str>,
+    #[serde(rename = "Equiv2")]
+    equiv2: Option<&'raw_text str>,
+}
+
+impl<'raw_text> Row<'raw_text> {
+    /// The item types which this item type is a kind of, like "weap" for "swor"
+    pub fn get_parents(&self) -> impl Iterator<Item = &'raw_text str> {
+        [self.equiv1, self.equiv2].into_iter().flatten()
+    }
+}
+
+pub struct ExcelItemTypes<'raw_text> {
+    pub rows: Vec<Row<'raw_text>>,
+}
+
+impl<'raw_text> ExcelItemTypes<'raw_text> {
+    pub fn new(text: &'raw_text str) -> Self {
+        let rows = ExcelTable::new(text)
+            .deserialize()
+            .unwrap_or_else(|e| panic!("Invalid itemtypes.txt: {e}"));
+
+        Self { rows }
+    }
+}
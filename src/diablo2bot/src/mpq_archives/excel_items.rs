@@ -0,0 +1,104 @@
+use serde::Deserialize;
+
+use crate::enums::item_tier::ItemTier;
+
+use super::excel_table::ExcelTable;
+
+/// The text of weapons.txt, armor.txt or misc.txt, they share the columns which are used
+pub struct ExcelItemsRawText {
+    text: String,
+}
+
+impl ExcelItemsRawText {
+    pub fn new(text: String) -> Self {
+        Self { text }
+    }
+
+    pub fn parse(&self) -> ExcelItems<'_> {
+        ExcelItems::new(&self.text)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Row<'raw_text> {
+    pub name: &'raw_text str,
+    pub code: &'raw_text str,
+    /// the key of the displayed name in the string tables
+    #[serde(rename = "namestr")]
+    pub name_string_id: &'raw_text str,
+    #[serde(rename = "type")]
+    pub item_type: &'raw_text str,
+    #[serde(rename = "invwidth")]
+    pub inventory_width: u8,
+    #[serde(rename = "invheight")]
+    pub inventory_height: u8,
+    #[serde(rename = "gemsockets")]
+    pub max_sockets: Option<u8>,
+    // misc.txt doesn't have the tier columns
+    #[serde(rename = "normcode", borrow)]
+    normal_code: Option<&'raw_text str>,
+    #[serde(rename = "ubercode", borrow)]
+    exceptional_code: Option<&'raw_text str>,
+    #[serde(rename = "ultracode", borrow)]
+    elite_code: Option<&'raw_text str>,
+}
+
+impl Row<'_> {
+    pub fn get_tier(&self) -> Option<ItemTier> {
+        let code = Some(self.code);
+
+        if code == self.normal_code {
+            Some(ItemTier::Normal)
+        } else if code == self.exceptional_code {
+            Some(ItemTier::Exceptional)
+        } else if code == self.elite_code {
+            Some(ItemTier::Elite)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct ExcelItems<'raw_text> {
+    pub rows: Vec<Row<'raw_text>>,
+}
+
+impl<'raw_text> ExcelItems<'raw_text> {
+    pub fn new(text: &'raw_text str) -> Self {
+        let rows = ExcelTable::new(text)
+            .deserialize()
+            .unwrap_or_else(|e| panic!("Invalid item table: {e}"));
+
+        Self { rows }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enums::item_tier::ItemTier;
+
+    use super::ExcelItems;
+
+    #[test]
+    fn test_excel_items() {
+        let text = "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\tnormcode\tubercode\tultracode\r\n\
+                    Cap\tcap\tcap\thelm\t2\t2\t2\tcap\txap\tuap\r\n\
+                    Expansion\r\n\
+                    Shako\tuap\tuap\thelm\t2\t2\t2\tcap\txap\tuap\r\n";
+
+        let items = ExcelItems::new(text);
+
+        assert_eq!(items.rows.len(), 2);
+        assert_eq!(items.rows[0].get_tier(), Some(ItemTier::Normal));
+        assert_eq!(items.rows[1].get_tier(), Some(ItemTier::Elite));
+        assert_eq!(items.rows[1].max_sockets, Some(2));
+
+        let misc = ExcelItems::new(
+            "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\r\n\
+             El Rune\tr01\tr01\trune\t1\t1\t\r\n",
+        );
+
+        assert_eq!(misc.rows[0].get_tier(), None);
+        assert_eq!(misc.rows[0].max_sockets, None);
+    }
+}
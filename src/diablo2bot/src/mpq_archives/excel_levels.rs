@@ -1,12 +1,13 @@
-use std::collections::HashMap;
-
 use crate::{
     constants::zone_names::{STONY_FIELD, TAMOE_HIGHLAND},
     enums::game_difficulty::GameDifficulty,
     level_name::LevelName,
 };
 
-use super::excel_monstats::ExcelMonstats;
+use super::{
+    excel_monstats::ExcelMonstats,
+    excel_table::{ExcelError, ExcelRow, ExcelTable},
+};
 
 pub struct ExcelLevelsRawText {
     text: String,
@@ -34,65 +35,32 @@ pub struct ExcelLevels<'raw_text> {
 
 impl<'raw_text> ExcelLevels<'raw_text> {
     pub fn new(text: &'raw_text str) -> Self {
-        let separator = '\t';
-
-        let mut line_iter = text.split("\r\n");
-
-        let mut column_headers = HashMap::new();
-        let mut num_columns = 0;
-
-        if let Some(header_line) = line_iter.next() {
-            for (i, header) in header_line.split(separator).enumerate() {
-                num_columns += 1;
-                column_headers.insert(header, i);
-            }
-        }
-
-        let area_name_col_id = column_headers["Name"];
-        let level_name_col_id = column_headers["LevelName"];
-        let mon1 = column_headers["mon1"];
-        let nmon1 = column_headers["nmon1"];
-
-        let mut row: Vec<&str> = vec![""; num_columns];
+        let table = ExcelTable::new(text);
 
-        let mut parsed_rows = Vec::new();
+        let rows = table
+            .rows()
+            .filter(|row| row.get::<&str>("Name") != Ok("Null"))
+            .map(|row| Self::parse_row(&row))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| panic!("Invalid levels.txt: {e}"));
 
-        for line in line_iter {
-            for (i, column) in line.split(separator).enumerate() {
-                row[i] = column;
-            }
-
-            let area_name = row[area_name_col_id];
-
-            if area_name == "Expansion" || area_name == "Null" {
-                continue;
-            }
-
-            let level_name = row[level_name_col_id];
-
-            let mut monster_names_normal = [None; 10];
-            let mut monster_names_nightmare_hell = [None; 10];
-
-            for i in 0..10 {
-                let monster_name = row[mon1 + i];
-                if !monster_name.is_empty() {
-                    monster_names_normal[i] = Some(monster_name);
-                }
+        Self { rows }
+    }
 
-                let monster_name = row[nmon1 + i];
-                if !monster_name.is_empty() {
-                    monster_names_nightmare_hell[i] = Some(monster_name);
-                }
-            }
+    fn parse_row(row: &ExcelRow<'_, 'raw_text>) -> Result<Row<'raw_text>, ExcelError> {
+        let mut monster_names_normal = [None; 10];
+        let mut monster_names_nightmare_hell = [None; 10];
 
-            parsed_rows.push(Row {
-                level_name,
-                monster_names_normal,
-                monster_names_nightmare_hell,
-            })
+        for i in 0..10 {
+            monster_names_normal[i] = row.get(&format!("mon{}", i + 1))?;
+            monster_names_nightmare_hell[i] = row.get(&format!("nmon{}", i + 1))?;
         }
 
-        Self { rows: parsed_rows }
+        Ok(Row {
+            level_name: row.get("LevelName")?,
+            monster_names_normal,
+            monster_names_nightmare_hell,
+        })
     }
 
     fn get_extra_monster_ids(level_name: &str) -> Vec<&'raw_text str> {
@@ -120,8 +88,7 @@ impl<'raw_text> ExcelLevels<'raw_text> {
         monster_ids
             .iter()
             .map(|&monster_id| monstats.get_row(monster_id))
-            .flat_map(|monstats_row| monstats_row.minion_ids.iter().flatten())
-            .cloned()
+            .flat_map(|monstats_row| monstats_row.minion_ids().into_iter().flatten())
             .collect()
     }
 
@@ -168,7 +135,7 @@ mod tests {
     fn test_excel_levels() {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let excel_levels_raw_text = archives.extract_excel_levels_raw_text().unwrap();
         let now = Instant::now();
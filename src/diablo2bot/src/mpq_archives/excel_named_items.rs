@@ -0,0 +1,42 @@
+use serde::Deserialize;
+
+use super::excel_table::ExcelTable;
+
+/// The text of uniqueitems.txt or setitems.txt
+pub struct ExcelNamedItemsRawText {
+    text: String,
+}
+
+impl ExcelNamedItemsRawText {
+    pub fn new(text: String) -> Self {
+        Self { text }
+    }
+
+    pub fn parse(&self) -> ExcelNamedItems<'_> {
+        ExcelNamedItems::new(&self.text)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Row<'raw_text> {
+    /// the key of the displayed name in the string tables
+    #[serde(rename = "index")]
+    pub name_string_id: &'raw_text str,
+    /// the code of the base item, the column is named "item" in setitems.txt
+    #[serde(alias = "item")]
+    pub code: &'raw_text str,
+}
+
+pub struct ExcelNamedItems<'raw_text> {
+    pub rows: Vec<Row<'raw_text>>,
+}
+
+impl<'raw_text> ExcelNamedItems<'raw_text> {
+    pub fn new(text: &'raw_text str) -> Self {
+        let rows = ExcelTable::new(text)
+            .deserialize()
+            .unwrap_or_else(|e| panic!("Invalid named item table: {e}"));
+
+        Self { rows }
+    }
+}
@@ -0,0 +1,97 @@
+use serde::Deserialize;
+
+use super::excel_table::ExcelTable;
+
+/// The text of runes.txt, which contains the runewords
+pub struct ExcelRunesRawText {
+    text: String,
+}
+
+impl ExcelRunesRawText {
+    pub fn new(text: String) -> Self {
+        Self { text }
+    }
+
+    pub fn parse(&self) -> ExcelRunes<'_> {
+        ExcelRunes::new(&self.text)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Row<'raw_text> {
+    /// the key of the displayed name in the string tables, like "Runeword1"
+    #[serde(rename = "Name")]
+    pub name_string_id: &'raw_text str,
+    #[serde(rename = "Rune Name")]
+    pub name: &'raw_text str,
+    /// whether the runeword can be made in the game
+    pub complete: bool,
+    itype1: Option<&'raw_text str>,
+    itype2: Option<&'raw_text str>,
+    itype3: Option<&'raw_text str>,
+    itype4: Option<&'raw_text str>,
+    itype5: Option<&'raw_text str>,
+    itype6: Option<&'raw_text str>,
+    etype1: Option<&'raw_text str>,
+    etype2: Option<&'raw_text str>,
+    etype3: Option<&'raw_text str>,
+    #[serde(rename = "Rune1")]
+    rune1: Option<&'raw_text str>,
+    #[serde(rename = "Rune2")]
+    rune2: Option<&'raw_text str>,
+    #[serde(rename = "Rune3")]
+    rune3: Option<&'raw_text str>,
+    #[serde(rename = "Rune4")]
+    rune4: Option<&'raw_text str>,
+    #[serde(rename = "Rune5")]
+    rune5: Option<&'raw_text str>,
+    #[serde(rename = "Rune6")]
+    rune6: Option<&'raw_text str>,
+}
+
+impl<'raw_text> Row<'raw_text> {
+    /// The item types which can be a base of the runeword, like "swor" or "shld"
+    pub fn get_item_types(&self) -> impl Iterator<Item = &'raw_text str> {
+        [
+            self.itype1,
+            self.itype2,
+            self.itype3,
+            self.itype4,
+            self.itype5,
+            self.itype6,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// The item types which can't be a base of the runeword, even when they are one of the
+    /// item types
+    pub fn get_excluded_item_types(&self) -> impl Iterator<Item = &'raw_text str> {
+        [self.etype1, self.etype2, self.etype3]
+            .into_iter()
+            .flatten()
+    }
+
+    /// The codes of the runes in the order they are socketed, like "r07"
+    pub fn get_rune_codes(&self) -> impl Iterator<Item = &'raw_text str> {
+        [
+            self.rune1, self.rune2, self.rune3, self.rune4, self.rune5, self.rune6,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+pub struct ExcelRunes<'raw_text> {
+    pub rows: Vec<Row<'raw_text>>,
+}
+
+impl<'raw_text> ExcelRunes<'raw_text> {
+    pub fn new(text: &'raw_text str) -> Self {
+        let rows = ExcelTable::new(text)
+            .deserialize()
+            .unwrap_or_else(|e| panic!("Invalid runes.txt: {e}"));
+
+        Self { rows }
+    }
+}
@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
+use serde::Deserialize;
+
 use crate::enums::character_class::CharacterClass;
 
+use super::excel_table::ExcelTable;
+
 pub struct ExcelSkillsRawText {
     text: String,
 }
@@ -21,10 +25,29 @@ pub struct SkillData<'skill_names> {
     pub icon_sprite_id: u32,
 }
 
+#[derive(Deserialize)]
 struct Row<'raw_text> {
+    #[serde(rename = "skill")]
     skill_name: &'raw_text str,
+    #[serde(rename = "IconCel")]
     skill_icon_sprite_id: u32,
-    skill_class: Option<CharacterClass>,
+    #[serde(rename = "charclass")]
+    skill_class: &'raw_text str,
+}
+
+impl Row<'_> {
+    fn get_skill_class(&self) -> Option<CharacterClass> {
+        match self.skill_class {
+            "ama" => Some(CharacterClass::Amazon),
+            "sor" => Some(CharacterClass::Sorceress),
+            "nec" => Some(CharacterClass::Necromancer),
+            "pal" => Some(CharacterClass::Paladin),
+            "bar" => Some(CharacterClass::Barbarian),
+            "dru" => Some(CharacterClass::Druid),
+            "ass" => Some(CharacterClass::Assassin),
+            _ => None,
+        }
+    }
 }
 
 pub struct ExcelSkills<'raw_text> {
@@ -33,60 +56,11 @@ pub struct ExcelSkills<'raw_text> {
 
 impl<'raw_text> ExcelSkills<'raw_text> {
     pub fn new(text: &'raw_text str) -> Self {
-        let separator = '\t';
-
-        let mut line_iter = text.split("\r\n");
-
-        let mut column_headers = HashMap::new();
-        let mut num_columns = 0;
-
-        if let Some(header_line) = line_iter.next() {
-            for (i, header) in header_line.split(separator).enumerate() {
-                num_columns += 1;
-                column_headers.insert(header, i);
-            }
-        }
-
-        let skill_name_col_id = column_headers["skill"];
-        let skill_icon_sprite_id_col_id = column_headers["IconCel"];
-        let skill_class_col_id = column_headers["charclass"];
-
-        let mut row: Vec<&str> = vec![""; num_columns];
-
-        let mut parsed_rows = Vec::new();
-
-        for line in line_iter {
-            for (i, column) in line.split(separator).enumerate() {
-                row[i] = column;
-            }
-
-            let skill_name = row[skill_name_col_id];
-
-            if skill_name == "Expansion" {
-                continue;
-            }
-
-            let skill_icon_sprite_id = row[skill_icon_sprite_id_col_id].parse::<u32>().unwrap();
-
-            let skill_class = match row[skill_class_col_id] {
-                "ama" => Some(CharacterClass::Amazon),
-                "sor" => Some(CharacterClass::Sorceress),
-                "nec" => Some(CharacterClass::Necromancer),
-                "pal" => Some(CharacterClass::Paladin),
-                "bar" => Some(CharacterClass::Barbarian),
-                "dru" => Some(CharacterClass::Druid),
-                "ass" => Some(CharacterClass::Assassin),
-                _ => None,
-            };
-
-            parsed_rows.push(Row {
-                skill_name,
-                skill_icon_sprite_id,
-                skill_class,
-            })
-        }
+        let rows = ExcelTable::new(text)
+            .deserialize()
+            .unwrap_or_else(|e| panic!("Invalid skills.txt: {e}"));
 
-        Self { rows: parsed_rows }
+        Self { rows }
     }
 
     pub fn group_skills_by_class<'skill_names>(
@@ -103,7 +77,7 @@ impl<'raw_text> ExcelSkills<'raw_text> {
                     found_skill = true;
 
                     skill_class_to_skill_data
-                        .entry(row.skill_class)
+                        .entry(row.get_skill_class())
                         .or_insert_with(Vec::new)
                         .push(SkillData {
                             name: skill_name,
@@ -131,7 +105,7 @@ mod tests {
     fn test_excel_skills() {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let now = Instant::now();
         let excel_skills_raw_text = archives.extract_excel_skills_raw_text().unwrap();
@@ -0,0 +1,412 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{
+    de::{
+        self, value::BorrowedStrDeserializer, DeserializeSeed, IntoDeserializer, MapAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize,
+};
+
+const SEPARATOR: char = '\t';
+// Rows which separate the classic rows from the expansion rows
+const EXPANSION_ROW: &str = "Expansion";
+
+/// A tab separated excel table, like `data/global/excel/levels.txt`.
+///
+/// The rows can be deserialized into structs whose fields are named, or renamed, after the
+/// column headers. Empty cells are `None` for `Option` fields and `false` for `bool` fields.
+pub struct ExcelTable<'raw_text> {
+    column_headers: Vec<&'raw_text str>,
+    column_ids: HashMap<&'raw_text str, usize>,
+    rows: Vec<(usize, Vec<&'raw_text str>)>,
+}
+
+#[derive(Clone, Copy)]
+pub struct ExcelRow<'table, 'raw_text> {
+    table: &'table ExcelTable<'raw_text>,
+    /// line number in the file, starting at 1
+    line: usize,
+    cells: &'table [&'raw_text str],
+}
+
+/// Rows which can be looked up by the cell of an id column
+pub struct ExcelRows<'raw_text, T> {
+    rows: Vec<T>,
+    id_to_row_id: HashMap<&'raw_text str, usize>,
+}
+
+/// A cell which couldn't be parsed, or a missing column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExcelError {
+    /// line number in the file, starting at 1
+    pub line: usize,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+impl<'raw_text> ExcelTable<'raw_text> {
+    pub fn new(text: &'raw_text str) -> Self {
+        let mut line_iter = text
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .enumerate();
+
+        let column_headers: Vec<&str> = match line_iter.next() {
+            Some((_, header_line)) => header_line.split(SEPARATOR).collect(),
+            None => Vec::new(),
+        };
+
+        let mut column_ids = HashMap::new();
+        for (i, header) in column_headers.iter().enumerate() {
+            column_ids.entry(*header).or_insert(i);
+        }
+
+        let rows = line_iter
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                let mut cells: Vec<&str> = line.split(SEPARATOR).collect();
+                cells.resize(column_headers.len().max(cells.len()), "");
+                (i + 1, cells)
+            })
+            .filter(|(_, cells)| cells[0] != EXPANSION_ROW)
+            .collect();
+
+        Self {
+            column_headers,
+            column_ids,
+            rows,
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = ExcelRow<'_, 'raw_text>> {
+        self.rows.iter().map(|(line, cells)| ExcelRow {
+            table: self,
+            line: *line,
+            cells,
+        })
+    }
+
+    pub fn deserialize<T: Deserialize<'raw_text>>(&self) -> Result<Vec<T>, ExcelError> {
+        self.rows().map(|row| row.deserialize()).collect()
+    }
+
+    /// Deserializes the rows, so that they can be looked up by the cell of the id column
+    pub fn deserialize_by_id<T: Deserialize<'raw_text>>(
+        &self,
+        id_column: &str,
+    ) -> Result<ExcelRows<'raw_text, T>, ExcelError> {
+        let mut rows = Vec::with_capacity(self.rows.len());
+        let mut id_to_row_id = HashMap::with_capacity(self.rows.len());
+
+        for row in self.rows() {
+            id_to_row_id
+                .entry(row.get(id_column)?)
+                .or_insert(rows.len());
+            rows.push(row.deserialize()?);
+        }
+
+        Ok(ExcelRows { rows, id_to_row_id })
+    }
+}
+
+impl<'raw_text> ExcelRow<'_, 'raw_text> {
+    pub fn get<T: Deserialize<'raw_text>>(&self, column: &str) -> Result<T, ExcelError> {
+        let column_id = *self
+            .table
+            .column_ids
+            .get(column)
+            .ok_or_else(|| ExcelError {
+                line: self.line,
+                column: None,
+                message: format!("missing column {column}"),
+            })?;
+
+        T::deserialize(CellDeserializer {
+            cell: self.cells[column_id],
+        })
+        .map_err(|e| ExcelError {
+            line: self.line,
+            column: Some(column.to_string()),
+            message: e.message,
+        })
+    }
+
+    pub fn deserialize<T: Deserialize<'raw_text>>(&self) -> Result<T, ExcelError> {
+        T::deserialize(RowDeserializer {
+            table: self.table,
+            cells: self.cells,
+        })
+        .map_err(|mut e| {
+            e.line = self.line;
+            e
+        })
+    }
+}
+
+impl<'raw_text, T> ExcelRows<'raw_text, T> {
+    /// For rows which are parsed cell by cell, if several rows have the same id the first
+    /// one is used
+    pub fn new(rows: Vec<T>, get_id: impl Fn(&T) -> &'raw_text str) -> Self {
+        let mut id_to_row_id = HashMap::with_capacity(rows.len());
+
+        for (i, row) in rows.iter().enumerate() {
+            id_to_row_id.entry(get_id(row)).or_insert(i);
+        }
+
+        Self { rows, id_to_row_id }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&T> {
+        self.id_to_row_id.get(id).map(|&row_id| &self.rows[row_id])
+    }
+}
+
+impl fmt::Display for ExcelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.column {
+            Some(column) => write!(
+                f,
+                "{} in column {} of line {}",
+                self.message, column, self.line
+            ),
+            None => write!(f, "{} in line {}", self.message, self.line),
+        }
+    }
+}
+
+impl std::error::Error for ExcelError {}
+
+impl de::Error for ExcelError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            line: 0,
+            column: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+struct RowDeserializer<'table, 'raw_text> {
+    table: &'table ExcelTable<'raw_text>,
+    cells: &'table [&'raw_text str],
+}
+
+struct RowMapAccess<'table, 'raw_text> {
+    table: &'table ExcelTable<'raw_text>,
+    cells: &'table [&'raw_text str],
+    column_id: usize,
+}
+
+struct CellDeserializer<'raw_text> {
+    cell: &'raw_text str,
+}
+
+impl<'de> de::Deserializer<'de> for RowDeserializer<'_, 'de> {
+    type Error = ExcelError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(RowMapAccess {
+            table: self.table,
+            cells: self.cells,
+            column_id: 0,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> MapAccess<'de> for RowMapAccess<'_, 'de> {
+    type Error = ExcelError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let column_headers = &self.table.column_headers;
+
+        // only the first of the columns with the same header is used
+        while let Some(header) = column_headers.get(self.column_id) {
+            if self.table.column_ids[header] == self.column_id {
+                return seed
+                    .deserialize(BorrowedStrDeserializer::new(header))
+                    .map(Some);
+            }
+
+            self.column_id += 1;
+        }
+
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let column_id = self.column_id;
+        self.column_id += 1;
+
+        seed.deserialize(CellDeserializer {
+            cell: self.cells[column_id],
+        })
+        .map_err(|mut e| {
+            e.column = Some(self.table.column_headers[column_id].to_string());
+            e
+        })
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.cell.parse() {
+                    Ok(number) => visitor.$visit(number),
+                    Err(_) => Err(de::Error::custom(format!(
+                        "can't parse {:?} as a number",
+                        self.cell
+                    ))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for CellDeserializer<'de> {
+    type Error = ExcelError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.cell)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.cell {
+            "1" => visitor.visit_bool(true),
+            "0" | "" => visitor.visit_bool(false),
+            _ => Err(de::Error::custom(format!(
+                "can't parse {:?} as a bool",
+                self.cell
+            ))),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.cell.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.cell.into_deserializer())
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{ExcelError, ExcelTable};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Row<'raw_text> {
+        #[serde(rename = "Id")]
+        id: &'raw_text str,
+        level: u32,
+        #[serde(borrow)]
+        spawn: Option<&'raw_text str>,
+        enabled: bool,
+    }
+
+    const TEXT: &str = "Id\tlevel\tspawn\tenabled\tunused\r\n\
+                        zombie\t3\t\t1\tx\r\n\
+                        Expansion\r\n\
+                        \r\n\
+                        ghoul\t7\tzombie\t0\r\n";
+
+    #[test]
+    fn test_deserialize() {
+        let table = ExcelTable::new(TEXT);
+
+        let rows: Vec<Row> = table.deserialize().unwrap();
+        assert_eq!(
+            rows,
+            [
+                Row {
+                    id: "zombie",
+                    level: 3,
+                    spawn: None,
+                    enabled: true,
+                },
+                Row {
+                    id: "ghoul",
+                    level: 7,
+                    spawn: Some("zombie"),
+                    enabled: false,
+                }
+            ]
+        );
+
+        let rows = table.deserialize_by_id::<Row>("Id").unwrap();
+        assert_eq!(rows.get("ghoul").unwrap().level, 7);
+        assert!(rows.get("Expansion").is_none());
+
+        let row = table.rows().nth(1).unwrap();
+        assert_eq!(row.get::<Option<&str>>("spawn"), Ok(Some("zombie")));
+    }
+
+    #[test]
+    fn test_errors() {
+        let table = ExcelTable::new("Id\tlevel\tspawn\tenabled\r\nzombie\tthree\t\t1\r\n");
+
+        assert_eq!(
+            table.deserialize::<Row>().unwrap_err(),
+            ExcelError {
+                line: 2,
+                column: Some("level".to_string()),
+                message: "can't parse \"three\" as a number".to_string(),
+            }
+        );
+
+        let row = table.rows().next().unwrap();
+        assert_eq!(
+            row.get::<u32>("Level").unwrap_err().to_string(),
+            "missing column Level in line 2"
+        );
+    }
+}
@@ -5,9 +5,15 @@ mod dc6_scene;
 mod dc6_stash_raw_bytes;
 mod dcc_bytes;
 pub mod excel_automap;
+pub mod excel_gems;
+pub mod excel_item_types;
+pub mod excel_items;
 pub mod excel_levels;
 pub mod excel_monstats;
 pub mod excel_monstats2;
+pub mod excel_named_items;
+pub mod excel_runes;
 pub mod excel_skills;
+pub mod excel_table;
 pub mod rand_transform_palettes;
 pub mod strings_table;
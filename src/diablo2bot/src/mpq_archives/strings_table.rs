@@ -105,10 +105,10 @@ mod tests {
     fn test_string_tables() {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let now = Instant::now();
-        let string_tables = StringTables::new(&mut archives);
+        let string_tables = StringTables::new(&archives);
         println!("elapsed: {} micros", now.elapsed().as_micros());
 
         println!("{}", string_tables.table.len());
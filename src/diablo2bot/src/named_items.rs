@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::{
+    enums::quality::Quality,
+    item_bases::ItemBases,
+    mpq_archives::{archives::Archives, excel_named_items::ExcelNamedItems},
+    string_tables::StringTables,
+};
+
+/// A unique or set item from uniqueitems.txt or setitems.txt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedItem {
+    pub quality: Quality,
+    /// the displayed name of the base item, like "Shako" for "Harlequin Crest"
+    pub base_name: String,
+}
+
+/// The unique and set items by their displayed names
+pub struct NamedItems {
+    items: HashMap<String, NamedItem>,
+}
+
+impl NamedItems {
+    pub fn new(archives: &Archives, string_tables: &StringTables, item_bases: &ItemBases) -> Self {
+        let unique_items_raw_text = archives.extract_excel_unique_items_raw_text().unwrap();
+        let set_items_raw_text = archives.extract_excel_set_items_raw_text().unwrap();
+
+        let mut named_items = Self {
+            items: HashMap::new(),
+        };
+
+        named_items.add_items(
+            &unique_items_raw_text.parse(),
+            Quality::Unique,
+            string_tables,
+            item_bases,
+        );
+        named_items.add_items(
+            &set_items_raw_text.parse(),
+            Quality::Set,
+            string_tables,
+            item_bases,
+        );
+
+        named_items
+    }
+
+    /// Returns the unique or set item with the name, when the quality matches
+    pub fn get(&self, name: &str, quality: Quality) -> Option<&NamedItem> {
+        self.items
+            .get(name)
+            .filter(|named_item| named_item.quality == quality)
+    }
+
+    fn add_items(
+        &mut self,
+        excel_named_items: &ExcelNamedItems,
+        quality: Quality,
+        string_tables: &StringTables,
+        item_bases: &ItemBases,
+    ) {
+        for row in &excel_named_items.rows {
+            // Rows without a known base item are placeholders
+            let Some(base_name) = item_bases.get_name(row.code) else {
+                continue;
+            };
+
+            let name = match string_tables.table.get(row.name_string_id) {
+                Some(name) => name.as_str(),
+                None => row.name_string_id,
+            };
+
+            self.items
+                .entry(name.to_string())
+                .or_insert_with(|| NamedItem {
+                    quality,
+                    base_name: base_name.to_string(),
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{enums::item_class::ItemClass, item_bases::item_bases_from_text};
+
+    #[test]
+    fn test_named_items() {
+        let string_tables = StringTables {
+            table: HashMap::new(),
+        };
+        let item_bases = item_bases_from_text(
+            &[(
+                ItemClass::Armor,
+                "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\r\n\
+                 Shako\tuap\tuap\thelm\t2\t2\t2\r\n\
+                 Cap\tcap\tcap\thelm\t2\t2\t2\r\n",
+            )],
+            &[],
+        );
+
+        let mut named_items = NamedItems {
+            items: HashMap::new(),
+        };
+        named_items.add_items(
+            &ExcelNamedItems::new(
+                "index\tcode\r\nHarlequin Crest\tuap\r\nBiggin's Bonnet\tcap\r\nUnused\t\r\n",
+            ),
+            Quality::Unique,
+            &string_tables,
+            &item_bases,
+        );
+        named_items.add_items(
+            &ExcelNamedItems::new("index\tset\titem\r\nInfernal Cranium\tInfernal Tools\tcap\r\n"),
+            Quality::Set,
+            &string_tables,
+            &item_bases,
+        );
+
+        let harlequin_crest = named_items.get("Harlequin Crest", Quality::Unique).unwrap();
+        assert_eq!(harlequin_crest.base_name, "Shako");
+        assert!(named_items.get("Harlequin Crest", Quality::Set).is_none());
+        assert_eq!(
+            named_items
+                .get("Infernal Cranium", Quality::Set)
+                .unwrap()
+                .base_name,
+            "Cap"
+        );
+        assert!(named_items.get("Unused", Quality::Unique).is_none());
+    }
+}
@@ -6,7 +6,7 @@ use std::{
 };
 
 use arrayvec::ArrayVec;
-use dcc_decoder::Dcc;
+use dcc_decoder::{DccError, DccReader};
 use nohash_hasher::BuildNoHashHasher;
 use serde::{Deserialize, Serialize};
 
@@ -477,12 +477,15 @@ fn count_unique_values(data: [u8; WINDOW_SIZE]) -> usize {
     count
 }
 
-fn parse_dcc_into_sprite_window_structs(dcc: &Dcc) -> Vec<SpriteWindows> {
+fn parse_dcc_into_sprite_window_structs(dcc: &DccReader) -> Result<Vec<SpriteWindows>, DccError> {
     let mut sprite_windows = Vec::new();
 
     let mut window = [0; WINDOW_SIZE];
 
-    for direction in &dcc.directions {
+    // one direction is decoded at a time to keep the memory use low
+    for direction_idx in 0..dcc.num_directions() {
+        let direction = dcc.decode_direction(direction_idx)?;
+
         for frame in &direction.frames {
             let mut windows = Vec::new();
 
@@ -533,7 +536,7 @@ fn parse_dcc_into_sprite_window_structs(dcc: &Dcc) -> Vec<SpriteWindows> {
         }
     }
 
-    sprite_windows
+    Ok(sprite_windows)
 }
 
 fn transform_window(window: &[u8; WINDOW_SIZE], palette: &Palette) -> [u8; WINDOW_SIZE] {
@@ -580,7 +583,7 @@ struct SpriteWindowSetup<'a> {
 }
 
 fn load_window_sprites(
-    archives: &mut Archives,
+    archives: &Archives,
     sprite_window_setup: &mut SpriteWindowSetup,
     dcc_file: &DccFile,
     max_windows_per_sprite_frame: u32,
@@ -594,9 +597,20 @@ fn load_window_sprites(
             if let Ok(dcc_file_bytes) = archives.extract_dcc_file_bytes(&dcc_file.full_path) {
                 let start = sprite_window_setup.matrix_windows_neutral.len();
 
-                let dcc = dcc_file_bytes.parse();
-
-                let sprites_windows = parse_dcc_into_sprite_window_structs(&dcc);
+                // a malformed sprite, e.g. of a mod, is skipped instead of stopping the bot
+                let sprites_windows = match dcc_file_bytes
+                    .parse()
+                    .and_then(|dcc| parse_dcc_into_sprite_window_structs(&dcc))
+                {
+                    Ok(sprites_windows) => sprites_windows,
+                    Err(e) => {
+                        eprintln!("Skipping malformed sprite {}: {e}", dcc_file.full_path);
+                        sprite_window_setup
+                            .filename_to_window_ids
+                            .insert(dcc_file.name.clone(), (start, start));
+                        return;
+                    }
+                };
 
                 for sprite_windows in &sprites_windows {
                     let mut window_count = 0;
@@ -674,7 +688,7 @@ fn add_sprite_windows(
 fn get_level_monsters(
     level: LevelName,
     game_difficulty: GameDifficulty,
-    archives: &mut Archives,
+    archives: &Archives,
 ) -> Result<Vec<Monster>, BotError> {
     let excel_levels_raw_text = archives.extract_excel_levels_raw_text()?;
     let excel_levels = excel_levels_raw_text.parse();
@@ -694,7 +708,7 @@ fn get_level_monsters(
     ))
 }
 
-fn get_monster_data(name: &str, archives: &mut Archives) -> Monster {
+fn get_monster_data(name: &str, archives: &Archives) -> Monster {
     let excel_monstats_raw_text = archives.extract_excel_monstats_raw_text().unwrap();
     let excel_monstats = excel_monstats_raw_text.parse();
 
@@ -719,7 +733,7 @@ fn group_monsters(monsters: Vec<Monster>) -> HashMap<String, Vec<Monster>> {
 }
 
 pub fn cache_monster_tree(
-    archives: &mut Archives,
+    archives: &Archives,
     file_io: &FileIo,
     name: &str,
     config: &MonsterMatcherConfig,
@@ -767,7 +781,7 @@ impl MonsterMatcherConfig {
 }
 
 pub fn get_monster_tree(
-    archives: &mut Archives,
+    archives: &Archives,
     file_io: &FileIo,
     name: &str,
     config: &MonsterMatcherConfig,
@@ -975,10 +989,10 @@ mod tests {
         let system_settings = file_io.load_system_settings().unwrap();
         let act = Act::Act4;
 
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
         let npc_name = &act.get_potion_seller_name();
 
-        let string_tables = StringTables::new(&mut archives);
+        let string_tables = StringTables::new(&archives);
         let zone_name_converter = ZoneNameConverter::new(&string_tables);
 
         let monster_matcher_config = MonsterMatcherConfig {
@@ -990,7 +1004,7 @@ mod tests {
         };
 
         let tree = get_monster_tree(
-            &mut archives,
+            &archives,
             &file_io,
             npc_name,
             &monster_matcher_config,
@@ -1043,9 +1057,9 @@ mod tests {
         let file_io = FileIo::new();
         file_io.create_temp_dir().unwrap();
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
-        let string_tables = StringTables::new(&mut archives);
+        let string_tables = StringTables::new(&archives);
         let zone_name_converter = ZoneNameConverter::new(&string_tables);
 
         let act = Act::Act1;
@@ -1059,7 +1073,7 @@ mod tests {
         };
 
         let tree = get_monster_tree(
-            &mut archives,
+            &archives,
             &file_io,
             "Stony Field",
             &monster_matcher_config,
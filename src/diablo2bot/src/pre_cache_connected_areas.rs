@@ -32,7 +32,8 @@ pub fn pre_cache_connected_areas(
             bot_settings,
         );
 
-        let mut args = CacheAreaArgs {
+        // the clone shares the opened archives
+        let args = CacheAreaArgs {
             archives: archives.clone(),
             area: connected_area.to_string(),
             file_io: file_io.clone(),
@@ -42,10 +43,10 @@ pub fn pre_cache_connected_areas(
 
         if bot_settings.pre_cache_connected_areas_multiple_threads {
             thread::spawn(move || {
-                cache_area(&mut args);
+                cache_area(&args);
             });
         } else {
-            cache_area(&mut args);
+            cache_area(&args);
         }
     }
 }
@@ -58,9 +59,9 @@ struct CacheAreaArgs {
     zone_name_converter: ZoneNameConverter,
 }
 
-fn cache_area(args: &mut CacheAreaArgs) {
+fn cache_area(args: &CacheAreaArgs) {
     cache_monster_tree(
-        &mut args.archives,
+        &args.archives,
         &args.file_io,
         &args.area,
         &args.monsters_matcher_config,
@@ -21,6 +21,8 @@ pub struct Profile {
     #[serde(deserialize_with = "validate_zone_to_farm")]
     pub zone_to_farm: WaypointZone,
     pub min_gold_to_pickup: u32,
+    #[serde(default)]
+    pub target_runewords: Vec<String>,
     pub game_difficulty: GameDifficulty,
     pub num_belt_columns_reserved_for_healing_potions: u8,
     pub num_belt_columns_reserved_for_mana_potions: u8,
@@ -38,6 +40,7 @@ pub struct Profile {
 pub struct SystemSettings {
     pub diablo2_folder_path: String,
     pub operating_system: OperatingSystem,
+    pub mpq_override_folder_path: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
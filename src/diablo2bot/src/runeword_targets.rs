@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    enums::quality::Quality,
+    item_bases::ItemBases,
+    item_rules::{ItemProperties, ItemRule, ItemRules},
+    point_u16::PointU16,
+    runewords::{Runeword, Runewords},
+};
+
+/// The runewords of the profile which runes and bases are collected for. The rules of the
+/// runes and the bases which are still needed are used before the rules of the item filter,
+/// both when picking up items and when deciding which of the identified items are kept.
+///
+/// Only the runes and bases which the bot moves to the stash are counted, the count starts
+/// over when the bot is started. The items which are already in the stash aren't read, so the
+/// profile should only name the runewords which are still missing runes or a base.
+pub struct RunewordTargets {
+    targets: Vec<RunewordTarget>,
+    stashed_runes: HashMap<String, u32>,
+    kept_items: HashMap<PointU16, KeptItem>,
+    rules: ItemRules,
+}
+
+struct RunewordTarget {
+    runeword: Runeword,
+    has_base: bool,
+}
+
+/// A rune or a base which is kept in the inventory until it is moved to the stash
+struct KeptItem {
+    name: String,
+    base_name: String,
+    quality: Quality,
+    sockets: Option<u8>,
+}
+
+impl RunewordTargets {
+    pub fn new(names: &[String], runewords: &Runewords) -> Result<Self, String> {
+        let mut targets = Vec::new();
+
+        for name in names {
+            let runeword = runewords
+                .get(name)
+                .ok_or_else(|| format!("Unknown runeword in the profile: {name}"))?;
+
+            targets.push(RunewordTarget {
+                runeword: runeword.clone(),
+                has_base: false,
+            });
+        }
+
+        let mut runeword_targets = Self {
+            targets,
+            stashed_runes: HashMap::new(),
+            kept_items: HashMap::new(),
+            rules: ItemRules::default(),
+        };
+        runeword_targets.update_rules();
+
+        Ok(runeword_targets)
+    }
+
+    pub fn find_rule(&self, item: &ItemProperties, item_bases: &ItemBases) -> Option<&ItemRule> {
+        self.rules.find_rule(item, item_bases)
+    }
+
+    /// Returns whether an identified item is kept. The runes and bases which are still needed
+    /// are kept, the bases which are only picked up for the runewords are dropped when they
+    /// don't have a socket for each rune, unless a rule of the item filter picks them up.
+    pub fn keep_item(
+        &self,
+        item: &ItemProperties,
+        item_bases: &ItemBases,
+        item_filter: &ItemRules,
+    ) -> bool {
+        let filter_rule = item_filter.find_rule(item, item_bases);
+
+        match self.find_rule(item, item_bases) {
+            Some(rule) if rule.pickup => true,
+            Some(_) => filter_rule.is_some_and(|rule| rule.pickup),
+            // Items which don't match any rule were picked up by a rule which doesn't apply any more
+            None => filter_rule.is_none_or(|rule| rule.pickup),
+        }
+    }
+
+    /// Remembers a kept rune or base by the inventory cell of its top left corner, it is counted
+    /// when it is moved to the stash
+    pub fn add_kept_item(&mut self, cell: PointU16, item: &ItemProperties) {
+        if item.quality != Quality::Rune && item.quality != Quality::Grey {
+            return;
+        }
+
+        self.kept_items.insert(
+            cell,
+            KeptItem {
+                name: item.name.to_string(),
+                base_name: item.base_name.to_string(),
+                quality: item.quality,
+                sockets: item.sockets,
+            },
+        );
+    }
+
+    /// Forgets the kept items, the items which are still in the inventory are added again when
+    /// the items are checked the next time
+    pub fn clear_kept_items(&mut self) {
+        self.kept_items.clear();
+    }
+
+    /// Counts the kept rune or base which was moved from the inventory cell to the stash
+    pub fn stash_item(&mut self, cell: PointU16, runewords: &Runewords) {
+        let Some(item) = self.kept_items.remove(&cell) else {
+            return;
+        };
+
+        if item.quality == Quality::Rune {
+            let Some(rune) = runewords.get_rune(&item.name) else {
+                return;
+            };
+
+            if !self.get_needed_runes().contains_key(item.name.as_str()) {
+                return;
+            }
+
+            *self.stashed_runes.entry(item.name.clone()).or_insert(0) += 1;
+
+            let target_names: Vec<&str> = rune
+                .runewords
+                .iter()
+                .filter(|name| {
+                    self.targets
+                        .iter()
+                        .any(|target| target.runeword.name == **name)
+                })
+                .map(String::as_str)
+                .collect();
+
+            println!(
+                "Stashed the {} rune (rank {}) for {}",
+                rune.letter,
+                rune.rank,
+                target_names.join(", ")
+            );
+        } else {
+            let Some(sockets) = item.sockets else {
+                return;
+            };
+
+            let Some(target) = self.targets.iter_mut().find(|target| {
+                !target.has_base
+                    && target.runeword.runes.len() == usize::from(sockets)
+                    && target.runeword.is_base(&item.base_name)
+            }) else {
+                return;
+            };
+
+            target.has_base = true;
+
+            println!(
+                "Stashed the {} base {}",
+                target.runeword.name, item.base_name
+            );
+        }
+
+        self.update_rules();
+    }
+
+    /// Returns how many of each rune are needed for the runewords, without the stashed runes
+    pub fn get_needed_runes(&self) -> HashMap<&str, u32> {
+        let mut needed_runes: HashMap<&str, u32> = HashMap::new();
+
+        for target in &self.targets {
+            for rune in &target.runeword.runes {
+                *needed_runes.entry(rune).or_insert(0) += 1;
+            }
+        }
+
+        for (rune, stashed) in &self.stashed_runes {
+            if let Some(needed) = needed_runes.get_mut(rune.as_str()) {
+                *needed = needed.saturating_sub(*stashed);
+            }
+        }
+
+        needed_runes.retain(|_, needed| *needed > 0);
+
+        needed_runes
+    }
+
+    // The bases are picked up whatever their socket count is, as it is only known after the
+    // tooltip has been read. Only the bases with a socket for each rune are kept, the rules of
+    // the other socket counts come last so that they don't hide the rule of another runeword.
+    fn update_rules(&mut self) {
+        let mut needed_runes: Vec<&str> = self.get_needed_runes().into_keys().collect();
+        needed_runes.sort();
+
+        let mut rules: Vec<ItemRule> = needed_runes
+            .into_iter()
+            .map(|rune| ItemRule {
+                pickup: true,
+                name: Some(rune.to_string()),
+                qualities: Some(vec![Quality::Rune]),
+                ..Default::default()
+            })
+            .collect();
+
+        let mut added_bases = HashSet::new();
+        let mut unwanted_base_names = HashSet::new();
+        let mut unwanted_bases = Vec::new();
+
+        for target in self.targets.iter().filter(|target| !target.has_base) {
+            let sockets = target.runeword.runes.len() as u8;
+
+            for base_name in &target.runeword.base_names {
+                if !added_bases.insert((base_name, sockets)) {
+                    continue;
+                }
+
+                let rule = ItemRule {
+                    pickup: true,
+                    name: Some(base_name.clone()),
+                    qualities: Some(vec![Quality::Grey]),
+                    ..Default::default()
+                };
+
+                rules.push(ItemRule {
+                    identified: Some(false),
+                    ..rule.clone()
+                });
+                rules.push(ItemRule {
+                    identified: Some(true),
+                    min_sockets: Some(sockets),
+                    max_sockets: Some(sockets),
+                    ..rule.clone()
+                });
+
+                if unwanted_base_names.insert(base_name) {
+                    unwanted_bases.push(ItemRule {
+                        pickup: false,
+                        identified: Some(true),
+                        ..rule
+                    });
+                }
+            }
+        }
+
+        rules.extend(unwanted_bases);
+
+        self.rules = ItemRules { rules };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        enums::item_class::ItemClass,
+        item_bases::item_bases_from_text,
+        item_tooltip::ItemStat,
+        mpq_archives::{
+            excel_gems::ExcelGems, excel_item_types::ExcelItemTypes, excel_runes::ExcelRunes,
+        },
+        string_tables::StringTables,
+    };
+
+    #[test]
+    fn test_runeword_targets() {
+        let item_bases = item_bases_from_text(
+            &[
+                (
+                    ItemClass::Weapon,
+                    "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\r\n\
+                     Crystal Sword\tcrs\tcrs\tswor\t2\t3\t6\r\n",
+                ),
+                (
+                    ItemClass::Misc,
+                    "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\r\n\
+                     Tal Rune\tr07\tr07\trune\t1\t1\t\r\n\
+                     Amn Rune\tr11\tr11\trune\t1\t1\t\r\n",
+                ),
+            ],
+            &[],
+        );
+        let runewords = Runewords::from_excel_tables(
+            &ExcelRunes::new(
+                "Name\tRune Name\tcomplete\titype1\tRune1\tRune2\tRune3\r\n\
+                 Runeword1\tSteel\t1\tswor\tr07\tr11\t\r\n\
+                 Runeword2\tTest\t1\tswor\tr07\tr07\tr11\r\n",
+            ),
+            &ExcelGems::new("letter\tcode\r\nTal\tr07\r\nAmn\tr11\r\n"),
+            &ExcelItemTypes::new("Code\tEquiv1\tEquiv2\r\nswor\t\t\r\n"),
+            &StringTables {
+                table: HashMap::new(),
+            },
+            &item_bases,
+        );
+
+        assert!(RunewordTargets::new(&["Spirit".to_string()], &runewords).is_err());
+
+        let mut runeword_targets =
+            RunewordTargets::new(&["Steel".to_string(), "Test".to_string()], &runewords).unwrap();
+
+        assert_eq!(
+            runeword_targets.get_needed_runes(),
+            HashMap::from([("Tal Rune", 3), ("Amn Rune", 2)])
+        );
+
+        let item = |name, quality, sockets, stats| ItemProperties {
+            name,
+            base_name: name,
+            quality,
+            base: item_bases.get(name),
+            gold: None,
+            ethereal: None,
+            sockets,
+            stats,
+        };
+        let stats: &[ItemStat] = &[];
+        let cell = |col| PointU16::new(0, col);
+
+        let tal = item("Tal Rune", Quality::Rune, None, None);
+        assert!(runeword_targets.find_rule(&tal, &item_bases).is_some());
+
+        // The runes are only counted when they are moved to the stash
+        for col in 0..3 {
+            runeword_targets.add_kept_item(cell(col), &tal);
+        }
+        runeword_targets.stash_item(cell(0), &runewords);
+        runeword_targets.stash_item(cell(0), &runewords);
+        assert_eq!(
+            runeword_targets.get_needed_runes(),
+            HashMap::from([("Tal Rune", 2), ("Amn Rune", 2)])
+        );
+
+        runeword_targets.clear_kept_items();
+        runeword_targets.stash_item(cell(1), &runewords);
+        assert_eq!(runeword_targets.get_needed_runes()["Tal Rune"], 2);
+
+        for col in 0..2 {
+            runeword_targets.add_kept_item(cell(col), &tal);
+            runeword_targets.stash_item(cell(col), &runewords);
+        }
+        assert_eq!(
+            runeword_targets.get_needed_runes(),
+            HashMap::from([("Amn Rune", 2)])
+        );
+        assert!(runeword_targets.find_rule(&tal, &item_bases).is_none());
+
+        let sword_on_ground = item("Crystal Sword", Quality::Grey, None, None);
+        let sword = |sockets| item("Crystal Sword", Quality::Grey, Some(sockets), Some(stats));
+        assert!(runeword_targets
+            .find_rule(&sword_on_ground, &item_bases)
+            .is_some());
+        assert!(
+            runeword_targets
+                .find_rule(&sword(2), &item_bases)
+                .unwrap()
+                .pickup
+        );
+        assert!(
+            runeword_targets
+                .find_rule(&sword(3), &item_bases)
+                .unwrap()
+                .pickup
+        );
+        assert!(
+            !runeword_targets
+                .find_rule(&sword(4), &item_bases)
+                .unwrap()
+                .pickup
+        );
+
+        // The bases with the wrong socket count are dropped unless the item filter keeps them
+        let no_filter = ItemRules::default();
+        let filter = |pickup| ItemRules {
+            rules: vec![ItemRule {
+                pickup,
+                name: Some("Crystal Sword".to_string()),
+                ..Default::default()
+            }],
+        };
+        assert!(runeword_targets.keep_item(&sword(2), &item_bases, &no_filter));
+        assert!(runeword_targets.keep_item(&sword(2), &item_bases, &filter(false)));
+        assert!(!runeword_targets.keep_item(&sword(4), &item_bases, &no_filter));
+        assert!(!runeword_targets.keep_item(&sword(4), &item_bases, &filter(false)));
+        assert!(runeword_targets.keep_item(&sword(4), &item_bases, &filter(true)));
+
+        runeword_targets.add_kept_item(cell(0), &sword(2));
+        runeword_targets.stash_item(cell(0), &runewords);
+        assert!(runeword_targets
+            .find_rule(&sword(2), &item_bases)
+            .is_some_and(|rule| !rule.pickup));
+        assert!(
+            runeword_targets
+                .find_rule(&sword(3), &item_bases)
+                .unwrap()
+                .pickup
+        );
+
+        runeword_targets.add_kept_item(cell(0), &sword(3));
+        runeword_targets.stash_item(cell(0), &runewords);
+        assert!(runeword_targets
+            .find_rule(&sword_on_ground, &item_bases)
+            .is_none());
+        assert!(runeword_targets.find_rule(&sword(4), &item_bases).is_none());
+        assert!(runeword_targets.keep_item(&sword(4), &item_bases, &no_filter));
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    item_bases::ItemBases,
+    mpq_archives::{
+        archives::Archives, excel_gems::ExcelGems, excel_item_types::ExcelItemTypes,
+        excel_runes::ExcelRunes,
+    },
+    string_tables::StringTables,
+};
+
+/// A rune from gems.txt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rune {
+    /// the letters which the rune adds to the name of a socketed item, like "Tal"
+    pub letter: String,
+    /// 1 for El and 33 for Zod
+    pub rank: u8,
+    /// the names of the runewords which the rune is a part of
+    pub runewords: Vec<String>,
+}
+
+/// A runeword from runes.txt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Runeword {
+    pub name: String,
+    /// the displayed names of the runes in the order they are socketed, like "Tal Rune"
+    pub runes: Vec<String>,
+    /// the displayed names of the base items which can get a socket for each rune
+    pub base_names: Vec<String>,
+}
+
+impl Runeword {
+    pub fn is_base(&self, base_name: &str) -> bool {
+        self.base_names.iter().any(|name| name == base_name)
+    }
+}
+
+/// The runewords by their names and the runes by their displayed names
+pub struct Runewords {
+    runewords: HashMap<String, Runeword>,
+    runes: HashMap<String, Rune>,
+}
+
+impl Runewords {
+    pub fn new(archives: &Archives, string_tables: &StringTables, item_bases: &ItemBases) -> Self {
+        let runes_raw_text = archives.extract_excel_runes_raw_text().unwrap();
+        let gems_raw_text = archives.extract_excel_gems_raw_text().unwrap();
+        let item_types_raw_text = archives.extract_excel_item_types_raw_text().unwrap();
+
+        Self::from_excel_tables(
+            &runes_raw_text.parse(),
+            &gems_raw_text.parse(),
+            &item_types_raw_text.parse(),
+            string_tables,
+            item_bases,
+        )
+    }
+
+    pub fn from_excel_tables(
+        excel_runes: &ExcelRunes,
+        excel_gems: &ExcelGems,
+        excel_item_types: &ExcelItemTypes,
+        string_tables: &StringTables,
+        item_bases: &ItemBases,
+    ) -> Self {
+        let mut runewords = Self {
+            runewords: HashMap::new(),
+            runes: HashMap::new(),
+        };
+
+        let mut rune_names = HashMap::new();
+
+        for row in &excel_gems.rows {
+            // The gems don't have letters
+            let Some(letter) = row.letter else {
+                continue;
+            };
+
+            let Some(name) = item_bases.get_name(row.code) else {
+                continue;
+            };
+
+            let Some(rank) = item_bases.get(name).and_then(|base| base.rune_rank) else {
+                continue;
+            };
+
+            rune_names.insert(row.code, name);
+            runewords.runes.insert(
+                name.to_string(),
+                Rune {
+                    letter: letter.to_string(),
+                    rank,
+                    runewords: Vec::new(),
+                },
+            );
+        }
+
+        let item_type_parents: HashMap<&str, Vec<&str>> = excel_item_types
+            .rows
+            .iter()
+            .map(|row| (row.code, row.get_parents().collect()))
+            .collect();
+
+        for row in excel_runes.rows.iter().filter(|row| row.complete) {
+            let Some(runes) = row
+                .get_rune_codes()
+                .map(|code| rune_names.get(code).map(|name| name.to_string()))
+                .collect::<Option<Vec<String>>>()
+            else {
+                continue;
+            };
+
+            let name = match string_tables.table.get(row.name_string_id) {
+                Some(name) => name.as_str(),
+                None => row.name,
+            };
+
+            let mut base_names: Vec<String> = item_bases
+                .iter()
+                .filter(|(_, base)| usize::from(base.max_sockets) >= runes.len())
+                .filter(|(_, base)| {
+                    let item_types = get_item_types(&base.item_type, &item_type_parents);
+
+                    row.get_item_types()
+                        .any(|item_type| item_types.contains(item_type))
+                        && !row
+                            .get_excluded_item_types()
+                            .any(|item_type| item_types.contains(item_type))
+                })
+                .map(|(name, _)| name.to_string())
+                .collect();
+            base_names.sort();
+
+            for rune_name in &runes {
+                let rune = runewords.runes.get_mut(rune_name).unwrap();
+
+                if !rune.runewords.iter().any(|runeword| runeword == name) {
+                    rune.runewords.push(name.to_string());
+                }
+            }
+
+            runewords.runewords.insert(
+                name.to_string(),
+                Runeword {
+                    name: name.to_string(),
+                    runes,
+                    base_names,
+                },
+            );
+        }
+
+        runewords
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Runeword> {
+        self.runewords.get(name)
+    }
+
+    /// Returns the rune with the displayed name, like "Tal Rune"
+    pub fn get_rune(&self, name: &str) -> Option<&Rune> {
+        self.runes.get(name)
+    }
+}
+
+/// Returns the item type and all item types which it is a kind of, like "swor", "mele"
+/// and "weap" for a sword
+fn get_item_types<'a>(
+    item_type: &'a str,
+    item_type_parents: &HashMap<&'a str, Vec<&'a str>>,
+) -> HashSet<&'a str> {
+    let mut item_types = HashSet::new();
+    let mut unvisited = vec![item_type];
+
+    while let Some(item_type) = unvisited.pop() {
+        if item_types.insert(item_type) {
+            if let Some(parents) = item_type_parents.get(item_type) {
+                unvisited.extend(parents);
+            }
+        }
+    }
+
+    item_types
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{enums::item_class::ItemClass, item_bases::item_bases_from_text};
+
+    #[test]
+    fn test_runewords() {
+        let string_tables = StringTables {
+            table: HashMap::from([("Runeword1".to_string(), "Spirit".to_string())]),
+        };
+        let item_bases = item_bases_from_text(
+            &[
+                (
+                    ItemClass::Weapon,
+                    "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\r\n\
+                     Crystal Sword\tcrs\tcrs\tswor\t2\t3\t6\r\n\
+                     Short Sword\tssd\tssd\tswor\t1\t3\t2\r\n\
+                     Hand Axe\thax\thax\taxe\t1\t3\t4\r\n",
+                ),
+                (
+                    ItemClass::Misc,
+                    "name\tcode\tnamestr\ttype\tinvwidth\tinvheight\tgemsockets\r\n\
+                     Tal Rune\tr07\tr07\trune\t1\t1\t\r\n\
+                     Ral Rune\tr08\tr08\trune\t1\t1\t\r\n\
+                     Ort Rune\tr09\tr09\trune\t1\t1\t\r\n\
+                     Thul Rune\tr10\tr10\trune\t1\t1\t\r\n\
+                     Amn Rune\tr11\tr11\trune\t1\t1\t\r\n\
+                     Chipped Ruby\tgcr\tgcr\tgema\t1\t1\t\r\n",
+                ),
+            ],
+            &[],
+        );
+
+        let runewords = Runewords::from_excel_tables(
+            &ExcelRunes::new(
+                "Name\tRune Name\tcomplete\titype1\titype2\tetype1\tRune1\tRune2\tRune3\tRune4\r\n\
+                 Runeword1\tSpirit\t1\tswor\tshld\t\tr07\tr10\tr09\tr11\r\n\
+                 Runeword2\tSteel\t1\tweap\t\taxe\tr07\tr11\t\t\r\n\
+                 Runeword3\tUnfinished\t\tweap\t\t\tr08\t\t\t\r\n",
+            ),
+            &ExcelGems::new(
+                "name\tletter\tcode\r\n\
+                 Chipped Ruby\t\tgcr\r\n\
+                 Tal Rune\tTal\tr07\r\n\
+                 Ral Rune\tRal\tr08\r\n\
+                 Ort Rune\tOrt\tr09\r\n\
+                 Thul Rune\tThul\tr10\r\n\
+                 Amn Rune\tAmn\tr11\r\n",
+            ),
+            &ExcelItemTypes::new(
+                "ItemType\tCode\tEquiv1\tEquiv2\r\n\
+                 Weapon\tweap\t\t\r\n\
+                 Melee Weapon\tmele\tweap\t\r\n\
+                 Sword\tswor\tmele\t\r\n\
+                 Axe\taxe\tmele\t\r\n",
+            ),
+            &string_tables,
+            &item_bases,
+        );
+
+        let spirit = runewords.get("Spirit").unwrap();
+        assert_eq!(
+            spirit.runes,
+            ["Tal Rune", "Thul Rune", "Ort Rune", "Amn Rune"]
+        );
+        assert_eq!(spirit.base_names, ["Crystal Sword"]);
+
+        let steel = runewords.get("Steel").unwrap();
+        assert_eq!(steel.base_names, ["Crystal Sword", "Short Sword"]);
+        assert!(runewords.get("Unfinished").is_none());
+
+        let tal = runewords.get_rune("Tal Rune").unwrap();
+        assert_eq!(tal.letter, "Tal");
+        assert_eq!(tal.rank, 7);
+        assert_eq!(tal.runewords, ["Spirit", "Steel"]);
+        assert!(runewords.get_rune("Ral Rune").unwrap().runewords.is_empty());
+        assert!(runewords.get_rune("Chipped Ruby").is_none());
+    }
+}
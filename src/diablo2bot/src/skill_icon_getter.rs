@@ -12,7 +12,7 @@ pub struct SkillIconGetter {
 }
 
 impl SkillIconGetter {
-    pub fn new(archives: &mut Archives, skill_names: &[&str]) -> Self {
+    pub fn new(archives: &Archives, skill_names: &[&str]) -> Self {
         let excel_skills_raw_text = archives.extract_excel_skills_raw_text().unwrap();
         let excel_skills = excel_skills_raw_text.parse();
 
@@ -32,7 +32,7 @@ impl SkillIconGetter {
 
     fn create_skill_name_to_icon_sprite_map(
         skill_class_to_skills: HashMap<Option<CharacterClass>, Vec<SkillData>>,
-        archives: &mut Archives,
+        archives: &Archives,
     ) -> HashMap<String, Matrix> {
         let mut skill_name_to_icon_sprite = HashMap::new();
 
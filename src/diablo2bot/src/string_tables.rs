@@ -16,7 +16,7 @@ const STRING_TABLE_TYPE: [StringTableType; 3] = [
 ];
 
 impl StringTables {
-    pub fn new(archives: &mut Archives) -> Self {
+    pub fn new(archives: &Archives) -> Self {
         let string_tables: Vec<HashMap<String, String>> = STRING_TABLE_TYPE
             .into_iter()
             .map(|s| archives.extract_string_table(s).unwrap().parse().dictionary)
@@ -46,8 +46,9 @@ pub struct ItemFilter {
     pub set: bool,
     pub unique: bool,
     pub rune: bool,
-    pub row_size: u8,
-    pub col_size: u8,
+    /// The inventory size of the item, only needed for items which aren't in the item tables
+    pub row_size: Option<u8>,
+    pub col_size: Option<u8>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
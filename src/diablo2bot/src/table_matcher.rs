@@ -14,7 +14,7 @@ pub struct ConsumableItemsTableMatcher {
 }
 
 impl ConsumableItemsTableMatcher {
-    pub fn new(archives: &mut Archives, font_char_map: &HashMap<char, Matrix>) -> Self {
+    pub fn new(archives: &Archives, font_char_map: &HashMap<char, Matrix>) -> Self {
         let consumable_items = load_consumable_items(archives, font_char_map);
 
         Self {
@@ -57,7 +57,7 @@ fn get_keybind_numbers_points(font_char_map: &HashMap<char, Matrix>) -> Vec<Poin
 }
 
 fn load_consumable_items(
-    archives: &mut Archives,
+    archives: &Archives,
     font_char_map: &HashMap<char, Matrix>,
 ) -> Vec<ConsumableItem> {
     let keybind_numbers_points = get_keybind_numbers_points(font_char_map);
@@ -113,7 +113,7 @@ mod tests {
     fn test_table_matcher() {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let font_dc6_bytes = archives.extract_font_16_bytes().unwrap();
         let font_dc6_file = font_dc6_bytes.parse();
@@ -126,7 +126,7 @@ mod tests {
         let palette_transformer = act_palette_bytes.get_palette_transformer(&pixel_palette);
 
         let consumable_item_matcher =
-            super::ConsumableItemsTableMatcher::new(&mut archives, &font_char_map);
+            super::ConsumableItemsTableMatcher::new(&archives, &font_char_map);
 
         let test_data_path = file_io.root.join("test_data");
         let dir = get_directory(&test_data_path.as_os_str().to_str().unwrap(), "table");
@@ -765,14 +765,14 @@ mod tests {
         let file_io = FileIo::new();
         let system_settings = file_io.load_system_settings().unwrap();
         let bot_settings = file_io.load_bot_settings().unwrap();
-        let mut archives = Archives::new(&system_settings.diablo2_folder_path);
+        let archives = Archives::new(&system_settings.diablo2_folder_path);
 
         let pal_pl2_bytes = archives.extract_pal_pl2_bytes(Act::Act1.into()).unwrap();
         let act_palette_bytes = pal_pl2_bytes.extract_act_palette_bytes();
         let pixel_palette = act_palette_bytes.get_pixel_palette();
         let palette_transformer = act_palette_bytes.get_palette_transformer(&pixel_palette);
 
-        let map_sprite_matcher = MapMatcher::new("1 Wilderness", &mut archives);
+        let map_sprite_matcher = MapMatcher::new("1 Wilderness", &archives);
 
         let folder_path = "/home/kasper/code/diablo2bot/test_data/zone_traveller/area/1 Wilderness";
 
@@ -0,0 +1,57 @@
+use crate::error::{Ds1Error, Ds1ErrorReason};
+
+pub struct ByteStream<'bytes> {
+    bytes: &'bytes [u8],
+    offset: usize,
+}
+
+impl<'bytes> ByteStream<'bytes> {
+    pub fn new(bytes: &'bytes [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn error(&self, reason: Ds1ErrorReason) -> Ds1Error {
+        Ds1Error::new(self.offset, reason)
+    }
+
+    pub fn stream_i32(&mut self) -> Result<i32, Ds1Error> {
+        Ok(i32::from_le_bytes(self.stream_array()?))
+    }
+
+    pub fn stream_u32(&mut self) -> Result<u32, Ds1Error> {
+        Ok(u32::from_le_bytes(self.stream_array()?))
+    }
+
+    pub fn stream_array<const N: usize>(&mut self) -> Result<[u8; N], Ds1Error> {
+        Ok(self.stream_bytes(N)?.try_into().unwrap())
+    }
+
+    /// Streams the bytes up to the next 0 byte, and skips the 0 byte
+    pub fn stream_null_terminated(&mut self) -> Result<&'bytes [u8], Ds1Error> {
+        let len = self.bytes[self.offset.min(self.bytes.len())..]
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| Ds1Error::new(self.bytes.len(), Ds1ErrorReason::UnexpectedEnd))?;
+        let bytes = self.stream_bytes(len)?;
+        self.offset += 1;
+        Ok(bytes)
+    }
+
+    pub fn skip_bytes(&mut self, num_bytes: usize) -> Result<(), Ds1Error> {
+        self.stream_bytes(num_bytes).map(|_| ())
+    }
+
+    pub fn stream_bytes(&mut self, num_bytes: usize) -> Result<&'bytes [u8], Ds1Error> {
+        let byte_slice = self
+            .offset
+            .checked_add(num_bytes)
+            .and_then(|end| self.bytes.get(self.offset..end))
+            .ok_or_else(|| self.error(Ds1ErrorReason::UnexpectedEnd))?;
+        self.offset += num_bytes;
+        Ok(byte_slice)
+    }
+}
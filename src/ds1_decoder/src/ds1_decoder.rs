@@ -0,0 +1,515 @@
+use crate::{
+    byte_stream::ByteStream,
+    error::{Ds1Error, Ds1ErrorReason},
+};
+
+const MAX_VERSION: i32 = 18;
+// Largest width and height of a map, far larger than any map of the game
+const MAX_MAP_SIZE: i32 = 1024;
+const MAX_WALL_LAYERS: i32 = 4;
+const MAX_FLOOR_LAYERS: i32 = 2;
+const MAX_ACT: u32 = 4;
+
+// Orientations of the walls of files before version 7
+const ORIENTATION_LOOKUP: [u8; 25] = [
+    0x00, 0x01, 0x02, 0x01, 0x02, 0x03, 0x03, 0x05, 0x05, 0x06, 0x06, 0x07, 0x07, 0x08, 0x09, 0x0A,
+    0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x10, 0x11, 0x12, 0x14,
+];
+
+/// A preset layout of tiles, the layers hold a cell per tile in row major order
+#[derive(Debug)]
+pub struct Ds1 {
+    pub version: i32,
+    /// width and height in tiles
+    pub width: u32,
+    pub height: u32,
+    /// 0 based
+    pub act: u32,
+    pub tag_type: u32,
+    /// the DT1 files of the tiles
+    pub files: Vec<String>,
+    pub walls: Vec<Vec<WallCell>>,
+    pub floors: Vec<Vec<TileCell>>,
+    pub shadows: Vec<TileCell>,
+    /// empty if the map has no substitution layer
+    pub substitutions: Vec<u32>,
+    pub objects: Vec<Object>,
+    pub substitution_groups: Vec<SubstitutionGroup>,
+}
+
+/// Refers to the DT1 tiles with the main index `style` and the sub index `sequence`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TileCell {
+    pub prop1: u8,
+    pub sequence: u8,
+    pub unknown1: u8,
+    pub style: u8,
+    pub unknown2: u8,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WallCell {
+    pub tile: TileCell,
+    /// the orientation of the DT1 tile
+    pub orientation: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Object {
+    pub object_type: i32,
+    pub id: i32,
+    /// position in sub tiles
+    pub x: i32,
+    pub y: i32,
+    pub flags: i32,
+    /// the path of an NPC, empty for other objects
+    pub path: Vec<PathPoint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathPoint {
+    pub x: i32,
+    pub y: i32,
+    pub action: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubstitutionGroup {
+    pub tile_x: i32,
+    pub tile_y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub unknown: i32,
+}
+
+enum LayerType {
+    Wall(usize),
+    Orientation(usize),
+    Floor(usize),
+    Shadow,
+    Substitution,
+}
+
+impl Ds1 {
+    /// Parses a DS1 file, panics if the file is malformed
+    pub fn new(data: &[u8]) -> Self {
+        Self::try_new(data).unwrap_or_else(|e| panic!("Invalid ds1 file: {e}"))
+    }
+
+    pub fn try_new(data: &[u8]) -> Result<Self, Ds1Error> {
+        let mut byte_stream = ByteStream::new(data);
+
+        let version = byte_stream.stream_i32()?;
+        if !(1..=MAX_VERSION).contains(&version) {
+            return Err(Ds1Error::new(
+                0,
+                Ds1ErrorReason::UnsupportedVersion(version),
+            ));
+        }
+
+        let size_offset = byte_stream.offset();
+        let width = byte_stream.stream_i32()?.wrapping_add(1);
+        let height = byte_stream.stream_i32()?.wrapping_add(1);
+
+        if !(1..=MAX_MAP_SIZE).contains(&width) || !(1..=MAX_MAP_SIZE).contains(&height) {
+            return Err(Ds1Error::new(
+                size_offset,
+                Ds1ErrorReason::MapTooLarge { width, height },
+            ));
+        }
+
+        let (width, height) = (width as u32, height as u32);
+
+        let act = if version >= 8 {
+            byte_stream.stream_u32()?.min(MAX_ACT)
+        } else {
+            0
+        };
+
+        let tag_type = if version >= 10 {
+            byte_stream.stream_u32()?
+        } else {
+            0
+        };
+
+        let files = if version >= 3 {
+            Self::load_files(&mut byte_stream)?
+        } else {
+            Vec::new()
+        };
+
+        if (9..=13).contains(&version) {
+            byte_stream.skip_bytes(2 * 4)?;
+        }
+
+        let (num_walls, num_floors) = if version >= 4 {
+            let num_walls = Self::load_num_layers(&mut byte_stream, MAX_WALL_LAYERS)?;
+            let num_floors = if version >= 16 {
+                Self::load_num_layers(&mut byte_stream, MAX_FLOOR_LAYERS)?
+            } else {
+                1
+            };
+            (num_walls, num_floors)
+        } else {
+            (1, 1)
+        };
+
+        let layer_types = Self::layer_types(version, tag_type, num_walls, num_floors);
+
+        let num_cells = width as usize * height as usize;
+        let mut ds1 = Self {
+            version,
+            width,
+            height,
+            act,
+            tag_type,
+            files,
+            walls: vec![vec![WallCell::default(); num_cells]; num_walls],
+            floors: vec![vec![TileCell::default(); num_cells]; num_floors],
+            shadows: vec![TileCell::default(); num_cells],
+            substitutions: Vec::new(),
+            objects: Vec::new(),
+            substitution_groups: Vec::new(),
+        };
+
+        for layer_type in layer_types {
+            ds1.load_layer(&mut byte_stream, &layer_type)?;
+        }
+
+        if version >= 2 {
+            ds1.objects = Self::load_objects(&mut byte_stream, version)?;
+        }
+
+        if version >= 12 && (tag_type == 1 || tag_type == 2) {
+            ds1.substitution_groups = Self::load_substitution_groups(&mut byte_stream, version)?;
+        }
+
+        if version >= 14 {
+            ds1.load_npc_paths(&mut byte_stream)?;
+        }
+
+        Ok(ds1)
+    }
+
+    /// Returns the index of a tile in the layers
+    pub fn cell_index(&self, row: u32, col: u32) -> usize {
+        row as usize * self.width as usize + col as usize
+    }
+
+    fn load_files(byte_stream: &mut ByteStream) -> Result<Vec<String>, Ds1Error> {
+        let num_files = byte_stream.stream_u32()?;
+
+        (0..num_files)
+            .map(|_| {
+                let name = byte_stream.stream_null_terminated()?;
+                Ok(String::from_utf8_lossy(name).into_owned())
+            })
+            .collect()
+    }
+
+    fn load_num_layers(byte_stream: &mut ByteStream, max_layers: i32) -> Result<usize, Ds1Error> {
+        let num_layers = byte_stream.stream_i32()?;
+
+        if !(0..=max_layers).contains(&num_layers) {
+            return Err(Ds1Error::new(
+                byte_stream.offset() - 4,
+                Ds1ErrorReason::TooManyLayers(num_layers),
+            ));
+        }
+
+        Ok(num_layers as usize)
+    }
+
+    fn layer_types(
+        version: i32,
+        tag_type: u32,
+        num_walls: usize,
+        num_floors: usize,
+    ) -> Vec<LayerType> {
+        if version < 4 {
+            return vec![
+                LayerType::Wall(0),
+                LayerType::Floor(0),
+                LayerType::Orientation(0),
+                LayerType::Substitution,
+                LayerType::Shadow,
+            ];
+        }
+
+        let mut layer_types = Vec::new();
+
+        for wall in 0..num_walls {
+            layer_types.push(LayerType::Wall(wall));
+            layer_types.push(LayerType::Orientation(wall));
+        }
+
+        layer_types.extend((0..num_floors).map(LayerType::Floor));
+        layer_types.push(LayerType::Shadow);
+
+        if tag_type == 1 || tag_type == 2 {
+            layer_types.push(LayerType::Substitution);
+        }
+
+        layer_types
+    }
+
+    fn load_layer(
+        &mut self,
+        byte_stream: &mut ByteStream,
+        layer_type: &LayerType,
+    ) -> Result<(), Ds1Error> {
+        let num_cells = self.width as usize * self.height as usize;
+        let bytes = byte_stream.stream_bytes(num_cells * 4)?;
+        let values = bytes
+            .chunks_exact(4)
+            .map(|value| u32::from_le_bytes(value.try_into().unwrap()));
+
+        match *layer_type {
+            LayerType::Wall(wall) => {
+                for (cell, value) in self.walls[wall].iter_mut().zip(values) {
+                    cell.tile = TileCell::new(value);
+                }
+            }
+            LayerType::Orientation(wall) => {
+                for (cell, value) in self.walls[wall].iter_mut().zip(values) {
+                    let orientation = value as u8;
+                    cell.orientation = if self.version < 7 {
+                        ORIENTATION_LOOKUP
+                            .get(usize::from(orientation))
+                            .copied()
+                            .unwrap_or(orientation)
+                    } else {
+                        orientation
+                    };
+                }
+            }
+            LayerType::Floor(floor) => {
+                for (cell, value) in self.floors[floor].iter_mut().zip(values) {
+                    *cell = TileCell::new(value);
+                }
+            }
+            LayerType::Shadow => {
+                for (cell, value) in self.shadows.iter_mut().zip(values) {
+                    *cell = TileCell::new(value);
+                }
+            }
+            LayerType::Substitution => {
+                self.substitutions = values.collect();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_objects(byte_stream: &mut ByteStream, version: i32) -> Result<Vec<Object>, Ds1Error> {
+        let num_objects = byte_stream.stream_u32()?;
+
+        (0..num_objects)
+            .map(|_| {
+                let object_type = byte_stream.stream_i32()?;
+                let id = byte_stream.stream_i32()?;
+                let x = byte_stream.stream_i32()?;
+                let y = byte_stream.stream_i32()?;
+                let flags = if version > 5 {
+                    byte_stream.stream_i32()?
+                } else {
+                    0
+                };
+
+                Ok(Object {
+                    object_type,
+                    id,
+                    x,
+                    y,
+                    flags,
+                    path: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    fn load_substitution_groups(
+        byte_stream: &mut ByteStream,
+        version: i32,
+    ) -> Result<Vec<SubstitutionGroup>, Ds1Error> {
+        if version >= 18 {
+            // unknown
+            byte_stream.skip_bytes(4)?;
+        }
+
+        let num_groups = byte_stream.stream_u32()?;
+
+        (0..num_groups)
+            .map(|_| {
+                let tile_x = byte_stream.stream_i32()?;
+                let tile_y = byte_stream.stream_i32()?;
+                let width = byte_stream.stream_i32()?;
+                let height = byte_stream.stream_i32()?;
+                let unknown = if version >= 13 {
+                    byte_stream.stream_i32()?
+                } else {
+                    0
+                };
+
+                Ok(SubstitutionGroup {
+                    tile_x,
+                    tile_y,
+                    width,
+                    height,
+                    unknown,
+                })
+            })
+            .collect()
+    }
+
+    /// Loads the paths of the NPCs into the objects at the same positions
+    fn load_npc_paths(&mut self, byte_stream: &mut ByteStream) -> Result<(), Ds1Error> {
+        let num_paths = byte_stream.stream_u32()?;
+
+        for _ in 0..num_paths {
+            let num_points = byte_stream.stream_u32()?;
+            let x = byte_stream.stream_i32()?;
+            let y = byte_stream.stream_i32()?;
+
+            let path = (0..num_points)
+                .map(|_| {
+                    let x = byte_stream.stream_i32()?;
+                    let y = byte_stream.stream_i32()?;
+                    let action = if self.version >= 15 {
+                        byte_stream.stream_i32()?
+                    } else {
+                        1
+                    };
+
+                    Ok(PathPoint { x, y, action })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if let Some(object) = self
+                .objects
+                .iter_mut()
+                .find(|object| object.x == x && object.y == y)
+            {
+                object.path = path;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TileCell {
+    fn new(value: u32) -> Self {
+        Self {
+            prop1: value as u8,
+            sequence: ((value >> 8) & 0x3F) as u8,
+            unknown1: ((value >> 14) & 0x3F) as u8,
+            style: ((value >> 20) & 0x3F) as u8,
+            unknown2: ((value >> 26) & 0x1F) as u8,
+            hidden: value & 0x8000_0000 != 0,
+        }
+    }
+
+    /// An empty cell has no tile
+    pub fn is_empty(&self) -> bool {
+        self.prop1 == 0
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn tile_value(style: u32, sequence: u32) -> u32 {
+        1 | sequence << 8 | style << 20
+    }
+
+    // a version 18 map of 3x1 tiles with a floor tile, a wall tile and an NPC with a path
+    pub(crate) fn ds1_file() -> Vec<u8> {
+        let mut data = Vec::new();
+        for value in [18_u32, 2, 0, 1, 0, 1] {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(b"tiles.dt1\0");
+        // walls, floors
+        data.extend(1_u32.to_le_bytes());
+        data.extend(1_u32.to_le_bytes());
+
+        let layers = [
+            [0, tile_value(3, 0), 0],
+            [0, 1, 0],
+            [tile_value(1, 2), 0, 0],
+            [0, 0, 0],
+        ];
+        for value in layers.iter().flatten() {
+            data.extend(value.to_le_bytes());
+        }
+
+        // an object, an NPC path of 2 points
+        for value in [1_u32, 1, 2, 3, 4, 0, 1, 2, 3, 4, 5, 6, 1, 7, 8, 2] {
+            data.extend(value.to_le_bytes());
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_load_ds1() {
+        let ds1 = Ds1::try_new(&ds1_file()).unwrap();
+
+        assert_eq!((ds1.width, ds1.height, ds1.act), (3, 1, 1));
+        assert_eq!(ds1.files, ["tiles.dt1"]);
+        assert_eq!(ds1.walls.len(), 1);
+        assert_eq!(ds1.walls[0][1].tile.style, 3);
+        assert_eq!(ds1.walls[0][1].orientation, 1);
+        assert!(ds1.walls[0][0].tile.is_empty());
+        assert_eq!(ds1.floors[0][ds1.cell_index(0, 0)].sequence, 2);
+        assert!(ds1.substitutions.is_empty());
+
+        assert_eq!(ds1.objects.len(), 1);
+        assert_eq!((ds1.objects[0].x, ds1.objects[0].y), (3, 4));
+        assert_eq!(
+            ds1.objects[0].path,
+            [
+                PathPoint {
+                    x: 5,
+                    y: 6,
+                    action: 1
+                },
+                PathPoint {
+                    x: 7,
+                    y: 8,
+                    action: 2
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let data = ds1_file();
+
+        for len in 0..data.len() {
+            let error = Ds1::try_new(&data[..len]).unwrap_err();
+            assert_eq!(error.reason, Ds1ErrorReason::UnexpectedEnd);
+        }
+
+        let mut data = ds1_file();
+        data[0] = 19;
+        let error = Ds1::try_new(&data).unwrap_err();
+        assert_eq!(error.reason, Ds1ErrorReason::UnsupportedVersion(19));
+
+        let mut data = ds1_file();
+        data[4..8].copy_from_slice(&(-1_i32).to_le_bytes());
+        let error = Ds1::try_new(&data).unwrap_err();
+        assert_eq!(error.byte_offset, 4);
+        assert_eq!(
+            error.reason,
+            Ds1ErrorReason::MapTooLarge {
+                width: 0,
+                height: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+
+/// Why a DS1 file couldn't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ds1ErrorReason {
+    /// a stream read past the end of the file
+    UnexpectedEnd,
+    UnsupportedVersion(i32),
+    /// the map is larger than any map of the game
+    MapTooLarge {
+        width: i32,
+        height: i32,
+    },
+    /// there are more layers of a kind than the game supports
+    TooManyLayers(i32),
+}
+
+/// A malformed DS1 file, with the position in the file where the problem was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ds1Error {
+    pub byte_offset: usize,
+    pub reason: Ds1ErrorReason,
+}
+
+impl Ds1Error {
+    pub(crate) fn new(byte_offset: usize, reason: Ds1ErrorReason) -> Self {
+        Self {
+            byte_offset,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Ds1ErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ds1ErrorReason::UnexpectedEnd => write!(f, "unexpected end of data"),
+            Ds1ErrorReason::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {version}")
+            }
+            Ds1ErrorReason::MapTooLarge { width, height } => {
+                write!(f, "map of {width}x{height} tiles is too large")
+            }
+            Ds1ErrorReason::TooManyLayers(num_layers) => {
+                write!(f, "{num_layers} layers are too many")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Ds1Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.reason, self.byte_offset)
+    }
+}
+
+impl std::error::Error for Ds1Error {}
@@ -0,0 +1,8 @@
+mod byte_stream;
+mod ds1_decoder;
+mod error;
+mod walkability;
+
+pub use crate::ds1_decoder::{Ds1, Object, PathPoint, SubstitutionGroup, TileCell, WallCell};
+pub use crate::error::{Ds1Error, Ds1ErrorReason};
+pub use crate::walkability::WalkabilityGrid;
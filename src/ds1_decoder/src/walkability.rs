@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use dt1_decoder::{Dt1, SubTileFlags};
+
+use crate::ds1_decoder::Ds1;
+
+const SUB_TILES_PER_SIDE: u32 = 5;
+const FLOOR_ORIENTATION: u32 = 0;
+
+type SubTilesFlags = [[SubTileFlags; 5]; 5];
+
+/// The flags of the sub tiles of a map, each tile of the map has 5x5 sub tiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalkabilityGrid {
+    /// width and height in sub tiles
+    pub width: u32,
+    pub height: u32,
+    /// the flags of the sub tiles in row major order
+    pub flags: Vec<SubTileFlags>,
+}
+
+impl WalkabilityGrid {
+    /// Combines the flags of the floor and wall tiles of the map. The tiles are looked up
+    /// in the DT1 files by their orientation, main index and sub index, the first matching
+    /// tile is used. The sub tiles without a floor or wall tile block walking.
+    pub fn new(ds1: &Ds1, dt1_files: &[Dt1]) -> Self {
+        let tiles = Self::tiles_flags(dt1_files);
+
+        let width = ds1.width * SUB_TILES_PER_SIDE;
+        let height = ds1.height * SUB_TILES_PER_SIDE;
+
+        let mut grid = Self {
+            width,
+            height,
+            flags: vec![SubTileFlags::default(); width as usize * height as usize],
+        };
+        let mut has_tile = vec![false; ds1.width as usize * ds1.height as usize];
+
+        let floors = ds1.floors.iter().flat_map(|floor| {
+            floor
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| (i, cell, FLOOR_ORIENTATION))
+        });
+        let walls = ds1.walls.iter().flat_map(|wall| {
+            wall.iter()
+                .enumerate()
+                .map(|(i, cell)| (i, &cell.tile, u32::from(cell.orientation)))
+        });
+
+        for (i, cell, orientation) in floors.chain(walls) {
+            if cell.is_empty() {
+                continue;
+            }
+
+            let key = (orientation, u32::from(cell.style), u32::from(cell.sequence));
+            if let Some(sub_tiles_flags) = tiles.get(&key) {
+                let row = i as u32 / ds1.width;
+                let col = i as u32 % ds1.width;
+                grid.add_tile_flags(row, col, sub_tiles_flags);
+                has_tile[i] = true;
+            }
+        }
+
+        for (i, _) in has_tile
+            .iter()
+            .enumerate()
+            .filter(|(_, &has_tile)| !has_tile)
+        {
+            let row = i as u32 / ds1.width;
+            let col = i as u32 % ds1.width;
+            let blocked = [[SubTileFlags(SubTileFlags::BLOCK_WALK); 5]; 5];
+            grid.add_tile_flags(row, col, &blocked);
+        }
+
+        grid
+    }
+
+    pub fn get(&self, row: u32, col: u32) -> SubTileFlags {
+        self.flags[row as usize * self.width as usize + col as usize]
+    }
+
+    pub fn is_walkable(&self, row: u32, col: u32) -> bool {
+        !self.get(row, col).blocks_walk()
+    }
+
+    fn tiles_flags(dt1_files: &[Dt1]) -> HashMap<(u32, u32, u32), SubTilesFlags> {
+        let mut tiles = HashMap::new();
+
+        for tile in dt1_files.iter().flat_map(|dt1| &dt1.tiles) {
+            let key = (
+                tile.header.orientation,
+                tile.header.main_index,
+                tile.header.sub_index,
+            );
+            tiles.entry(key).or_insert_with(|| tile.sub_tile_flags());
+        }
+
+        tiles
+    }
+
+    // the flags are in the order of the DT1 files, the bottom row first
+    fn add_tile_flags(&mut self, tile_row: u32, tile_col: u32, sub_tiles_flags: &SubTilesFlags) {
+        for (i, row_flags) in sub_tiles_flags.iter().enumerate() {
+            let sub_row = SUB_TILES_PER_SIDE - 1 - i as u32;
+
+            for (sub_col, flags) in row_flags.iter().enumerate() {
+                let row = tile_row * SUB_TILES_PER_SIDE + sub_row;
+                let col = tile_col * SUB_TILES_PER_SIDE + sub_col as u32;
+                let idx = row as usize * self.width as usize + col as usize;
+                self.flags[idx] = SubTileFlags(self.flags[idx].0 | flags.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ds1_decoder::tests::ds1_file;
+
+    // tiles without sub tiles, with the flags of one sub tile set
+    fn dt1_file(tiles: &[(u32, u32, u32, usize, u8)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(7_u32.to_le_bytes());
+        data.extend(6_u32.to_le_bytes());
+        data.extend([0; 260]);
+        data.extend((tiles.len() as u32).to_le_bytes());
+        data.extend(276_u32.to_le_bytes());
+
+        for &(orientation, main_index, sub_index, sub_tile, flags) in tiles {
+            let mut tile_header = [0; 96];
+            tile_header[20..24].copy_from_slice(&orientation.to_le_bytes());
+            tile_header[24..28].copy_from_slice(&main_index.to_le_bytes());
+            tile_header[28..32].copy_from_slice(&sub_index.to_le_bytes());
+            tile_header[40 + sub_tile] = flags;
+            data.extend(tile_header);
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_walkability_grid() {
+        let ds1 = Ds1::try_new(&ds1_file()).unwrap();
+        let dt1_data = dt1_file(&[
+            (0, 1, 2, 0, SubTileFlags::BLOCK_JUMP),
+            (1, 3, 0, 24, SubTileFlags::BLOCK_WALK),
+            (0, 1, 2, 0, SubTileFlags::BLOCK_WALK),
+        ]);
+        let dt1 = Dt1::try_new(&dt1_data).unwrap();
+
+        let grid = WalkabilityGrid::new(&ds1, &[dt1]);
+
+        assert_eq!((grid.width, grid.height), (15, 5));
+        // the first stored sub tile is at the bottom left of the tile
+        assert!(grid.get(4, 0).blocks_jump());
+        assert!(!grid.get(0, 0).blocks_jump());
+        assert!(grid.is_walkable(4, 0));
+        assert!(grid.is_walkable(0, 5));
+        // and the last one at the top right
+        assert!(!grid.is_walkable(0, 9));
+        assert!(grid.is_walkable(4, 9));
+        // the last tile has neither a floor nor a wall
+        assert!(!grid.is_walkable(2, 12));
+    }
+}
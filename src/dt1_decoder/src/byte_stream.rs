@@ -1,3 +1,5 @@
+use crate::error::{Dt1Error, Dt1ErrorReason};
+
 pub struct ByteStream<'bytes> {
     bytes: &'bytes [u8],
     offset: usize,
@@ -8,59 +10,49 @@ impl<'bytes> ByteStream<'bytes> {
         Self { bytes, offset: 0 }
     }
 
-    pub fn stream_i16(&mut self) -> i16 {
-        i16::from_le_bytes(self.stream_two_bytes())
+    pub fn offset(&self) -> usize {
+        self.offset
     }
 
-    pub fn stream_i32(&mut self) -> i32 {
-        i32::from_le_bytes(self.stream_four_bytes())
+    pub fn error(&self, reason: Dt1ErrorReason) -> Dt1Error {
+        Dt1Error::new(self.offset, reason)
     }
 
-    pub fn stream_u16(&mut self) -> u16 {
-        u16::from_le_bytes(self.stream_two_bytes())
+    pub fn stream_i16(&mut self) -> Result<i16, Dt1Error> {
+        Ok(i16::from_le_bytes(self.stream_array()?))
     }
 
-    pub fn stream_u32(&mut self) -> u32 {
-        u32::from_le_bytes(self.stream_four_bytes())
+    pub fn stream_i32(&mut self) -> Result<i32, Dt1Error> {
+        Ok(i32::from_le_bytes(self.stream_array()?))
     }
 
-    pub fn stream_u8(&mut self) -> u8 {
-        let byte = self.bytes[self.offset];
-        self.offset += 1;
-        byte
+    pub fn stream_u16(&mut self) -> Result<u16, Dt1Error> {
+        Ok(u16::from_le_bytes(self.stream_array()?))
     }
 
-    pub fn skip_bytes(&mut self, num_bytes: u32) {
-        self.offset += num_bytes as usize;
+    pub fn stream_u32(&mut self) -> Result<u32, Dt1Error> {
+        Ok(u32::from_le_bytes(self.stream_array()?))
     }
 
-    pub fn stream_bytes(&mut self, num_bytes: u32) -> &'bytes [u8] {
-        let byte_slice = &self.bytes[self.offset..self.offset + num_bytes as usize];
-        self.offset += num_bytes as usize;
-        byte_slice
+    pub fn stream_u8(&mut self) -> Result<u8, Dt1Error> {
+        Ok(self.stream_bytes(1)?[0])
     }
 
-    pub fn set_offset(&mut self, offset: usize) {
-        self.offset = offset;
+    pub fn stream_array<const N: usize>(&mut self) -> Result<[u8; N], Dt1Error> {
+        Ok(self.stream_bytes(N as u32)?.try_into().unwrap())
     }
 
-    fn stream_two_bytes(&mut self) -> [u8; 2] {
-        let bytes: [u8; 2] = self.bytes[self.offset..self.offset + 2]
-            .try_into()
-            .expect("slice with incorrect length");
-
-        self.offset += 2;
-
-        bytes
+    pub fn stream_bytes(&mut self, num_bytes: u32) -> Result<&'bytes [u8], Dt1Error> {
+        let byte_slice = self
+            .offset
+            .checked_add(num_bytes as usize)
+            .and_then(|end| self.bytes.get(self.offset..end))
+            .ok_or_else(|| self.error(Dt1ErrorReason::UnexpectedEnd))?;
+        self.offset += num_bytes as usize;
+        Ok(byte_slice)
     }
 
-    fn stream_four_bytes(&mut self) -> [u8; 4] {
-        let bytes: [u8; 4] = self.bytes[self.offset..self.offset + 4]
-            .try_into()
-            .expect("slice with incorrect length");
-
-        self.offset += 4;
-
-        bytes
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset;
     }
 }
@@ -1,4 +1,15 @@
-use crate::byte_stream::ByteStream;
+use crate::{
+    byte_stream::ByteStream,
+    error::{Dt1Error, Dt1ErrorReason},
+    sub_tile_flags::SubTileFlags,
+};
+
+const DT1_VERSION: (u32, u32) = (7, 6);
+const TILE_HEADERS_OFFSET: u32 = 276;
+const ISOMETRIC_SUB_TILE_SIZE: usize = 256;
+const SUB_TILES_PER_SIDE: usize = 5;
+// Largest height and width of a tile, far larger than the walls of the game
+const MAX_TILE_SIZE: u32 = 4096;
 
 #[derive(Debug)]
 pub struct Dt1<'dt1_file> {
@@ -39,6 +50,15 @@ pub struct TileHeader {
     pub unknown_bytes_4: [u8; 12],
 }
 
+/// A palette indexed image of a tile, the pixels which aren't drawn are 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileImage {
+    pub width: u32,
+    pub height: u32,
+    /// the rows of the image from top to bottom
+    pub pixels: Vec<u8>,
+}
+
 #[derive(Debug)]
 pub struct SubTile<'dt1_file> {
     pub header: SubTileHeader,
@@ -59,82 +79,92 @@ pub struct SubTileHeader {
 }
 
 impl<'dt1_file> Dt1<'dt1_file> {
+    /// Parses a DT1 file, panics if the file is malformed
     pub fn new(data: &'dt1_file [u8]) -> Self {
+        Self::try_new(data).unwrap_or_else(|e| panic!("Invalid dt1 file: {e}"))
+    }
+
+    pub fn try_new(data: &'dt1_file [u8]) -> Result<Self, Dt1Error> {
         let mut byte_stream = ByteStream::new(data);
 
-        let header = Dt1Header::new(&mut byte_stream);
+        let header = Dt1Header::new(&mut byte_stream)?;
 
         let tiles_headers: Vec<TileHeader> = (0..header.num_tiles)
             .map(|_| TileHeader::new(&mut byte_stream))
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         let tiles: Vec<Tile> = tiles_headers
             .into_iter()
             .map(|tile_header| Tile::new(&mut byte_stream, tile_header))
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        Self { header, tiles }
+        Ok(Self { header, tiles })
     }
 }
 
 impl Dt1Header {
-    pub fn new(byte_stream: &mut ByteStream) -> Self {
-        let version1 = byte_stream.stream_u32();
-        let version2 = byte_stream.stream_u32();
-
-        let mut is_dt1 = true;
-
-        if version1 != 7 || version2 != 6 {
-            is_dt1 = false;
+    pub fn new(byte_stream: &mut ByteStream) -> Result<Self, Dt1Error> {
+        let version1 = byte_stream.stream_u32()?;
+        let version2 = byte_stream.stream_u32()?;
+
+        if (version1, version2) != DT1_VERSION {
+            return Err(Dt1Error::new(
+                0,
+                Dt1ErrorReason::UnsupportedVersion { version1, version2 },
+            ));
         }
 
         for _ in 0..260 {
-            let c = byte_stream.stream_u8();
+            let c = byte_stream.stream_u8()?;
             if c != 0 {
-                is_dt1 = false;
-                break;
+                return Err(Dt1Error::new(
+                    byte_stream.offset() - 1,
+                    Dt1ErrorReason::InvalidHeader,
+                ));
             }
         }
 
-        let num_tiles = byte_stream.stream_u32();
+        let num_tiles = byte_stream.stream_u32()?;
 
-        let offset = byte_stream.stream_u32();
-
-        if offset != 276 {
-            is_dt1 = false;
-        }
+        let offset = byte_stream.stream_u32()?;
 
-        if !is_dt1 {
-            panic!("Not a dt1 file");
+        if offset != TILE_HEADERS_OFFSET {
+            return Err(Dt1Error::new(
+                byte_stream.offset() - 4,
+                Dt1ErrorReason::InvalidHeader,
+            ));
         }
 
-        Self {
+        Ok(Self {
             version1,
             version2,
             num_tiles,
-        }
+        })
     }
 }
 
 impl<'dt1_file> Tile<'dt1_file> {
-    pub fn new(byte_stream: &mut ByteStream<'dt1_file>, tile_header: TileHeader) -> Self {
+    pub fn new(
+        byte_stream: &mut ByteStream<'dt1_file>,
+        tile_header: TileHeader,
+    ) -> Result<Self, Dt1Error> {
         byte_stream.set_offset(tile_header.tiles_ptr as usize);
 
         let sub_tiles_headers: Vec<SubTileHeader> = (0..tile_header.num_tiles)
             .map(|_| SubTileHeader::new(byte_stream))
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         let sub_tiles: Vec<SubTile> = sub_tiles_headers
             .into_iter()
             .map(|sub_tile_header| {
                 SubTile::new(byte_stream, tile_header.tiles_ptr as usize, sub_tile_header)
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        Self {
+        Ok(Self {
             header: tile_header,
             sub_tiles,
-        }
+        })
     }
 
     pub fn draw<F>(&self, mut draw: F)
@@ -143,12 +173,13 @@ impl<'dt1_file> Tile<'dt1_file> {
     {
         let row_offset = match orientation_to_coordinate_system_type(self.header.orientation) {
             CoordinateSystemType::Positive => 0,
-            CoordinateSystemType::Negative => self.header.height as i16,
+            CoordinateSystemType::Negative => self.header.height as i32,
         };
 
         for sub_tile in &self.sub_tiles {
             let sub_tile_draw = |row: u32, col: u32, val: u8| {
-                let row = (sub_tile.header.y_position + row_offset) as u32 + row;
+                let row = (i32::from(sub_tile.header.y_position).wrapping_add(row_offset) as u32)
+                    .wrapping_add(row);
                 let col = u32::from(sub_tile.header.x_position) + col;
 
                 draw(row, col, val);
@@ -157,6 +188,62 @@ impl<'dt1_file> Tile<'dt1_file> {
             sub_tile.draw(sub_tile_draw);
         }
     }
+
+    /// Renders the tile into an image of the width and height of the tile
+    pub fn render(&self) -> TileImage {
+        let mut pixels = vec![0; self.header.width as usize * self.header.height as usize];
+        self.render_into(&mut pixels, self.header.width, 0, 0);
+
+        TileImage {
+            width: self.header.width,
+            height: self.header.height,
+            pixels,
+        }
+    }
+
+    /// Renders the tile with its top left corner at `row` and `col` of a target with rows
+    /// of `target_width` pixels, the pixels outside of the target are clipped
+    pub fn render_into(&self, target: &mut [u8], target_width: u32, row: i32, col: i32) {
+        let target_width = target_width as usize;
+        let target_height = target.len().checked_div(target_width).unwrap_or(0);
+
+        self.draw(|tile_row, tile_col, val| {
+            // negative rows and columns wrap around
+            let target_row = i64::from(row) + i64::from(tile_row as i32);
+            let target_col = i64::from(col) + i64::from(tile_col as i32);
+
+            if let (Ok(target_row), Ok(target_col)) =
+                (usize::try_from(target_row), usize::try_from(target_col))
+            {
+                if target_row < target_height && target_col < target_width {
+                    target[target_row * target_width + target_col] = val;
+                }
+            }
+        });
+    }
+
+    /// Returns the flags of the 5x5 sub tiles in the order in which they are stored, the
+    /// bottom row first. Each row goes from the left to the right, see [`Tile::sub_tile_flag`]
+    /// for the flags by their row from the top.
+    pub fn sub_tile_flags(&self) -> [[SubTileFlags; SUB_TILES_PER_SIDE]; SUB_TILES_PER_SIDE] {
+        let mut flags = [[SubTileFlags::default(); SUB_TILES_PER_SIDE]; SUB_TILES_PER_SIDE];
+
+        for (i, &flag) in self.header.sub_tiles_flags.iter().enumerate() {
+            flags[i / SUB_TILES_PER_SIDE][i % SUB_TILES_PER_SIDE] = SubTileFlags(flag);
+        }
+
+        flags
+    }
+
+    /// Returns the flags of a sub tile by its row from the top and its column from the left,
+    /// panics if the row or the column is out of the 5x5 sub tiles
+    pub fn sub_tile_flag(&self, row: usize, col: usize) -> SubTileFlags {
+        assert!(row < SUB_TILES_PER_SIDE && col < SUB_TILES_PER_SIDE);
+
+        SubTileFlags(
+            self.header.sub_tiles_flags[(SUB_TILES_PER_SIDE - 1 - row) * SUB_TILES_PER_SIDE + col],
+        )
+    }
 }
 
 fn orientation_to_coordinate_system_type(orientation: u32) -> CoordinateSystemType {
@@ -172,52 +259,38 @@ enum CoordinateSystemType {
 }
 
 impl TileHeader {
-    pub fn new(byte_stream: &mut ByteStream) -> Self {
-        let direction = byte_stream.stream_u32();
-
-        let flags = byte_stream.stream_u32();
+    pub fn new(byte_stream: &mut ByteStream) -> Result<Self, Dt1Error> {
+        let direction = byte_stream.stream_u32()?;
 
-        let height = byte_stream.stream_i32().unsigned_abs();
-        let width = byte_stream.stream_u32();
+        let flags = byte_stream.stream_u32()?;
 
-        let mut unknown_bytes_1 = [0; 4];
+        let height = byte_stream.stream_i32()?.unsigned_abs();
+        let width = byte_stream.stream_u32()?;
 
-        for b in &mut unknown_bytes_1 {
-            *b = byte_stream.stream_u8();
+        if width > MAX_TILE_SIZE || height > MAX_TILE_SIZE {
+            return Err(byte_stream.error(Dt1ErrorReason::TileTooLarge { width, height }));
         }
 
-        let orientation = byte_stream.stream_u32();
-        let main_index = byte_stream.stream_u32();
-        let sub_index = byte_stream.stream_u32();
-        let frame_index = byte_stream.stream_u32();
+        let unknown_bytes_1 = byte_stream.stream_array()?;
 
-        let mut unknown_bytes_2 = [0; 4];
-        for b in &mut unknown_bytes_2 {
-            *b = byte_stream.stream_u8();
-        }
+        let orientation = byte_stream.stream_u32()?;
+        let main_index = byte_stream.stream_u32()?;
+        let sub_index = byte_stream.stream_u32()?;
+        let frame_index = byte_stream.stream_u32()?;
 
-        let mut sub_tiles_flags = [0; 25];
-        for b in &mut sub_tiles_flags {
-            *b = byte_stream.stream_u8();
-        }
+        let unknown_bytes_2 = byte_stream.stream_array()?;
+        let sub_tiles_flags = byte_stream.stream_array()?;
+        let unknown_bytes_3 = byte_stream.stream_array()?;
 
-        let mut unknown_bytes_3 = [0; 7];
-        for b in &mut unknown_bytes_3 {
-            *b = byte_stream.stream_u8();
-        }
-
-        let tiles_ptr = byte_stream.stream_u32();
+        let tiles_ptr = byte_stream.stream_u32()?;
 
-        let tiles_length = byte_stream.stream_u32();
+        let tiles_length = byte_stream.stream_u32()?;
 
-        let num_tiles = byte_stream.stream_u32();
+        let num_tiles = byte_stream.stream_u32()?;
 
-        let mut unknown_bytes_4 = [0; 12];
-        for b in &mut unknown_bytes_4 {
-            *b = byte_stream.stream_u8();
-        }
+        let unknown_bytes_4 = byte_stream.stream_array()?;
 
-        Self {
+        Ok(Self {
             direction,
             flags,
             height,
@@ -234,7 +307,7 @@ impl TileHeader {
             tiles_length,
             num_tiles,
             unknown_bytes_4,
-        }
+        })
     }
 }
 
@@ -243,15 +316,32 @@ impl<'dt1_file> SubTile<'dt1_file> {
         byte_stream: &mut ByteStream<'dt1_file>,
         tile_offset: usize,
         sub_tile_header: SubTileHeader,
-    ) -> Self {
-        byte_stream.set_offset(tile_offset + sub_tile_header.data_offset as usize);
+    ) -> Result<Self, Dt1Error> {
+        let data_offset = tile_offset + sub_tile_header.data_offset as usize;
+        byte_stream.set_offset(data_offset);
 
-        let data = byte_stream.stream_bytes(sub_tile_header.data_length);
+        let data = byte_stream.stream_bytes(sub_tile_header.data_length)?;
 
-        Self {
+        let sub_tile = Self {
             header: sub_tile_header,
             data,
+        };
+
+        // the data is checked once so that drawing can't read past its end
+        let is_valid = if sub_tile.header.format == 1 {
+            data.len() >= ISOMETRIC_SUB_TILE_SIZE
+        } else {
+            sub_tile.walk_sub_tile_normal(|_, _, _| {})
+        };
+
+        if !is_valid {
+            return Err(Dt1Error::new(
+                data_offset,
+                Dt1ErrorReason::InvalidSubTileData,
+            ));
         }
+
+        Ok(sub_tile)
     }
 
     fn draw<F>(&self, draw: F)
@@ -265,7 +355,15 @@ impl<'dt1_file> SubTile<'dt1_file> {
         }
     }
 
-    fn draw_sub_tile_normal<F>(&self, mut draw: F)
+    fn draw_sub_tile_normal<F>(&self, draw: F)
+    where
+        F: FnMut(u32, u32, u8),
+    {
+        self.walk_sub_tile_normal(draw);
+    }
+
+    /// Draws the run length encoded pixels, returns false if the data ends within a run
+    fn walk_sub_tile_normal<F>(&self, mut draw: F) -> bool
     where
         F: FnMut(u32, u32, u8),
     {
@@ -277,23 +375,31 @@ impl<'dt1_file> SubTile<'dt1_file> {
             let col_jump = self.data[c];
             c += 1;
 
-            col_offset += u32::from(col_jump);
+            col_offset = col_offset.wrapping_add(u32::from(col_jump));
 
-            let num_pixels = self.data[c];
+            let Some(&num_pixels) = self.data.get(c) else {
+                return false;
+            };
             c += 1;
 
             if col_jump != 0 || num_pixels != 0 {
-                for col in col_offset..col_offset + u32::from(num_pixels) {
-                    draw(row, col, self.data[c]);
-                    c += 1;
+                let Some(pixels) = self.data.get(c..c + num_pixels as usize) else {
+                    return false;
+                };
+
+                for (i, pixel) in pixels.iter().enumerate() {
+                    draw(row, col_offset.wrapping_add(i as u32), *pixel);
                 }
 
-                col_offset += u32::from(num_pixels);
+                c += pixels.len();
+                col_offset = col_offset.wrapping_add(u32::from(num_pixels));
             } else {
                 row += 1;
                 col_offset = 0;
             }
         }
+
+        true
     }
 
     fn draw_sub_tile_isometric<F>(&self, mut draw: F)
@@ -330,30 +436,24 @@ impl<'dt1_file> SubTile<'dt1_file> {
 }
 
 impl SubTileHeader {
-    pub fn new(byte_stream: &mut ByteStream) -> Self {
-        let x_position = byte_stream.stream_u16();
-        let y_position = byte_stream.stream_i16();
+    pub fn new(byte_stream: &mut ByteStream) -> Result<Self, Dt1Error> {
+        let x_position = byte_stream.stream_u16()?;
+        let y_position = byte_stream.stream_i16()?;
 
-        let mut unknown_bytes_1 = [0; 2];
-        for b in &mut unknown_bytes_1 {
-            *b = byte_stream.stream_u8();
-        }
+        let unknown_bytes_1 = byte_stream.stream_array()?;
 
-        let x_grid_position = byte_stream.stream_u8();
-        let y_grid_position = byte_stream.stream_u8();
+        let x_grid_position = byte_stream.stream_u8()?;
+        let y_grid_position = byte_stream.stream_u8()?;
 
-        let format = byte_stream.stream_u16();
+        let format = byte_stream.stream_u16()?;
 
-        let data_length = byte_stream.stream_u32();
+        let data_length = byte_stream.stream_u32()?;
 
-        let mut unknown_bytes_2 = [0; 2];
-        for b in &mut unknown_bytes_2 {
-            *b = byte_stream.stream_u8();
-        }
+        let unknown_bytes_2 = byte_stream.stream_array()?;
 
-        let data_offset = byte_stream.stream_u32();
+        let data_offset = byte_stream.stream_u32()?;
 
-        Self {
+        Ok(Self {
             x_position,
             y_position,
             unknown_bytes_1,
@@ -363,6 +463,128 @@ impl SubTileHeader {
             data_length,
             unknown_bytes_2,
             data_offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a single tile with one run length encoded sub tile
+    fn dt1_file(sub_tile_data: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(7_u32.to_le_bytes());
+        data.extend(6_u32.to_le_bytes());
+        data.extend([0; 260]);
+        data.extend(1_u32.to_le_bytes());
+        data.extend(TILE_HEADERS_OFFSET.to_le_bytes());
+
+        let mut tile_header = [0; 96];
+        tile_header[8..12].copy_from_slice(&(-2_i32).to_le_bytes());
+        tile_header[12..16].copy_from_slice(&3_u32.to_le_bytes());
+        tile_header[72..76].copy_from_slice(&372_u32.to_le_bytes());
+        tile_header[80..84].copy_from_slice(&1_u32.to_le_bytes());
+        data.extend(tile_header);
+
+        let mut sub_tile_header = [0; 20];
+        sub_tile_header[10..14].copy_from_slice(&(sub_tile_data.len() as u32).to_le_bytes());
+        sub_tile_header[16..20].copy_from_slice(&20_u32.to_le_bytes());
+        data.extend(sub_tile_header);
+
+        data.extend(sub_tile_data);
+        data
+    }
+
+    #[test]
+    fn test_draw_tile() {
+        let data = dt1_file(&[1, 2, 5, 6, 0, 0, 0, 1, 7]);
+        let dt1 = Dt1::try_new(&data).unwrap();
+
+        let mut pixels = Vec::new();
+        dt1.tiles[0].draw(|row, col, val| pixels.push((row, col, val)));
+
+        assert_eq!(pixels, [(0, 1, 5), (0, 2, 6), (1, 0, 7)]);
+    }
+
+    #[test]
+    fn test_render_tile() {
+        let data = dt1_file(&[1, 2, 5, 6, 0, 0, 0, 1, 7]);
+        let dt1 = Dt1::try_new(&data).unwrap();
+
+        let image = dt1.tiles[0].render();
+
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.pixels, [0, 5, 6, 7, 0, 0]);
+
+        let mut target = vec![9; 3];
+        dt1.tiles[0].render_into(&mut target, 3, -1, 1);
+        assert_eq!(target, [9, 7, 9]);
+    }
+
+    #[test]
+    fn test_sub_tile_flags() {
+        let mut data = dt1_file(&[]);
+        // the sub tile flags start at byte 40 of the tile header
+        data[276 + 40 + 7] = SubTileFlags::BLOCK_WALK | SubTileFlags::BLOCK_LIGHT;
+        data[276 + 40 + 24] = SubTileFlags::BLOCK_LIGHT_AND_LINE_OF_SIGHT;
+        let dt1 = Dt1::try_new(&data).unwrap();
+
+        let flags = dt1.tiles[0].sub_tile_flags();
+
+        assert!(flags[1][2].blocks_walk());
+        assert!(flags[1][2].blocks_light());
+        assert!(!flags[1][2].blocks_line_of_sight());
+        assert!(flags[4][4].blocks_line_of_sight());
+        assert!(flags[4][4].blocks_light());
+        assert!(!flags[4][4].blocks_jump());
+        assert_eq!(flags[0][0], SubTileFlags(0));
+
+        // the flags are stored with the bottom row first
+        let tile = &dt1.tiles[0];
+        assert!(tile.sub_tile_flag(3, 2).blocks_walk());
+        assert!(tile.sub_tile_flag(0, 4).blocks_line_of_sight());
+        assert_eq!(tile.sub_tile_flag(4, 0), SubTileFlags(0));
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let data = dt1_file(&[1, 2, 5, 6, 0, 0, 0, 1, 7]);
+
+        for len in 0..data.len() {
+            let error = Dt1::try_new(&data[..len]).unwrap_err();
+            assert_eq!(error.reason, Dt1ErrorReason::UnexpectedEnd);
         }
+
+        let error = Dt1::try_new(&data[4..]).unwrap_err();
+        assert_eq!(
+            error.reason,
+            Dt1ErrorReason::UnsupportedVersion {
+                version1: 6,
+                version2: 0
+            }
+        );
+
+        let mut data = dt1_file(&[]);
+        data[100] = 1;
+        let error = Dt1::try_new(&data).unwrap_err();
+        assert_eq!(error.byte_offset, 100);
+        assert_eq!(error.reason, Dt1ErrorReason::InvalidHeader);
+
+        let mut data = dt1_file(&[]);
+        data[276 + 12..276 + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = Dt1::try_new(&data).unwrap_err();
+        assert_eq!(error.byte_offset, 276 + 16);
+        assert_eq!(
+            error.reason,
+            Dt1ErrorReason::TileTooLarge {
+                width: u32::MAX,
+                height: 2
+            }
+        );
+
+        let error = Dt1::try_new(&dt1_file(&[1, 2, 5])).unwrap_err();
+        assert_eq!(error.byte_offset, 392);
+        assert_eq!(error.reason, Dt1ErrorReason::InvalidSubTileData);
     }
 }
@@ -0,0 +1,61 @@
+use std::fmt;
+
+/// Why a DT1 file couldn't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dt1ErrorReason {
+    /// a stream read past the end of the file
+    UnexpectedEnd,
+    UnsupportedVersion {
+        version1: u32,
+        version2: u32,
+    },
+    /// the header has non-zero padding or an unexpected tile header offset
+    InvalidHeader,
+    /// the pixel data of a sub tile is shorter than its encoding requires
+    InvalidSubTileData,
+    /// the tile is larger than any tile of the game
+    TileTooLarge {
+        width: u32,
+        height: u32,
+    },
+}
+
+/// A malformed DT1 file, with the position in the file where the problem was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dt1Error {
+    pub byte_offset: usize,
+    pub reason: Dt1ErrorReason,
+}
+
+impl Dt1Error {
+    pub(crate) fn new(byte_offset: usize, reason: Dt1ErrorReason) -> Self {
+        Self {
+            byte_offset,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Dt1ErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dt1ErrorReason::UnexpectedEnd => write!(f, "unexpected end of data"),
+            Dt1ErrorReason::UnsupportedVersion { version1, version2 } => {
+                write!(f, "unsupported version {version1}.{version2}")
+            }
+            Dt1ErrorReason::InvalidHeader => write!(f, "not a dt1 file"),
+            Dt1ErrorReason::InvalidSubTileData => write!(f, "invalid sub tile data"),
+            Dt1ErrorReason::TileTooLarge { width, height } => {
+                write!(f, "tile of {width}x{height} pixels is too large")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Dt1Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.reason, self.byte_offset)
+    }
+}
+
+impl std::error::Error for Dt1Error {}
@@ -1,4 +1,8 @@
 mod byte_stream;
 mod dt1_decoder;
+mod error;
+mod sub_tile_flags;
 
-pub use crate::dt1_decoder::Dt1;
+pub use crate::dt1_decoder::{Dt1, Tile, TileImage};
+pub use crate::error::{Dt1Error, Dt1ErrorReason};
+pub use crate::sub_tile_flags::SubTileFlags;
@@ -0,0 +1,37 @@
+/// The flags of one of the 5x5 sub tiles of a tile, they describe what the sub tile blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubTileFlags(pub u8);
+
+impl SubTileFlags {
+    pub const BLOCK_WALK: u8 = 0x01;
+    pub const BLOCK_LIGHT_AND_LINE_OF_SIGHT: u8 = 0x02;
+    pub const BLOCK_JUMP: u8 = 0x04;
+    pub const BLOCK_PLAYER_WALK: u8 = 0x08;
+    pub const BLOCK_LIGHT: u8 = 0x20;
+
+    pub fn blocks_walk(self) -> bool {
+        self.has(Self::BLOCK_WALK)
+    }
+
+    pub fn blocks_line_of_sight(self) -> bool {
+        self.has(Self::BLOCK_LIGHT_AND_LINE_OF_SIGHT)
+    }
+
+    /// Jumping and teleporting
+    pub fn blocks_jump(self) -> bool {
+        self.has(Self::BLOCK_JUMP)
+    }
+
+    /// Blocks the player but not the monsters
+    pub fn blocks_player_walk(self) -> bool {
+        self.has(Self::BLOCK_PLAYER_WALK)
+    }
+
+    pub fn blocks_light(self) -> bool {
+        self.has(Self::BLOCK_LIGHT | Self::BLOCK_LIGHT_AND_LINE_OF_SIGHT)
+    }
+
+    fn has(self, flags: u8) -> bool {
+        self.0 & flags != 0
+    }
+}
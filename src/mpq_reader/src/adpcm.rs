@@ -0,0 +1,234 @@
+use byteorder::{ByteOrder, LittleEndian};
+use std::io::Error;
+
+const MAX_CHANNELS: usize = 2;
+const INITIAL_STEP_INDEX: usize = 0x2C;
+const MAX_STEP_INDEX: usize = 0x58;
+
+// Decrements the step index and repeats the last sample of the channel
+const COMMAND_DECREMENT_STEP: u8 = 0x80;
+// Increments the step index by 8 and keeps the current channel for the next sample
+const COMMAND_INCREMENT_STEP: u8 = 0x81;
+
+const NEXT_STEP_TABLE: [i32; 32] = [
+    -1, 0, -1, 4, -1, 2, -1, 6, -1, 1, -1, 5, -1, 3, -1, 7, -1, 1, -1, 5, -1, 3, -1, 7, -1, 2, -1,
+    4, -1, 6, -1, 8,
+];
+
+const STEP_SIZE_TABLE: [i32; MAX_STEP_INDEX + 1] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+struct SampleWriter<'a> {
+    out: &'a mut [u8],
+    written: usize,
+}
+
+impl SampleWriter<'_> {
+    // Returns false when the output buffer is full
+    fn write(&mut self, sample: i16) -> bool {
+        if self.written + 2 > self.out.len() {
+            return false;
+        }
+
+        LittleEndian::write_i16(&mut self.out[self.written..], sample);
+        self.written += 2;
+
+        true
+    }
+}
+
+/// Decompresses Storm's variant of IMA ADPCM into signed 16 bit little endian samples.
+///
+/// The compressed data starts with a zero byte and the bit shift used by the compressor.
+/// It is followed by the first sample of each channel and one byte per sample after that.
+/// Samples of the channels are interleaved.
+pub fn decompress(data: &[u8], out: &mut [u8], num_channels: usize) -> Result<usize, Error> {
+    if num_channels == 0 || num_channels > MAX_CHANNELS {
+        return Err(Error::other("Unsupported number of ADPCM channels"));
+    }
+
+    if data.len() < 2 {
+        return Err(Error::other("ADPCM data is missing its header"));
+    }
+
+    let bit_shift = u32::from(data[1]);
+
+    let mut writer = SampleWriter { out, written: 0 };
+    let mut predicted_samples = [0_i16; MAX_CHANNELS];
+    let mut step_indices = [INITIAL_STEP_INDEX; MAX_CHANNELS];
+
+    let mut pos = 2;
+
+    for predicted_sample in predicted_samples.iter_mut().take(num_channels) {
+        if pos + 2 > data.len() {
+            return Ok(writer.written);
+        }
+
+        *predicted_sample = LittleEndian::read_i16(&data[pos..]);
+        pos += 2;
+
+        if !writer.write(*predicted_sample) {
+            return Ok(writer.written);
+        }
+    }
+
+    let mut channel = num_channels - 1;
+
+    for &encoded_sample in &data[pos..] {
+        channel = (channel + 1) % num_channels;
+
+        match encoded_sample {
+            COMMAND_DECREMENT_STEP => {
+                step_indices[channel] = step_indices[channel].saturating_sub(1);
+
+                if !writer.write(predicted_samples[channel]) {
+                    break;
+                }
+            }
+            COMMAND_INCREMENT_STEP => {
+                step_indices[channel] = (step_indices[channel] + 8).min(MAX_STEP_INDEX);
+
+                // The next sample belongs to the same channel
+                channel = (channel + 1) % num_channels;
+            }
+            _ => {
+                let step_index = step_indices[channel];
+                let step_size = STEP_SIZE_TABLE[step_index];
+
+                predicted_samples[channel] = decode_sample(
+                    predicted_samples[channel],
+                    encoded_sample,
+                    step_size,
+                    bit_shift,
+                );
+
+                if !writer.write(predicted_samples[channel]) {
+                    break;
+                }
+
+                step_indices[channel] = get_next_step_index(step_index, encoded_sample);
+            }
+        }
+    }
+
+    Ok(writer.written)
+}
+
+fn decode_sample(predicted_sample: i16, encoded_sample: u8, step_size: i32, bit_shift: u32) -> i16 {
+    let mut difference = step_size >> bit_shift;
+
+    for bit in 0..6 {
+        if encoded_sample & (1 << bit) != 0 {
+            difference += step_size >> bit;
+        }
+    }
+
+    let sample = if encoded_sample & 0x40 != 0 {
+        i32::from(predicted_sample) - difference
+    } else {
+        i32::from(predicted_sample) + difference
+    };
+
+    sample.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16
+}
+
+fn get_next_step_index(step_index: usize, encoded_sample: u8) -> usize {
+    let step_index = step_index as i32 + NEXT_STEP_TABLE[(encoded_sample & 0x1F) as usize];
+
+    step_index.clamp(0, MAX_STEP_INDEX as i32) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use super::decompress;
+    use byteorder::{ByteOrder, LittleEndian};
+
+    fn to_samples(bytes: &[u8]) -> Vec<i16> {
+        bytes.chunks(2).map(LittleEndian::read_i16).collect()
+    }
+
+    #[test]
+    fn decompress_mono() {
+        // header, first sample 100, one sample, step decrement command, one negative sample
+        let data = [0x00, 0x02, 0x64, 0x00, 0x05, 0x80, 0x41];
+
+        let mut out = vec![0; 64];
+        let written = decompress(&data, &mut out, 1).unwrap();
+
+        // 0x05 with step size 494: 100 + (494 >> 2) + 494 + (494 >> 2) = 840
+        // 0x41 with step size 544: 840 - (544 >> 2) - 544 = 160
+        assert_eq!(to_samples(&out[..written]), [100, 840, 840, 160]);
+    }
+
+    #[test]
+    fn decompress_stereo() {
+        // header, first samples -10 and 10, one sample per channel, increment command, one sample
+        let data = [0x00, 0x00, 0xF6, 0xFF, 0x0A, 0x00, 0x40, 0x00, 0x81, 0x00];
+
+        let mut out = vec![0; 64];
+        let written = decompress(&data, &mut out, 2).unwrap();
+
+        // left: -10 - 494 = -504, right: 10 + 494 = 504
+        // the increment command raises the step index of the left channel from 43 to 51
+        // and the sample after it belongs to the left channel again: -504 + 963 = 459
+        assert_eq!(to_samples(&out[..written]), [-10, 10, -504, 504, 459]);
+    }
+
+    #[test]
+    fn decompress_stops_when_output_is_full() {
+        let data = [0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x01];
+
+        let mut out = vec![0; 6];
+        assert_eq!(decompress(&data, &mut out, 1).unwrap(), 6);
+    }
+
+    #[test]
+    fn decompress_huffman_then_adpcm_sector() {
+        let data = [0x00, 0x02, 0x64, 0x00, 0x05, 0x80, 0x41];
+
+        // Huffman (0x01) and ADPCM mono (0x40)
+        let mut sector = vec![0x41];
+        sector.extend(crate::huffman::test::compress_type_0(&data));
+
+        let mut out = vec![0; 8];
+        let written = crate::compression::decompress(&mut sector, &mut out).unwrap();
+
+        assert_eq!(to_samples(&out[..written]), [100, 840, 840, 160]);
+    }
+
+    #[test]
+    fn decompress_wav_sectors() {
+        // Storm compresses the ADPCM output of wav files with the huffman types 0x06 to 0x08
+        let mono = [0x00, 0x02, 0x64, 0x00, 0x05, 0x80, 0x41];
+        let stereo = [0x00, 0x00, 0xF6, 0xFF, 0x0A, 0x00, 0x40, 0x00, 0x81, 0x00];
+
+        for huffman_type in [0x06, 0x07, 0x08] {
+            // Huffman (0x01) and ADPCM mono (0x40)
+            let mut sector = vec![0x41];
+            sector.extend(crate::huffman::test::compress_with_type(
+                huffman_type,
+                &mono,
+            ));
+
+            let mut out = vec![0; 8];
+            let written = crate::compression::decompress(&mut sector, &mut out).unwrap();
+            assert_eq!(to_samples(&out[..written]), [100, 840, 840, 160]);
+
+            // Huffman (0x01) and ADPCM stereo (0x80)
+            let mut sector = vec![0x81];
+            sector.extend(crate::huffman::test::compress_with_type(
+                huffman_type,
+                &stereo,
+            ));
+
+            let mut out = vec![0; 10];
+            let written = crate::compression::decompress(&mut sector, &mut out).unwrap();
+            assert_eq!(to_samples(&out[..written]), [-10, 10, -504, 504, 459]);
+        }
+    }
+}
@@ -1,68 +1,165 @@
 use crate::compression::*;
 use crate::crypt::{decrypt, hash_string};
+use crate::ext_tables::{BetTable, HetTable};
+use crate::file_reader::FileReader;
+use crate::integrity::{Attributes, IntegrityError, IntegrityFailure};
+use crate::listfile::{parse_listfile, FileList, UnnamedEntry};
+use crate::wav::Wav;
 use adler32::RollingAdler32;
 use byteorder::{ByteOrder, LittleEndian};
 use std::fmt;
 use std::fs;
 use std::io::prelude::*;
-use std::io::SeekFrom;
 use std::io::{Error, ErrorKind};
 use std::mem;
 use std::path::{Path, PathBuf};
 
-const HEADER_SIZE_V1: usize = 0x20;
-//const HEADER_SIZE_V2: usize = 0x2C;
-//const HEADER_SIZE_V3: usize = 0x44;
-//const HEADER_SIZE_V4: usize = 0xD0;
+pub(crate) const HEADER_SIZE_V1: usize = 0x20;
+const HEADER_SIZE_V2: usize = 0x2C;
+const HEADER_SIZE_V3: usize = 0x44;
+const HEADER_SIZE_V4: usize = 0xD0;
 const USER_HEADER_SIZE: usize = 16;
 
-const ID_MPQA: &[u8] = b"MPQ\x1A";
+pub(crate) const HASH_ENTRY_SIZE: usize = 16;
+pub(crate) const BLOCK_ENTRY_SIZE: usize = 16;
+const HI_BLOCK_ENTRY_SIZE: usize = 2;
+
+pub(crate) const HASH_ENTRY_EMPTY: u32 = 0xFFFFFFFF; // hash entry was never used, ends the search
+const HASH_ENTRY_DELETED: u32 = 0xFFFFFFFE; // hash entry of a deleted file, search continues
+
+pub(crate) const ID_MPQA: &[u8] = b"MPQ\x1A";
 const ID_MPQB: &[u8] = b"MPQ\x1B";
 
-const FILE_IMPLODE: u32 = 0x00000100; // implode method by pkware compression library
-const FILE_COMPRESS: u32 = 0x00000200; // compress methods by multiple methods
-const FILE_ENCRYPTED: u32 = 0x00010000; // file is encrypted
-const FILE_FIX_KEY: u32 = 0x00020000; // file decryption key is altered according to position of file in archive
+// files which are not always named in the (listfile)
+const SPECIAL_FILES: [&str; 3] = ["(listfile)", "(attributes)", "(signature)"];
+
+pub(crate) const FILE_IMPLODE: u32 = 0x00000100; // implode method by pkware compression library
+pub(crate) const FILE_COMPRESS: u32 = 0x00000200; // compress methods by multiple methods
+pub(crate) const FILE_ENCRYPTED: u32 = 0x00010000; // file is encrypted
+pub(crate) const FILE_FIX_KEY: u32 = 0x00020000; // file decryption key is altered according to position of file in archive
 const FILE_PATCH_FILE: u32 = 0x00100000; // file is a patch file. file data begins with patchinfo struct
-const FILE_SINGLE_UNIT: u32 = 0x01000000; // file is stored as single unit
-const FILE_SECTOR_CRC: u32 = 0x04000000;
+pub(crate) const FILE_SINGLE_UNIT: u32 = 0x01000000; // file is stored as single unit
+pub(crate) const FILE_SECTOR_CRC: u32 = 0x04000000; // sectors are followed by their adler32 checksums
+pub(crate) const FILE_EXISTS: u32 = 0x80000000; // set if file exists, reset when the file was deleted
 const FILE_COMPRESS_MASK: u32 = 0x0000FF00;
 
 #[derive(Debug, Clone)]
 struct Header {
     _magic: [u8; 4],
     _header_size: u32,
-    _archive_size: u32,
-    _format_version: u16, // 0 = Original, 1 = Extended
+    archive_size: u32,
+    format_version: u16, // 0 = Original, 1 = Extended, 2 and 3 = Cataclysm
     sector_size_shift: u16,
     hash_table_offset: u32,
     block_table_offset: u32,
     hash_table_count: u32,
     block_table_count: u32,
     // Header v2
-    _extended_offset: u64,
-    _hash_table_offset_high: u16,
-    _block_table_offset_high: u16,
-    // ToDo: Header v3 and v4
+    hi_block_table_offset: u64,
+    hash_table_offset_high: u16,
+    block_table_offset_high: u16,
+    // Header v3
+    archive_size_64: u64,
+    bet_table_offset: u64,
+    het_table_offset: u64,
+    // Header v4, followed by the md5 hashes of the tables which are not verified
+    hash_table_size: u64,
+    block_table_size: u64,
+    hi_block_table_size: u64,
+    het_table_size: u64,
+    bet_table_size: u64,
+    _raw_chunk_size: u32,
 }
 
 impl Header {
-    pub fn new(src: &[u8; HEADER_SIZE_V1]) -> Header {
+    pub fn new(src: &[u8; HEADER_SIZE_V4]) -> Header {
+        let format_version = LittleEndian::read_u16(&src[0x0C..]);
+        let v2 = format_version >= 1;
+        let v3 = format_version >= 2;
+        let v4 = format_version >= 3;
+
+        let read_u16 = |enabled: bool, offset: usize| match enabled {
+            true => LittleEndian::read_u16(&src[offset..]),
+            false => 0,
+        };
+        let read_u32 = |enabled: bool, offset: usize| match enabled {
+            true => LittleEndian::read_u32(&src[offset..]),
+            false => 0,
+        };
+        let read_u64 = |enabled: bool, offset: usize| match enabled {
+            true => LittleEndian::read_u64(&src[offset..]),
+            false => 0,
+        };
+
         Header {
             _magic: [src[0], src[1], src[2], src[3]],
             _header_size: LittleEndian::read_u32(&src[0x04..]),
-            _archive_size: LittleEndian::read_u32(&src[0x08..]),
-            _format_version: LittleEndian::read_u16(&src[0x0C..]),
+            archive_size: LittleEndian::read_u32(&src[0x08..]),
+            format_version,
             sector_size_shift: LittleEndian::read_u16(&src[0x0E..]),
             hash_table_offset: LittleEndian::read_u32(&src[0x10..]),
             block_table_offset: LittleEndian::read_u32(&src[0x14..]),
             hash_table_count: LittleEndian::read_u32(&src[0x18..]),
             block_table_count: LittleEndian::read_u32(&src[0x1C..]),
-            _extended_offset: 0,
-            _hash_table_offset_high: 0,
-            _block_table_offset_high: 0,
+            hi_block_table_offset: read_u64(v2, 0x20),
+            hash_table_offset_high: read_u16(v2, 0x28),
+            block_table_offset_high: read_u16(v2, 0x2A),
+            archive_size_64: read_u64(v3, 0x2C),
+            bet_table_offset: read_u64(v3, 0x34),
+            het_table_offset: read_u64(v3, 0x3C),
+            hash_table_size: read_u64(v4, 0x44),
+            block_table_size: read_u64(v4, 0x4C),
+            hi_block_table_size: read_u64(v4, 0x54),
+            het_table_size: read_u64(v4, 0x5C),
+            bet_table_size: read_u64(v4, 0x64),
+            _raw_chunk_size: read_u32(v4, 0x6C),
+        }
+    }
+
+    // size of the header on disk, determined by the format version
+    fn size(format_version: u16) -> usize {
+        match format_version {
+            0 => HEADER_SIZE_V1,
+            1 => HEADER_SIZE_V2,
+            2 => HEADER_SIZE_V3,
+            _ => HEADER_SIZE_V4,
+        }
+    }
+
+    fn archive_size(&self) -> u64 {
+        match self.archive_size_64 {
+            0 => u64::from(self.archive_size),
+            size => size,
         }
     }
+
+    fn hash_table_position(&self) -> u64 {
+        u64::from(self.hash_table_offset) | (u64::from(self.hash_table_offset_high) << 32)
+    }
+
+    fn block_table_position(&self) -> u64 {
+        u64::from(self.block_table_offset) | (u64::from(self.block_table_offset_high) << 32)
+    }
+
+    // v3 headers don't store the sizes of the tables, they end where the next table begins
+    fn table_size(&self, position: u64, size: u64) -> u64 {
+        if size != 0 {
+            return size;
+        }
+
+        [
+            self.hash_table_position(),
+            self.block_table_position(),
+            self.hi_block_table_offset,
+            self.het_table_offset,
+            self.bet_table_offset,
+            self.archive_size(),
+        ]
+        .into_iter()
+        .filter(|&next| next > position)
+        .min()
+        .map_or(0, |next| next - position)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -113,7 +210,7 @@ impl Hash {
 #[derive(Debug, Clone)]
 struct Block {
     /// offset of the beginning of the file data, relative to the beginning of the archive
-    offset: u32,
+    offset: u64,
     /// compressed file size
     packed_size: u32,
     /// uncompressed file size
@@ -125,7 +222,7 @@ struct Block {
 impl Block {
     pub fn new(src: &[u8]) -> Block {
         Block {
-            offset: LittleEndian::read_u32(src),
+            offset: u64::from(LittleEndian::read_u32(src)),
             packed_size: LittleEndian::read_u32(&src[0x4..]),
             unpacked_size: LittleEndian::read_u32(&src[0x8..]),
             flags: LittleEndian::read_u32(&src[0xC..]),
@@ -133,6 +230,96 @@ impl Block {
     }
 }
 
+/// Header and table sizes of an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveInfo {
+    /// 0 = Original, 1 = Extended, 2 and 3 = Cataclysm
+    pub format_version: u16,
+    /// position of the header in the file, behind the user data
+    pub header_offset: u64,
+    pub archive_size: u64,
+    pub sector_size: u32,
+    pub hash_table_entries: usize,
+    pub block_table_entries: usize,
+    pub hi_block_table: bool,
+    pub het_bet_tables: bool,
+    pub user_data: bool,
+    pub blocks: Vec<BlockInfo>,
+}
+
+/// A block table entry, describing how a file is stored
+#[derive(Debug, Clone, Copy)]
+pub struct BlockInfo {
+    pub offset: u64,
+    pub packed_size: u32,
+    pub unpacked_size: u32,
+    pub flags: u32,
+    /// compression type byte of the first compressed sector, 0 when no sector got smaller,
+    /// only known for files named by the `(listfile)`
+    pub compression_type: Option<u8>,
+}
+
+impl BlockInfo {
+    pub fn exists(&self) -> bool {
+        self.flags & FILE_EXISTS != 0
+    }
+
+    pub fn encrypted(&self) -> bool {
+        self.flags & FILE_ENCRYPTED != 0
+    }
+
+    pub fn single_unit(&self) -> bool {
+        self.flags & FILE_SINGLE_UNIT != 0
+    }
+
+    pub fn sector_crc(&self) -> bool {
+        self.flags & FILE_SECTOR_CRC != 0
+    }
+
+    /// Name of the storage method, the algorithms of multi-compressed files are those of
+    /// their first compressed sector and "unknown" for files without a name
+    pub fn compression(&self) -> String {
+        if self.flags & FILE_IMPLODE != 0 {
+            String::from("pkware")
+        } else if self.flags & FILE_COMPRESS != 0 {
+            match self.compression_type {
+                Some(0) => String::from("none"),
+                Some(compression_type) => compression_names(compression_type),
+                None => String::from("unknown"),
+            }
+        } else {
+            String::from("none")
+        }
+    }
+}
+
+// positional read which doesn't move a shared file cursor,
+// so an archive can be read through `&self` from multiple threads
+fn read_exact_at(file: &fs::File, buf: &mut [u8], position: u64) -> Result<(), Error> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::FileExt::read_exact_at(file, buf, position)
+    }
+
+    #[cfg(windows)]
+    {
+        let mut read = 0;
+
+        while read < buf.len() {
+            match std::os::windows::fs::FileExt::seek_read(
+                file,
+                &mut buf[read..],
+                position + read as u64,
+            )? {
+                0 => return Err(Error::from(ErrorKind::UnexpectedEof)),
+                n => read += n,
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub struct Archive {
     path: PathBuf,
     file: fs::File,
@@ -140,8 +327,12 @@ pub struct Archive {
     user_data_header: Option<UserDataHeader>,
     hash_table: Vec<Hash>,
     block_table: Vec<Block>,
+    het_table: Option<HetTable>,
+    bet_table: Option<BetTable>,
     sector_size: u32,
     offset: u64,
+    verification: bool,
+    attributes: Option<Attributes>,
 }
 
 impl Clone for Archive {
@@ -155,24 +346,26 @@ impl Clone for Archive {
             user_data_header: self.user_data_header.clone(),
             hash_table: self.hash_table.clone(),
             block_table: self.block_table.clone(),
+            het_table: self.het_table.clone(),
+            bet_table: self.bet_table.clone(),
             sector_size: self.sector_size,
             offset: self.offset,
+            verification: self.verification,
+            attributes: self.attributes.clone(),
         }
     }
 }
 
 impl Archive {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Archive, Error> {
-        let mut file = fs::File::open(&path).expect("no file found");
+        let file = fs::File::open(&path)?;
 
         let mut buffer: [u8; HEADER_SIZE_V1] = [0; HEADER_SIZE_V1];
         let mut offset: u64 = 0;
         let mut user_data_header = None;
 
         loop {
-            file.seek(SeekFrom::Start(offset))?;
-
-            file.read_exact(&mut buffer)?;
+            read_exact_at(&file, &mut buffer, offset)?;
 
             if buffer.starts_with(ID_MPQA) {
                 break;
@@ -183,9 +376,7 @@ impl Archive {
 
                 offset += u64::from(header.header_offset);
 
-                file.seek(SeekFrom::Start(offset))?;
-
-                file.read_exact(&mut buffer)?;
+                read_exact_at(&file, &mut buffer, offset)?;
 
                 if !buffer.starts_with(ID_MPQA) {
                     return Err(Error::new(
@@ -202,42 +393,89 @@ impl Archive {
             offset += 0x200;
         }
 
-        let header = Header::new(&buffer);
+        let header = Self::read_header(&file, offset)?;
 
         // read hash table
-        let mut hash_buff: Vec<u8> =
-            vec![0; (header.hash_table_count as usize) * mem::size_of::<Hash>()];
         let mut hash_table: Vec<Hash> = Vec::with_capacity(header.hash_table_count as usize);
 
-        file.seek(SeekFrom::Start(
-            u64::from(header.hash_table_offset) + offset,
-        ))?;
-
-        file.read_exact(&mut hash_buff)?;
-
-        decrypt(&mut hash_buff, hash_string("(hash table)", 0x300));
-
-        for x in 0..header.hash_table_count {
-            hash_table.push(Hash::new(&hash_buff[x as usize * mem::size_of::<Hash>()..]));
+        if header.hash_table_count != 0 {
+            let hash_buff = Self::read_table(
+                &file,
+                header.hash_table_position() + offset,
+                header.hash_table_size,
+                header.hash_table_count as usize * HASH_ENTRY_SIZE,
+                Some(hash_string("(hash table)", 0x300)),
+            )?;
+
+            for entry in hash_buff.chunks_exact(HASH_ENTRY_SIZE) {
+                hash_table.push(Hash::new(entry));
+            }
         }
 
         // read block table
-        let mut block_buff: Vec<u8> =
-            vec![0; (header.block_table_count as usize) * mem::size_of::<Block>()];
         let mut block_table: Vec<Block> = Vec::with_capacity(header.block_table_count as usize);
 
-        file.seek(SeekFrom::Start(
-            u64::from(header.block_table_offset) + offset,
-        ))?;
+        if header.block_table_count != 0 {
+            let block_buff = Self::read_table(
+                &file,
+                header.block_table_position() + offset,
+                header.block_table_size,
+                header.block_table_count as usize * BLOCK_ENTRY_SIZE,
+                Some(hash_string("(block table)", 0x300)),
+            )?;
+
+            for entry in block_buff.chunks_exact(BLOCK_ENTRY_SIZE) {
+                block_table.push(Block::new(entry));
+            }
+        }
 
-        file.read_exact(&mut block_buff)?;
+        // read hi-block table, holds the upper 16 bits of the file offsets
+        if header.hi_block_table_offset != 0 && !block_table.is_empty() {
+            let hi_block_buff = Self::read_table(
+                &file,
+                header.hi_block_table_offset + offset,
+                header.hi_block_table_size,
+                block_table.len() * HI_BLOCK_ENTRY_SIZE,
+                None,
+            )?;
+
+            for (block, entry) in block_table
+                .iter_mut()
+                .zip(hi_block_buff.chunks_exact(HI_BLOCK_ENTRY_SIZE))
+            {
+                block.offset |= u64::from(LittleEndian::read_u16(entry)) << 32;
+            }
+        }
 
-        decrypt(&mut block_buff, hash_string("(block table)", 0x300));
+        // read HET and BET tables, used instead of the hash and block tables when these are missing
+        let mut het_table = None;
+        let mut bet_table = None;
+
+        if header.het_table_offset != 0 && header.bet_table_offset != 0 {
+            let size = header.table_size(header.het_table_offset, header.het_table_size);
+            let het_buff = Self::read_raw(&file, header.het_table_offset + offset, size)?;
+
+            let size = header.table_size(header.bet_table_offset, header.bet_table_size);
+            let bet_buff = Self::read_raw(&file, header.bet_table_offset + offset, size)?;
+
+            let het = HetTable::new(het_buff, hash_string("(hash table)", 0x300))?;
+            let bet = BetTable::new(bet_buff, hash_string("(block table)", 0x300))?;
+
+            if block_table.is_empty() {
+                block_table = bet
+                    .entries
+                    .iter()
+                    .map(|entry| Block {
+                        offset: entry.offset,
+                        packed_size: entry.packed_size as u32,
+                        unpacked_size: entry.unpacked_size as u32,
+                        flags: entry.flags,
+                    })
+                    .collect();
+            }
 
-        for x in 0..header.block_table_count {
-            block_table.push(Block::new(
-                &block_buff[x as usize * mem::size_of::<Block>()..],
-            ));
+            het_table = Some(het);
+            bet_table = Some(bet);
         }
 
         let sector_size = 512 << header.sector_size_shift;
@@ -249,119 +487,449 @@ impl Archive {
             user_data_header,
             hash_table,
             block_table,
+            het_table,
+            bet_table,
             sector_size,
             offset,
+            verification: false,
+            attributes: None,
         })
     }
 
-    pub fn open_file(&mut self, filename: &str) -> Result<File, Error> {
-        let start_index =
-            (hash_string(filename, 0x0) & (self.header.hash_table_count - 1)) as usize;
-        let mut hash;
+    fn read_header(file: &fs::File, offset: u64) -> Result<Header, Error> {
+        let mut buffer = [0; HEADER_SIZE_V4];
+
+        read_exact_at(file, &mut buffer[..HEADER_SIZE_V1], offset)?;
 
+        let format_version = LittleEndian::read_u16(&buffer[0x0C..]);
+        let header_size = Header::size(format_version);
+
+        read_exact_at(
+            file,
+            &mut buffer[HEADER_SIZE_V1..header_size],
+            offset + HEADER_SIZE_V1 as u64,
+        )?;
+
+        Ok(Header::new(&buffer))
+    }
+
+    fn read_raw(file: &fs::File, position: u64, size: u64) -> Result<Vec<u8>, Error> {
+        let mut buff: Vec<u8> = vec![0; size as usize];
+
+        read_exact_at(file, &mut buff, position)?;
+
+        Ok(buff)
+    }
+
+    // read a table which is compressed when its size on disk is smaller than its real size
+    fn read_table(
+        file: &fs::File,
+        position: u64,
+        packed_size: u64,
+        size: usize,
+        key: Option<u32>,
+    ) -> Result<Vec<u8>, Error> {
+        let packed_size = match packed_size {
+            0 => size as u64,
+            packed_size => packed_size.min(size as u64),
+        };
+
+        let mut buff = Self::read_raw(file, position, packed_size)?;
+
+        if let Some(key) = key {
+            decrypt(&mut buff, key);
+        }
+
+        if buff.len() < size {
+            let mut out = vec![0; size];
+            decompress(&mut buff, &mut out)?;
+            buff = out;
+        }
+
+        Ok(buff)
+    }
+
+    // find the block table index of a file by its name
+    fn find_block_index(&self, filename: &str) -> Option<usize> {
+        if self.hash_table.is_empty() {
+            return match (&self.het_table, &self.bet_table) {
+                (Some(het_table), Some(bet_table)) => het_table.find(filename, bet_table),
+                _ => None,
+            };
+        }
+
+        self.find_hash_index(filename)
+            .map(|hash_index| self.hash_table[hash_index].block_index as usize)
+    }
+
+    // find the hash table index of a file by its name
+    fn find_hash_index(&self, filename: &str) -> Option<usize> {
+        if self.hash_table.is_empty() {
+            return None;
+        }
+
+        let count = self.hash_table.len();
+        let start_index = (hash_string(filename, 0x0) as usize) & (count - 1);
         let hash_a = hash_string(filename, 0x100);
         let hash_b = hash_string(filename, 0x200);
-        let mut file_key = 0;
 
-        for i in start_index..self.hash_table.len() {
-            hash = &self.hash_table[i];
-
-            if hash.hash_a == hash_a && hash.hash_b == hash_b {
-                let block = &self.block_table[hash.block_index as usize];
-                let mut sector_offsets: Vec<u32> = Vec::new();
-                let mut sector_checksums: Vec<u32> = Vec::new();
-
-                // file if encrypted, generate decryption key
-                if block.flags & FILE_ENCRYPTED != 0 {
-                    match filename.split(&['\\', '/'][..]).last() {
-                        Some(basename) => file_key = hash_string(basename, 0x300),
-                        None => {
-                            return Err(Error::new(
-                                ErrorKind::Other,
-                                "Unable to extract filename from path",
-                            ));
-                        }
-                    }
+        for i in 0..count {
+            let hash_index = (start_index + i) % count;
+            let hash = &self.hash_table[hash_index];
+
+            match hash.block_index {
+                HASH_ENTRY_EMPTY => return None,
+                HASH_ENTRY_DELETED => {}
+                _ if hash.hash_a == hash_a && hash.hash_b == hash_b => return Some(hash_index),
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// Enables checking the files read from the archive against the CRC32 and MD5 stored in
+    /// `(attributes)`, a failed check is reported as an [`IntegrityError`] naming the file.
+    ///
+    /// The checksums of the sectors are always checked, verification only makes a checksum
+    /// sector which can't be decompressed an error instead of reading the sectors unchecked.
+    pub fn set_verification(&mut self, enabled: bool) -> Result<(), Error> {
+        self.verification = false;
+        self.attributes = None;
+
+        if enabled {
+            self.attributes = match self.read_file("(attributes)") {
+                Ok(bytes) => Some(Attributes::new(&bytes, self.block_table.len())?),
+                Err(e) if e.kind() == ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+        }
 
-                    // fix decryption key
-                    if block.flags & FILE_FIX_KEY != 0 {
-                        file_key = (file_key + (block.offset)) ^ block.unpacked_size;
+        self.verification = enabled;
+
+        Ok(())
+    }
+
+    /// Reads a file with verification enabled
+    pub fn verify_file(&mut self, filename: &str) -> Result<(), Error> {
+        self.with_verification(|archive| archive.read_file(filename).map(|_| ()))
+    }
+
+    /// Reads all files named by the `(listfile)` with verification enabled,
+    /// returns the ones which failed a check
+    pub fn verify(&mut self) -> Result<Vec<IntegrityError>, Error> {
+        let names = self.list_files()?.names;
+
+        self.verify_files(&names)
+    }
+
+    /// Reads the files with verification enabled, returns the ones which failed a check
+    pub fn verify_files<S: AsRef<str>>(
+        &mut self,
+        names: &[S],
+    ) -> Result<Vec<IntegrityError>, Error> {
+        self.with_verification(|archive| {
+            let mut damaged = Vec::new();
+
+            for name in names {
+                if let Err(e) = archive.read_file(name.as_ref()) {
+                    match IntegrityError::from_io(&e) {
+                        Some(error) => damaged.push(error.clone()),
+                        None => return Err(e),
                     }
                 }
+            }
 
-                // block split into sectors, read sector offsets
-                if block.flags & FILE_SINGLE_UNIT == 0 {
-                    // FixMe: handle empty files, packed and unpacked size should be 0
+            Ok(damaged)
+        })
+    }
 
-                    if block.unpacked_size == 0 || self.sector_size == 0 {
-                        return Err(Error::new(ErrorKind::UnexpectedEof, filename));
-                    }
+    fn with_verification<T>(
+        &mut self,
+        f: impl FnOnce(&mut Archive) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let enabled = self.verification;
 
-                    let num_sectors = ((block.unpacked_size - 1) / self.sector_size) + 1;
+        if !enabled {
+            self.set_verification(true)?;
+        }
 
-                    let mut sector_buff: Vec<u8> = vec![0; ((num_sectors as usize) + 1) * 4];
+        let result = f(self);
 
-                    self.file
-                        .seek(SeekFrom::Start(u64::from(block.offset) + self.offset))?;
-                    self.file.read_exact(&mut sector_buff)?;
+        if !enabled {
+            self.set_verification(false)?;
+        }
 
-                    if block.flags & FILE_ENCRYPTED != 0 {
-                        decrypt(&mut sector_buff, file_key - 1);
-                    }
+        result
+    }
 
-                    let mut x = 0;
-                    while x < sector_buff.len() - 3 {
-                        sector_offsets.push(LittleEndian::read_u32(&sector_buff[x..]));
-                        x += 4;
-                    }
+    /// Lists the files of the archive named by its internal `(listfile)`
+    pub fn list_files(&self) -> Result<FileList, Error> {
+        self.list_files_with::<&Path>(&[])
+    }
 
-                    // load sector checksums
-                    if block.flags & FILE_COMPRESS != 0 && block.flags & FILE_SECTOR_CRC != 0 {
-                        let mut buff: Vec<u8> = vec![0; 4];
+    /// Lists the files of the archive named by its internal `(listfile)` and external listfiles
+    pub fn list_files_with<P: AsRef<Path>>(&self, listfiles: &[P]) -> Result<FileList, Error> {
+        let mut candidates: Vec<String> =
+            SPECIAL_FILES.iter().map(|name| name.to_string()).collect();
 
-                        self.file.read_exact(&mut buff)?;
+        match self.read_file("(listfile)") {
+            Ok(bytes) => candidates.extend(parse_listfile(&bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
 
-                        let last_offset = LittleEndian::read_u32(&buff);
-                        let checksum_offset = sector_offsets[num_sectors as usize];
-                        let sector_size = last_offset - checksum_offset;
-                        let expected_size = num_sectors * mem::size_of::<u32>() as u32;
+        for listfile in listfiles {
+            candidates.extend(parse_listfile(&fs::read(listfile)?));
+        }
 
-                        // is checksum sector the expected size
-                        if sector_size == expected_size {
-                            self.file.seek(SeekFrom::Start(
-                                u64::from(block.offset) + u64::from(checksum_offset),
-                            ))?;
+        Ok(self.name_files(candidates))
+    }
 
-                            for _ in 0..num_sectors {
-                                self.file.read_exact(&mut buff)?;
+    /// Names of the files in the internal `(listfile)` matching a glob pattern
+    /// like `data\global\monsters\*\cof\*.cof`
+    pub fn find_files(&self, pattern: &str) -> Result<Vec<String>, Error> {
+        Ok(self
+            .list_files()?
+            .matching(pattern)
+            .map(String::from)
+            .collect())
+    }
 
-                                sector_checksums.push(LittleEndian::read_u32(&buff));
-                            }
-                        }
-                    }
+    fn name_files(&self, candidates: Vec<String>) -> FileList {
+        let mut named_hashes = vec![false; self.hash_table.len()];
+        let mut named_blocks = vec![false; self.block_table.len()];
+        let mut names = Vec::new();
+
+        for name in candidates {
+            if let Some(hash_index) = self.find_hash_index(&name) {
+                named_hashes[hash_index] = true;
+            } else if let Some(block_index) = self.find_block_index(&name) {
+                named_blocks[block_index] = true;
+            } else {
+                continue;
+            }
+
+            names.push(name);
+        }
+
+        names.sort_by_key(|name| name.to_lowercase().replace('/', "\\"));
+        names.dedup_by(|a, b| {
+            a.replace('/', "\\")
+                .eq_ignore_ascii_case(&b.replace('/', "\\"))
+        });
+
+        let unnamed = if self.hash_table.is_empty() {
+            self.block_table
+                .iter()
+                .enumerate()
+                .filter(|(block_index, block)| {
+                    block.flags & FILE_EXISTS != 0 && !named_blocks[*block_index]
+                })
+                .map(|(block_index, _)| UnnamedEntry {
+                    hash_index: None,
+                    block_index,
+                })
+                .collect()
+        } else {
+            self.hash_table
+                .iter()
+                .enumerate()
+                .filter(|(hash_index, hash)| {
+                    (hash.block_index as usize) < self.block_table.len()
+                        && !named_hashes[*hash_index]
+                })
+                .map(|(hash_index, hash)| UnnamedEntry {
+                    hash_index: Some(hash_index),
+                    block_index: hash.block_index as usize,
+                })
+                .collect()
+        };
+
+        FileList { names, unnamed }
+    }
+
+    /// Reads the whole contents of a file
+    pub fn read_file(&self, filename: &str) -> Result<Vec<u8>, Error> {
+        let file = self.open_file(filename)?;
+        let mut buf: Vec<u8> = vec![0; file.size() as usize];
+
+        file.read(self, &mut buf)?;
+
+        Ok(buf)
+    }
+
+    /// Opens a file to be read as a stream, see [`FileReader`]
+    pub fn open_reader(&self, filename: &str) -> Result<FileReader<'_>, Error> {
+        let file = self.open_file(filename)?;
+
+        Ok(FileReader::new(self, file))
+    }
+
+    pub fn open_file(&self, filename: &str) -> Result<File, Error> {
+        let (block_index, block) = match self.find_block_index(filename) {
+            Some(block_index) => match self.block_table.get(block_index) {
+                Some(block) => (block_index, block.clone()),
+                None => return Err(Error::new(ErrorKind::InvalidData, filename)),
+            },
+            None => return Err(Error::new(ErrorKind::NotFound, filename)),
+        };
+
+        let mut sector_offsets: Vec<u32> = Vec::new();
+        let mut sector_checksums: Vec<u32> = Vec::new();
+        let mut file_key = 0;
+
+        // file if encrypted, generate decryption key
+        if block.flags & FILE_ENCRYPTED != 0 {
+            match filename.split(&['\\', '/'][..]).next_back() {
+                Some(basename) => file_key = hash_string(basename, 0x300),
+                None => {
+                    return Err(Error::other("Unable to extract filename from path"));
                 }
+            }
 
-                return Ok(File {
-                    _name: String::from(filename),
-                    _hash: hash.clone(),
-                    block: block.clone(),
-                    sector_offsets,
-                    sector_checksums,
-                    file_key,
-                });
+            // fix decryption key
+            if block.flags & FILE_FIX_KEY != 0 {
+                file_key = file_key.wrapping_add(block.offset as u32) ^ block.unpacked_size;
             }
         }
 
-        Err(Error::new(ErrorKind::NotFound, filename))
+        // compressed block split into sectors, read sector offsets
+        if block.flags & FILE_SINGLE_UNIT == 0 && block.flags & FILE_COMPRESS_MASK != 0 {
+            // FixMe: handle empty files, packed and unpacked size should be 0
+
+            if block.unpacked_size == 0 || self.sector_size == 0 {
+                return Err(Error::new(ErrorKind::UnexpectedEof, filename));
+            }
+
+            let num_sectors = (((block.unpacked_size - 1) / self.sector_size) + 1) as usize;
+
+            // with sector checksums the offsets end with the end of the checksum sector
+            let has_checksums = block.flags & FILE_SECTOR_CRC != 0;
+            let num_offsets = num_sectors + 1 + usize::from(has_checksums);
+
+            let mut sector_buff: Vec<u8> = vec![0; num_offsets * 4];
+
+            read_exact_at(&self.file, &mut sector_buff, block.offset + self.offset)?;
+
+            if block.flags & FILE_ENCRYPTED != 0 {
+                decrypt(&mut sector_buff, file_key.wrapping_sub(1));
+            }
+
+            sector_offsets = sector_buff
+                .chunks_exact(4)
+                .map(LittleEndian::read_u32)
+                .collect();
+
+            if has_checksums {
+                let last_offset = sector_offsets.pop().unwrap();
+
+                sector_checksums = self.read_sector_checksums(
+                    filename,
+                    &block,
+                    sector_offsets[num_sectors],
+                    last_offset,
+                )?;
+            }
+        }
+
+        Ok(File {
+            name: String::from(filename),
+            block_index,
+            block,
+            sector_offsets,
+            sector_checksums,
+            file_key,
+        })
+    }
+
+    // read the adler32 checksums of the sectors, stored after the last sector
+    // and compressed when that makes them smaller
+    fn read_sector_checksums(
+        &self,
+        filename: &str,
+        block: &Block,
+        checksum_offset: u32,
+        last_offset: u32,
+    ) -> Result<Vec<u32>, Error> {
+        let num_sectors = (((block.unpacked_size - 1) / self.sector_size) + 1) as usize;
+        let expected_size = num_sectors * mem::size_of::<u32>();
+        let size = last_offset.wrapping_sub(checksum_offset) as usize;
+
+        // no usable checksum sector, the sectors can't be verified
+        if size == 0 || size > expected_size {
+            return Ok(Vec::new());
+        }
+
+        let position = block.offset + u64::from(checksum_offset) + self.offset;
+        let mut buff = Self::read_raw(&self.file, position, size as u64)?;
+
+        if size < expected_size {
+            let mut out = vec![0; expected_size];
+
+            if let Err(e) = decompress(&mut buff, &mut out) {
+                // without verification the sectors are read unchecked, like a missing checksum sector
+                if !self.verification {
+                    return Ok(Vec::new());
+                }
+
+                let reason = format!("sector checksums can't be decompressed, {e}");
+                let failure = IntegrityFailure::Corrupted {
+                    sector: None,
+                    reason,
+                };
+
+                return Err(IntegrityError::new(filename, failure).into());
+            }
+
+            buff = out;
+        }
+
+        Ok(buff.chunks_exact(4).map(LittleEndian::read_u32).collect())
     }
 
-    pub fn read_user_data(&mut self) -> Result<Option<Vec<u8>>, Error> {
+    /// Header and table sizes and the block table entries
+    pub fn info(&self) -> ArchiveInfo {
+        // the sector data of encrypted files can only be read with the key from their name
+        let mut compression_types = vec![None; self.block_table.len()];
+
+        for name in self.list_files().map(|list| list.names).unwrap_or_default() {
+            if let Ok(file) = self.open_file(&name) {
+                compression_types[file.block_index] = file.compression_type(self).ok();
+            }
+        }
+
+        ArchiveInfo {
+            format_version: self.header.format_version,
+            header_offset: self.offset,
+            archive_size: self.header.archive_size(),
+            sector_size: self.sector_size,
+            hash_table_entries: self.hash_table.len(),
+            block_table_entries: self.block_table.len(),
+            hi_block_table: self.header.hi_block_table_offset != 0,
+            het_bet_tables: self.het_table.is_some(),
+            user_data: self.user_data_header.is_some(),
+            blocks: self
+                .block_table
+                .iter()
+                .zip(compression_types)
+                .map(|(block, compression_type)| BlockInfo {
+                    offset: block.offset,
+                    packed_size: block.packed_size,
+                    unpacked_size: block.unpacked_size,
+                    flags: block.flags,
+                    compression_type,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn read_user_data(&self) -> Result<Option<Vec<u8>>, Error> {
         match self.user_data_header {
             Some(ref header) => {
                 let mut buf: Vec<u8> = vec![0; header.user_data_size as usize];
 
-                self.file.seek(SeekFrom::Start(USER_HEADER_SIZE as u64))?;
-                self.file.read_exact(&mut buf)?;
+                read_exact_at(&self.file, &mut buf, USER_HEADER_SIZE as u64)?;
 
                 Ok(Some(buf))
             }
@@ -382,8 +950,8 @@ impl fmt::Debug for Archive {
 
 #[derive(Debug)]
 pub struct File {
-    _name: String,
-    _hash: Hash,
+    name: String,
+    block_index: usize,
     block: Block,
     sector_offsets: Vec<u32>,
     sector_checksums: Vec<u32>,
@@ -396,111 +964,236 @@ impl File {
     }
 
     // read data from file
-    pub fn read(&self, archive: &mut Archive, buf: &mut [u8]) -> Result<usize, Error> {
-        if self.block.flags & FILE_PATCH_FILE != 0 {
-            Err(Error::new(ErrorKind::Other, "Patch file not supported"))
+    pub fn read(&self, archive: &Archive, buf: &mut [u8]) -> Result<usize, Error> {
+        let read = if self.block.flags & FILE_PATCH_FILE != 0 {
+            return Err(Error::other("Patch file not supported"));
         } else if self.block.flags & FILE_SINGLE_UNIT != 0 {
             // file is single block file
             self.read_single_unit_file(
                 self.block.packed_size as usize,
-                &mut archive.file,
+                &archive.file,
                 archive.offset,
                 buf,
-            )
+            )?
         } else {
             // read as sector based MPQ file
-            self.read_sector_file(archive, buf)
+            self.read_sector_file(archive, buf)?
+        };
+
+        // only a completely read file can be compared with (attributes)
+        if archive.verification && read == self.size() as usize {
+            if let Some(attributes) = &archive.attributes {
+                attributes.verify(&self.name, self.block_index, &buf[..read])?;
+            }
+        }
+
+        Ok(read)
+    }
+
+    // names the file and sector when the archive ends before the file data
+    fn truncated(&self, sector: Option<usize>, error: Error) -> Error {
+        match error.kind() {
+            ErrorKind::UnexpectedEof => {
+                IntegrityError::new(&self.name, IntegrityFailure::Truncated { sector }).into()
+            }
+            _ => error,
         }
     }
 
-    fn read_sector_file(&self, archive: &mut Archive, out: &mut [u8]) -> Result<usize, Error> {
-        let mut buff: Vec<u8> = vec![0; archive.sector_size as usize];
+    // names the file and sector of data which can't be decoded
+    fn corrupted(&self, sector: Option<usize>, reason: impl ToString) -> Error {
+        let failure = IntegrityFailure::Corrupted {
+            sector,
+            reason: reason.to_string(),
+        };
+
+        IntegrityError::new(&self.name, failure).into()
+    }
+
+    fn read_sector_file(&self, archive: &Archive, out: &mut [u8]) -> Result<usize, Error> {
+        let num_sectors = (self.size() as usize).div_ceil(archive.sector_size as usize);
         let mut read: usize = 0;
 
-        if self.block.flags & FILE_COMPRESS_MASK != 0 {
-            for i in 0..self.sector_offsets.len() - 1 {
-                let sector_offset = self.sector_offsets[i];
-                let sector_size = self.sector_offsets[i + 1] - sector_offset;
+        for i in 0..num_sectors {
+            if read >= out.len() {
+                break;
+            }
 
-                let in_buf: &mut [u8] = &mut buff[0..sector_size as usize];
-                let out_buf: &mut [u8] = &mut out[read..];
+            read += self.read_sector(archive, i, &mut out[read..])?;
+        }
 
-                archive.file.seek(SeekFrom::Start(
-                    u64::from(self.block.offset) + u64::from(sector_offset) + archive.offset,
-                ))?;
+        Ok(read)
+    }
 
-                archive.file.read_exact(in_buf)?;
+    /// Compression type byte of the first unit which got smaller by compression,
+    /// 0 when all units are stored as they are
+    pub fn compression_type(&self, archive: &Archive) -> Result<u8, Error> {
+        if self.block.flags & FILE_COMPRESS == 0 {
+            return Ok(0);
+        }
 
-                if self.block.flags & FILE_ENCRYPTED != 0 {
-                    decrypt(in_buf, self.file_key + i as u32);
-                }
+        let (unit, offset, packed_size) = if self.block.flags & FILE_SINGLE_UNIT != 0 {
+            if self.block.packed_size >= self.block.unpacked_size {
+                return Ok(0);
+            }
 
-                // checksum verification
-                if !self.sector_checksums.is_empty() && self.sector_checksums[i] != 0 {
-                    let mut adler = RollingAdler32::from_value(0);
+            (None, 0, self.block.packed_size as usize)
+        } else {
+            let sector_size = archive.sector_size as usize;
 
-                    adler.update_buffer(in_buf);
+            let compressed = self
+                .sector_offsets
+                .windows(2)
+                .enumerate()
+                .find(|(i, offsets)| {
+                    let packed_size = offsets[1].wrapping_sub(offsets[0]) as usize;
+                    let unpacked_size = (self.size() as usize).saturating_sub(i * sector_size);
 
-                    if self.sector_checksums[i] != adler.hash() {
-                        return Err(Error::new(ErrorKind::Other, "Sector checksum error"));
-                    }
-                }
+                    packed_size != sector_size && packed_size != unpacked_size.min(sector_size)
+                });
 
-                if self.block.flags & FILE_COMPRESS != 0 {
-                    if in_buf.len() == archive.sector_size as usize || in_buf.len() == out_buf.len()
-                    {
-                        for (dst, src) in out_buf.iter_mut().zip(in_buf) {
-                            *dst = *src;
-                            read += 1;
-                        }
-                    } else {
-                        read += decompress(in_buf, out_buf)?;
-                    }
-                } else if self.block.flags & FILE_IMPLODE != 0 {
-                    if in_buf.len() == archive.sector_size as usize || in_buf.len() == out_buf.len()
-                    {
-                        for (dst, src) in out_buf.iter_mut().zip(in_buf) {
-                            *dst = *src;
-                            read += 1;
-                        }
-                    } else {
-                        read += explode(in_buf, out_buf)?;
-                    }
-                }
+            match compressed {
+                Some((i, offsets)) => (
+                    Some(i),
+                    offsets[0],
+                    offsets[1].wrapping_sub(offsets[0]) as usize,
+                ),
+                None => return Ok(0),
             }
+        };
+
+        // whole dwords are needed to decrypt, a shorter unit is read as it is
+        let mut in_buf: Vec<u8> = vec![0; packed_size.min(4)];
+        let position = self.block.offset + u64::from(offset) + archive.offset;
+
+        read_exact_at(&archive.file, &mut in_buf, position).map_err(|e| self.truncated(unit, e))?;
+
+        if self.block.flags & FILE_ENCRYPTED != 0 {
+            let key = self.file_key.wrapping_add(unit.unwrap_or(0) as u32);
+            decrypt(&mut in_buf, key);
+        }
+
+        in_buf
+            .first()
+            .copied()
+            .ok_or_else(|| self.corrupted(unit, "empty compressed unit"))
+    }
+
+    /// Size of the units the file is decoded in, the whole file when stored as single unit
+    pub(crate) fn unit_size(&self, archive: &Archive) -> usize {
+        match self.block.flags & FILE_SINGLE_UNIT {
+            0 => archive.sector_size as usize,
+            _ => self.size() as usize,
+        }
+    }
+
+    /// Reads and decodes a single unit of the file, see [`File::unit_size`]
+    pub(crate) fn read_unit(
+        &self,
+        archive: &Archive,
+        index: usize,
+        out: &mut [u8],
+    ) -> Result<usize, Error> {
+        if self.block.flags & (FILE_PATCH_FILE | FILE_SINGLE_UNIT) != 0 {
+            self.read(archive, out)
         } else {
-            archive.file.seek(SeekFrom::Start(
-                u64::from(self.block.offset) + archive.offset,
-            ))?;
-            archive.file.read_exact(out)?;
+            self.read_sector(archive, index, out)
+        }
+    }
+
+    // read and decode a single sector of a file stored in sectors
+    fn read_sector(&self, archive: &Archive, i: usize, out: &mut [u8]) -> Result<usize, Error> {
+        let sector_size = archive.sector_size as usize;
+
+        // decoded size of the sector, the last one is shorter
+        let unpacked_size = (self.size() as usize).saturating_sub(i * sector_size);
+        let out_len = unpacked_size.min(sector_size).min(out.len());
+        let out_buf: &mut [u8] = &mut out[..out_len];
 
-            read = out.len();
+        if self.block.flags & FILE_COMPRESS_MASK == 0 {
+            let position = self.block.offset + (i * sector_size) as u64 + archive.offset;
+
+            read_exact_at(&archive.file, out_buf, position)
+                .map_err(|e| self.truncated(Some(i), e))?;
+
+            if self.block.flags & FILE_ENCRYPTED != 0 {
+                decrypt(out_buf, self.file_key.wrapping_add(i as u32));
+            }
+
+            return Ok(out_buf.len());
         }
 
-        Ok(read)
+        let (Some(&sector_offset), Some(&sector_end)) =
+            (self.sector_offsets.get(i), self.sector_offsets.get(i + 1))
+        else {
+            return Err(self.corrupted(Some(i), "sector out of range"));
+        };
+        let packed_size = sector_end.wrapping_sub(sector_offset) as usize;
+
+        if packed_size == 0 || packed_size > sector_size {
+            return Err(self.corrupted(Some(i), "invalid sector offset"));
+        }
+
+        let mut in_buf: Vec<u8> = vec![0; packed_size];
+
+        let position = self.block.offset + u64::from(sector_offset) + archive.offset;
+
+        read_exact_at(&archive.file, &mut in_buf, position)
+            .map_err(|e| self.truncated(Some(i), e))?;
+
+        if self.block.flags & FILE_ENCRYPTED != 0 {
+            decrypt(&mut in_buf, self.file_key.wrapping_add(i as u32));
+        }
+
+        // checksum verification
+        if !self.sector_checksums.is_empty() && self.sector_checksums[i] != 0 {
+            let mut adler = RollingAdler32::from_value(0);
+
+            adler.update_buffer(&in_buf);
+
+            if self.sector_checksums[i] != adler.hash() {
+                let failure = IntegrityFailure::SectorChecksum { sector: i };
+                return Err(IntegrityError::new(&self.name, failure).into());
+            }
+        }
+
+        // sectors which didn't get smaller by compression are stored as they are
+        if in_buf.len() == sector_size || in_buf.len() == unpacked_size {
+            let mut read = 0;
+
+            for (dst, src) in out_buf.iter_mut().zip(in_buf) {
+                *dst = src;
+                read += 1;
+            }
+
+            Ok(read)
+        } else if self.block.flags & FILE_COMPRESS != 0 {
+            decompress(&mut in_buf, out_buf).map_err(|e| self.corrupted(Some(i), e))
+        } else {
+            explode(&mut in_buf, out_buf).map_err(|e| self.corrupted(Some(i), e))
+        }
     }
 
     fn read_single_unit_file(
         &self,
         buff_size: usize,
-        file: &mut fs::File,
+        file: &fs::File,
         offset: u64,
         out_buf: &mut [u8],
     ) -> Result<usize, Error> {
         let mut in_buff: Vec<u8> = vec![0; buff_size];
 
-        file.seek(SeekFrom::Start(u64::from(self.block.offset) + offset))?;
-
-        file.read_exact(&mut in_buff)?;
+        read_exact_at(file, &mut in_buff, self.block.offset + offset)
+            .map_err(|e| self.truncated(None, e))?;
 
         if self.block.flags & FILE_ENCRYPTED != 0 {
             decrypt(&mut in_buff, self.file_key);
         }
 
         if self.block.flags & FILE_COMPRESS != 0 && out_buf.len() > in_buff.len() {
-            decompress(&mut in_buff, out_buf)
+            decompress(&mut in_buff, out_buf).map_err(|e| self.corrupted(None, e))
         } else if self.block.flags & FILE_IMPLODE != 0 {
-            explode(&mut in_buff, out_buf)
+            explode(&mut in_buff, out_buf).map_err(|e| self.corrupted(None, e))
         } else {
             for (dst, src) in out_buf.iter_mut().zip(&in_buff) {
                 *dst = *src
@@ -511,7 +1204,7 @@ impl File {
     }
 
     // extract file from archive to the local filesystem
-    pub fn extract<P: AsRef<Path>>(&self, archive: &mut Archive, path: P) -> Result<usize, Error> {
+    pub fn extract<P: AsRef<Path>>(&self, archive: &Archive, path: P) -> Result<usize, Error> {
         let mut buf: Vec<u8> = vec![0; self.size() as usize];
 
         self.read(archive, &mut buf)?;
@@ -531,4 +1224,284 @@ impl File {
 
         file.write(&buf)
     }
+
+    // read a sound file from the archive as a PCM wav
+    pub fn read_wav(&self, archive: &Archive) -> Result<Wav, Error> {
+        let mut buf: Vec<u8> = vec![0; self.size() as usize];
+
+        self.read(archive, &mut buf)?;
+
+        Wav::from_bytes(&buf)
+    }
+
+    // extract a sound file from archive to the local filesystem as a playable PCM wav
+    pub fn extract_wav<P: AsRef<Path>>(&self, archive: &Archive, path: P) -> Result<usize, Error> {
+        let bytes = self.read_wav(archive)?.to_bytes();
+
+        fs::create_dir_all(path.as_ref().parent().unwrap())?;
+
+        if path.as_ref().exists() {
+            return Err(Error::new(ErrorKind::AlreadyExists, "File already exists"));
+        }
+
+        fs::write(path, &bytes)?;
+
+        Ok(bytes.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypt::encrypt;
+    use crate::ext_tables::test::{build_bet, build_het};
+    use crate::ext_tables::BetEntry;
+    use crate::{ArchiveBuilder, Compression, FileOptions};
+
+    fn write_archive(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}_{}.mpq", name, std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn header(format_version: u16, header_size: usize) -> Vec<u8> {
+        let mut header = vec![0; header_size];
+        header[0..4].copy_from_slice(ID_MPQA);
+        LittleEndian::write_u32(&mut header[0x04..], header_size as u32);
+        LittleEndian::write_u16(&mut header[0x0C..], format_version);
+        LittleEndian::write_u16(&mut header[0x0E..], 3);
+        header
+    }
+
+    #[test]
+    fn header_table_positions_use_high_bits() {
+        let mut buffer = [0; HEADER_SIZE_V4];
+        buffer[..HEADER_SIZE_V2].copy_from_slice(&header(1, HEADER_SIZE_V2));
+        LittleEndian::write_u32(&mut buffer[0x10..], 0x100);
+        LittleEndian::write_u32(&mut buffer[0x14..], 0x200);
+        LittleEndian::write_u16(&mut buffer[0x28..], 0x1);
+        LittleEndian::write_u16(&mut buffer[0x2A..], 0x2);
+
+        let header = Header::new(&buffer);
+
+        assert_eq!(header.hash_table_position(), 0x1_0000_0100);
+        assert_eq!(header.block_table_position(), 0x2_0000_0200);
+    }
+
+    #[test]
+    fn open_v2_archive_with_hi_block_table() {
+        let content = b"hello from a v2 archive";
+        let filename = "data\\global\\excel\\levels.txt";
+
+        let mut bytes = header(1, HEADER_SIZE_V2);
+        let data_offset = bytes.len();
+        bytes.extend(content);
+
+        // hash table with 4 entries, only one is used
+        let hash_table_offset = bytes.len();
+        let mut hash_table = vec![0xFF; 4 * HASH_ENTRY_SIZE];
+        let index = (hash_string(filename, 0) & 3) as usize * HASH_ENTRY_SIZE;
+        let entry = &mut hash_table[index..index + HASH_ENTRY_SIZE];
+        LittleEndian::write_u32(entry, hash_string(filename, 0x100));
+        LittleEndian::write_u32(&mut entry[4..], hash_string(filename, 0x200));
+        LittleEndian::write_u32(&mut entry[8..], 0);
+        LittleEndian::write_u32(&mut entry[12..], 0);
+        encrypt(&mut hash_table, hash_string("(hash table)", 0x300));
+        bytes.extend(hash_table);
+
+        let block_table_offset = bytes.len();
+        let mut block_table = vec![0; BLOCK_ENTRY_SIZE];
+        LittleEndian::write_u32(&mut block_table, data_offset as u32);
+        LittleEndian::write_u32(&mut block_table[4..], content.len() as u32);
+        LittleEndian::write_u32(&mut block_table[8..], content.len() as u32);
+        LittleEndian::write_u32(&mut block_table[12..], FILE_EXISTS | FILE_SINGLE_UNIT);
+        encrypt(&mut block_table, hash_string("(block table)", 0x300));
+        bytes.extend(block_table);
+
+        let hi_block_table_offset = bytes.len();
+        bytes.extend([0, 0]);
+
+        let archive_size = bytes.len() as u32;
+        LittleEndian::write_u32(&mut bytes[0x08..], archive_size);
+        LittleEndian::write_u32(&mut bytes[0x10..], hash_table_offset as u32);
+        LittleEndian::write_u32(&mut bytes[0x14..], block_table_offset as u32);
+        LittleEndian::write_u32(&mut bytes[0x18..], 4);
+        LittleEndian::write_u32(&mut bytes[0x1C..], 1);
+        LittleEndian::write_u64(&mut bytes[0x20..], hi_block_table_offset as u64);
+
+        let path = write_archive("open_v2_archive", &bytes);
+        let archive = Archive::open(&path).unwrap();
+
+        assert_eq!(archive.read_file(filename).unwrap(), content);
+
+        // without a listfile the file is reported as unnamed
+        let file_list = archive.list_files().unwrap();
+        assert!(file_list.names.is_empty());
+        assert_eq!(file_list.unnamed.len(), 1);
+        assert_eq!(file_list.unnamed[0].block_index, 0);
+        assert!(archive
+            .open_file("data\\global\\excel\\missiles.txt")
+            .is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn open_v3_archive_with_het_and_bet_tables() {
+        let names = ["(listfile)", "data\\global\\excel\\levels.txt"];
+        let contents: [&[u8]; 2] = [b"data\\global\\excel\\levels.txt\r\n", b"Name\tId\r\n"];
+
+        let mut bytes = header(2, HEADER_SIZE_V3);
+        let mut entries = Vec::new();
+
+        for content in contents {
+            entries.push(BetEntry {
+                offset: bytes.len() as u64,
+                unpacked_size: content.len() as u64,
+                packed_size: content.len() as u64,
+                flags: FILE_EXISTS | FILE_SINGLE_UNIT,
+            });
+            bytes.extend(content);
+        }
+
+        let het_table_offset = bytes.len();
+        bytes.extend(build_het(&names));
+        let bet_table_offset = bytes.len();
+        bytes.extend(build_bet(&names, &entries));
+
+        let archive_size = bytes.len() as u64;
+        LittleEndian::write_u64(&mut bytes[0x2C..], archive_size);
+        LittleEndian::write_u64(&mut bytes[0x34..], bet_table_offset as u64);
+        LittleEndian::write_u64(&mut bytes[0x3C..], het_table_offset as u64);
+
+        let path = write_archive("open_v3_archive", &bytes);
+        let archive = Archive::open(&path).unwrap();
+
+        assert_eq!(archive.read_file(names[0]).unwrap(), contents[0]);
+        assert_eq!(
+            archive.read_file("DATA/GLOBAL/EXCEL/LEVELS.TXT").unwrap(),
+            contents[1]
+        );
+        assert!(archive
+            .open_file("data\\global\\excel\\missiles.txt")
+            .is_err());
+
+        let file_list = archive.list_files().unwrap();
+        assert_eq!(file_list.names, names);
+        assert!(file_list.unnamed.is_empty());
+        assert_eq!(
+            archive.find_files("data\\global\\*\\*.txt").unwrap(),
+            [names[1]]
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn verification_names_damaged_file_and_sector() {
+        let table = (0..400)
+            .flat_map(|i| format!("Level {i}\t{i}\t{}\r\n", i % 5).into_bytes())
+            .collect::<Vec<u8>>();
+        let compressed = "data\\global\\excel\\levels.txt";
+        let stored = "data\\global\\excel\\missiles.txt";
+
+        let mut builder = ArchiveBuilder::new();
+        builder
+            .sector_size_shift(0)
+            .attributes(true)
+            .add_file(
+                compressed,
+                &table,
+                FileOptions {
+                    encrypted: true,
+                    sector_crc: true,
+                    ..FileOptions::default()
+                },
+            )
+            .add_file(
+                stored,
+                &table,
+                FileOptions {
+                    compression: Compression::None,
+                    ..FileOptions::default()
+                },
+            );
+        let mut bytes = builder.to_bytes().unwrap();
+
+        let path = write_archive("verify_intact", &bytes);
+        let mut archive = Archive::open(&path).unwrap();
+        assert!(archive.verify().unwrap().is_empty());
+
+        let compressed_file = archive.open_file(compressed).unwrap();
+        let stored_file = archive.open_file(stored).unwrap();
+        fs::remove_file(path).unwrap();
+
+        // damage the second sector of the compressed file and the stored file
+        let position = compressed_file.block.offset + u64::from(compressed_file.sector_offsets[1]);
+        bytes[position as usize + 2] ^= 0x55;
+        bytes[stored_file.block.offset as usize + 100] ^= 0x55;
+
+        let path = write_archive("verify_damaged", &bytes);
+        let mut archive = Archive::open(&path).unwrap();
+
+        // without verification the stored file is read as it is, the sector checksums of the
+        // compressed file are still checked
+        assert!(archive.read_file(stored).is_ok());
+
+        let error = archive.read_file(compressed).unwrap_err();
+        assert_eq!(
+            IntegrityError::from_io(&error).unwrap().failure,
+            IntegrityFailure::SectorChecksum { sector: 1 }
+        );
+
+        let error = archive.verify_file(compressed).unwrap_err();
+        assert_eq!(
+            IntegrityError::from_io(&error).unwrap().failure,
+            IntegrityFailure::SectorChecksum { sector: 1 }
+        );
+
+        let damaged = archive.verify().unwrap();
+        assert_eq!(damaged.len(), 2);
+        assert_eq!(damaged[0].file, compressed);
+        assert_eq!(damaged[1].file, stored);
+        assert!(matches!(damaged[1].failure, IntegrityFailure::Crc32 { .. }));
+        assert_eq!(
+            damaged[0].to_string(),
+            format!("{compressed}: checksum of sector 1 does not match")
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_shared_archive_from_threads() {
+        let files: Vec<(String, Vec<u8>)> = (0..8)
+            .map(|i| {
+                let data = format!("Level {i}\t{i}\r\n").repeat(200 * (i + 1));
+                (format!("data\\global\\file{i}.txt"), data.into_bytes())
+            })
+            .collect();
+
+        let mut builder = ArchiveBuilder::new();
+        for (name, data) in &files {
+            builder.add_file(name, data, FileOptions::default());
+        }
+        let path = write_archive("shared_archive", &builder.to_bytes().unwrap());
+
+        let archive = Archive::open(&path).unwrap();
+
+        std::thread::scope(|scope| {
+            for (name, data) in &files {
+                let archive = &archive;
+
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        assert_eq!(&archive.read_file(name).unwrap(), data);
+                    }
+                });
+            }
+        });
+
+        fs::remove_file(path).unwrap();
+    }
 }
@@ -0,0 +1,284 @@
+use mpq_reader::{Archive, BlockInfo, IntegrityError};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::Error;
+use std::path::{Component, Path, PathBuf};
+use std::process::exit;
+
+const USAGE: &str = "\
+Usage: mpq-tool <command> <archive> [arguments]
+
+Commands:
+  list <archive> [listfile...]       list the files named by the (listfile) and external listfiles
+  extract <archive> <pattern> <dir>  extract the files matching a pattern like data\\global\\excel\\*.txt
+  info <archive>                     show the header, table sizes and compression statistics
+  verify <archive>                   check the sector checksums and the (attributes) of all files";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["list", archive, ref listfiles @ ..] => list(archive, listfiles),
+        ["extract", archive, pattern, dir] => extract(archive, pattern, dir),
+        ["info", archive] => info(archive),
+        ["verify", archive] => verify(archive),
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => exit(1),
+        Err(e) => {
+            eprintln!("error: {e}");
+            exit(1);
+        }
+    }
+}
+
+fn open(path: &str) -> Result<Archive, Error> {
+    Archive::open(path).map_err(|e| Error::new(e.kind(), format!("{path}: {e}")))
+}
+
+fn list(path: &str, listfiles: &[&str]) -> Result<bool, Error> {
+    let archive = open(path)?;
+    let file_list = archive.list_files_with(listfiles)?;
+
+    for name in &file_list.names {
+        println!("{name}");
+    }
+
+    if !file_list.unnamed.is_empty() {
+        eprintln!(
+            "{} files are not named by a listfile",
+            file_list.unnamed.len()
+        );
+    }
+
+    Ok(true)
+}
+
+fn extract(path: &str, pattern: &str, dir: &str) -> Result<bool, Error> {
+    let archive = open(path)?;
+    let names = archive.find_files(pattern)?;
+    let mut extracted = 0;
+
+    for name in &names {
+        let Some(out_path) = output_path(dir, name) else {
+            eprintln!("skipped {name}: the name leaves the output directory");
+            continue;
+        };
+
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&out_path, archive.read_file(name)?)?;
+
+        println!("{}", out_path.display());
+        extracted += 1;
+    }
+
+    eprintln!("extracted {extracted} files");
+
+    Ok(extracted == names.len())
+}
+
+// the path inside the output directory, names which are absolute or contain ".." have none
+fn output_path(dir: &str, name: &str) -> Option<PathBuf> {
+    let relative_path: PathBuf = name.split(['\\', '/']).collect();
+    let mut components = relative_path.components().peekable();
+
+    match components.peek().is_some() && components.all(|c| matches!(c, Component::Normal(_))) {
+        true => Some(Path::new(dir).join(relative_path)),
+        false => None,
+    }
+}
+
+#[derive(Default)]
+struct CompressionStats {
+    files: usize,
+    packed_size: u64,
+    unpacked_size: u64,
+}
+
+fn info(path: &str) -> Result<bool, Error> {
+    let archive = open(path)?;
+    let info = archive.info();
+
+    let field = |name: &str, value: &dyn std::fmt::Display| println!("{name:<22}{value}");
+
+    field("format version", &info.format_version);
+    field("header offset", &format!("{:#x}", info.header_offset));
+    field("archive size", &info.archive_size);
+    field("sector size", &info.sector_size);
+    field("hash table entries", &info.hash_table_entries);
+    field("block table entries", &info.block_table_entries);
+    field("hi-block table", &info.hi_block_table);
+    field("HET/BET tables", &info.het_bet_tables);
+    field("user data", &info.user_data);
+
+    let blocks: Vec<&BlockInfo> = info.blocks.iter().filter(|block| block.exists()).collect();
+    let mut stats: BTreeMap<String, CompressionStats> = BTreeMap::new();
+
+    for block in &blocks {
+        let entry = stats.entry(block.compression()).or_default();
+        entry.files += 1;
+        entry.packed_size += u64::from(block.packed_size);
+        entry.unpacked_size += u64::from(block.unpacked_size);
+    }
+
+    let count = |f: fn(&BlockInfo) -> bool| blocks.iter().filter(|block| f(block)).count();
+
+    println!();
+    field("files", &blocks.len());
+    field("encrypted", &count(BlockInfo::encrypted));
+    field("single unit", &count(BlockInfo::single_unit));
+    field("sector checksums", &count(BlockInfo::sector_crc));
+
+    println!();
+    println!(
+        "{:<22}{:>8}{:>14}{:>14}{:>8}",
+        "compression", "files", "packed", "unpacked", "ratio"
+    );
+
+    for (compression, stats) in &stats {
+        let ratio = match stats.unpacked_size {
+            0 => 1.0,
+            unpacked_size => stats.packed_size as f64 / unpacked_size as f64,
+        };
+
+        println!(
+            "{:<22}{:>8}{:>14}{:>14}{:>8.3}",
+            compression, stats.files, stats.packed_size, stats.unpacked_size, ratio
+        );
+    }
+
+    Ok(true)
+}
+
+fn verify(path: &str) -> Result<bool, Error> {
+    let mut archive = open(path)?;
+    let names = archive.list_files()?.names;
+    let damaged: Vec<IntegrityError> = archive.verify_files(&names)?;
+
+    for error in &damaged {
+        println!("{error}");
+    }
+
+    println!("{} of {} files are damaged", damaged.len(), names.len());
+
+    Ok(damaged.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mpq_reader::{ArchiveBuilder, Compression, FileOptions};
+
+    const LEVELS: &[u8] = b"Name\tId\r\nAct 1 - Town\t1\r\nAct 1 - Wilderness 1\t2\r\n";
+
+    fn build_archive(dir: &Path) -> String {
+        let sounds: Vec<u8> = (0..3000u32).flat_map(|i| (i % 7).to_le_bytes()).collect();
+
+        let path = dir.join("test.mpq");
+        ArchiveBuilder::new()
+            .sector_size_shift(0)
+            .attributes(true)
+            .add_file(
+                "data\\global\\excel\\levels.txt",
+                LEVELS,
+                FileOptions::default(),
+            )
+            .add_file(
+                "data\\global\\excel\\sounds.txt",
+                &sounds,
+                FileOptions {
+                    compression: Compression::Bzip2,
+                    encrypted: true,
+                    sector_crc: true,
+                    ..Default::default()
+                },
+            )
+            .add_file(
+                "..\\evil.txt",
+                b"outside",
+                FileOptions {
+                    compression: Compression::None,
+                    ..Default::default()
+                },
+            )
+            .write(&path)
+            .unwrap();
+
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn list_extract_and_verify() {
+        let dir = env::temp_dir().join(format!("mpq_tool_{}", std::process::id()));
+        let out_dir = dir.join("out");
+        fs::create_dir_all(&dir).unwrap();
+        let path = build_archive(&dir);
+
+        assert!(list(&path, &[]).unwrap());
+        assert!(info(&path).unwrap());
+        assert!(verify(&path).unwrap());
+
+        // the file with ".." in its name is skipped, the others are extracted
+        assert!(!extract(&path, "**", out_dir.to_str().unwrap()).unwrap());
+        assert_eq!(
+            fs::read(out_dir.join("data/global/excel/levels.txt")).unwrap(),
+            LEVELS
+        );
+        assert!(out_dir.join("data/global/excel/sounds.txt").exists());
+        assert!(!dir.join("evil.txt").exists());
+
+        assert!(extract(
+            &path,
+            "data\\global\\excel\\*.txt",
+            out_dir.to_str().unwrap()
+        )
+        .unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn info_names_the_compression_of_each_file() {
+        let dir = env::temp_dir().join(format!("mpq_tool_info_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive = Archive::open(build_archive(&dir)).unwrap();
+
+        let mut compressions: Vec<String> = archive
+            .info()
+            .blocks
+            .iter()
+            .map(BlockInfo::compression)
+            .collect();
+        compressions.sort();
+
+        // the (listfile) and (attributes) are too short to get smaller by compression
+        assert_eq!(compressions, ["bzip2", "none", "none", "none", "zlib"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn output_path_stays_inside_the_directory() {
+        assert_eq!(
+            output_path("out", "data\\global\\excel\\levels.txt"),
+            Some(PathBuf::from("out/data/global/excel/levels.txt"))
+        );
+        assert_eq!(output_path("out", "..\\evil.txt"), None);
+        assert_eq!(output_path("out", "data/../../evil.txt"), None);
+        assert_eq!(
+            output_path("out", "\\etc\\passwd"),
+            Some(PathBuf::from("out/etc/passwd"))
+        );
+        assert_eq!(output_path("out", ""), None);
+    }
+}
@@ -0,0 +1,468 @@
+use crate::archive::{
+    BLOCK_ENTRY_SIZE, FILE_COMPRESS, FILE_ENCRYPTED, FILE_EXISTS, FILE_FIX_KEY, FILE_IMPLODE,
+    FILE_SECTOR_CRC, FILE_SINGLE_UNIT, HASH_ENTRY_EMPTY, HASH_ENTRY_SIZE, HEADER_SIZE_V1, ID_MPQA,
+};
+use crate::compression::{compress, COMPRESSION_BZIP2, COMPRESSION_ZLIB};
+use crate::crypt::{encrypt, hash_string};
+use crate::integrity::build_attributes;
+use crate::pkware::implode;
+use adler32::RollingAdler32;
+use byteorder::{ByteOrder, LittleEndian};
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+
+const LISTFILE: &str = "(listfile)";
+const ATTRIBUTES: &str = "(attributes)";
+// sectors of 4096 bytes, as in the game archives
+const DEFAULT_SECTOR_SIZE_SHIFT: u16 = 3;
+const MIN_HASH_TABLE_SIZE: usize = 16;
+
+/// Compression of a file written by [`ArchiveBuilder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    None,
+    #[default]
+    Zlib,
+    /// PKWARE Data Compression Library, as used by most files of the game archives
+    Pkware,
+    Bzip2,
+}
+
+/// How a file is stored by [`ArchiveBuilder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FileOptions {
+    pub compression: Compression,
+    pub encrypted: bool,
+    /// alter the encryption key by the position of the file in the archive
+    pub fix_key: bool,
+    /// store the file as a single unit instead of in sectors
+    pub single_unit: bool,
+    /// store an adler32 checksum of each sector, only used for compressed files in sectors
+    pub sector_crc: bool,
+}
+
+struct PendingFile {
+    name: String,
+    data: Vec<u8>,
+    options: FileOptions,
+}
+
+/// Writes MPQ v1 archives, for example patch archives with modified Excel tables.
+///
+/// ```no_run
+/// use mpq_reader::{ArchiveBuilder, FileOptions};
+///
+/// let mut builder = ArchiveBuilder::new();
+/// builder.add_file("data\\global\\excel\\levels.txt", b"Name\tId\r\n", FileOptions::default());
+/// builder.write("patch_test.mpq").unwrap();
+/// ```
+pub struct ArchiveBuilder {
+    sector_size_shift: u16,
+    listfile: bool,
+    attributes: bool,
+    files: Vec<PendingFile>,
+}
+
+impl Default for ArchiveBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArchiveBuilder {
+    pub fn new() -> ArchiveBuilder {
+        ArchiveBuilder {
+            sector_size_shift: DEFAULT_SECTOR_SIZE_SHIFT,
+            listfile: true,
+            attributes: false,
+            files: Vec::new(),
+        }
+    }
+
+    /// Sectors are `512 << shift` bytes large
+    pub fn sector_size_shift(&mut self, shift: u16) -> &mut Self {
+        self.sector_size_shift = shift;
+        self
+    }
+
+    /// Whether a `(listfile)` naming all files is generated, enabled by default
+    pub fn listfile(&mut self, listfile: bool) -> &mut Self {
+        self.listfile = listfile;
+        self
+    }
+
+    /// Whether an `(attributes)` file with the CRC32 and MD5 of all files is generated
+    pub fn attributes(&mut self, attributes: bool) -> &mut Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Adds a file, replacing an earlier file of the same name
+    pub fn add_file(&mut self, name: &str, data: &[u8], options: FileOptions) -> &mut Self {
+        let name = name.replace('/', "\\");
+
+        self.files
+            .retain(|file| !file.name.eq_ignore_ascii_case(&name));
+        self.files.push(PendingFile {
+            name,
+            data: data.to_vec(),
+            options,
+        });
+
+        self
+    }
+
+    /// Builds the archive in memory
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let listfile = self.listfile.then(|| {
+            let names: Vec<&str> = self
+                .files
+                .iter()
+                .map(|file| file.name.as_str())
+                .filter(|name| *name != LISTFILE)
+                .collect();
+
+            PendingFile {
+                name: String::from(LISTFILE),
+                data: names.join("\r\n").into_bytes(),
+                options: FileOptions::default(),
+            }
+        });
+
+        let mut files: Vec<&PendingFile> = self
+            .files
+            .iter()
+            .filter(|file| !(self.listfile && file.name == LISTFILE))
+            .filter(|file| !(self.attributes && file.name == ATTRIBUTES))
+            .chain(listfile.as_ref())
+            .collect();
+
+        // the checksums of all other files, the entry of the attributes file itself is empty
+        let attributes = self.attributes.then(|| {
+            let checksummed: Vec<Option<&[u8]>> = files
+                .iter()
+                .map(|file| Some(file.data.as_slice()))
+                .chain([None])
+                .collect();
+
+            PendingFile {
+                name: String::from(ATTRIBUTES),
+                data: build_attributes(&checksummed),
+                options: FileOptions::default(),
+            }
+        });
+
+        files.extend(attributes.as_ref());
+
+        let hash_table_size = (files.len() * 2)
+            .next_power_of_two()
+            .max(MIN_HASH_TABLE_SIZE);
+
+        let mut bytes = vec![0; HEADER_SIZE_V1];
+        let mut hash_table = vec![0xFF; hash_table_size * HASH_ENTRY_SIZE];
+        let mut block_table = Vec::with_capacity(files.len() * BLOCK_ENTRY_SIZE);
+
+        for (block_index, file) in files.iter().enumerate() {
+            let offset = bytes.len() as u32;
+            let (data, flags) = self.write_file(file, offset)?;
+
+            let mut block = [0; BLOCK_ENTRY_SIZE];
+            LittleEndian::write_u32(&mut block, offset);
+            LittleEndian::write_u32(&mut block[0x4..], data.len() as u32);
+            LittleEndian::write_u32(&mut block[0x8..], file.data.len() as u32);
+            LittleEndian::write_u32(&mut block[0xC..], flags);
+            block_table.extend(block);
+
+            bytes.extend(data);
+
+            // insert into the hash table, collisions move on to the next entry
+            let mut hash_index = hash_string(&file.name, 0x0) as usize & (hash_table_size - 1);
+
+            while LittleEndian::read_u32(&hash_table[hash_index * HASH_ENTRY_SIZE + 12..])
+                != HASH_ENTRY_EMPTY
+            {
+                hash_index = (hash_index + 1) % hash_table_size;
+            }
+
+            let hash = &mut hash_table[hash_index * HASH_ENTRY_SIZE..][..HASH_ENTRY_SIZE];
+            LittleEndian::write_u32(hash, hash_string(&file.name, 0x100));
+            LittleEndian::write_u32(&mut hash[4..], hash_string(&file.name, 0x200));
+            LittleEndian::write_u16(&mut hash[8..], 0); // neutral locale
+            LittleEndian::write_u16(&mut hash[10..], 0);
+            LittleEndian::write_u32(&mut hash[12..], block_index as u32);
+        }
+
+        encrypt(&mut hash_table, hash_string("(hash table)", 0x300));
+        encrypt(&mut block_table, hash_string("(block table)", 0x300));
+
+        let hash_table_offset = bytes.len();
+        bytes.extend(hash_table);
+        let block_table_offset = bytes.len();
+        bytes.extend(block_table);
+
+        let archive_size = bytes.len() as u32;
+        let header = &mut bytes[..HEADER_SIZE_V1];
+        header[0..4].copy_from_slice(ID_MPQA);
+        LittleEndian::write_u32(&mut header[0x04..], HEADER_SIZE_V1 as u32);
+        LittleEndian::write_u32(&mut header[0x08..], archive_size);
+        LittleEndian::write_u16(&mut header[0x0C..], 0);
+        LittleEndian::write_u16(&mut header[0x0E..], self.sector_size_shift);
+        LittleEndian::write_u32(&mut header[0x10..], hash_table_offset as u32);
+        LittleEndian::write_u32(&mut header[0x14..], block_table_offset as u32);
+        LittleEndian::write_u32(&mut header[0x18..], hash_table_size as u32);
+        LittleEndian::write_u32(&mut header[0x1C..], files.len() as u32);
+
+        Ok(bytes)
+    }
+
+    /// Builds the archive and writes it to the local filesystem
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.to_bytes()?)
+    }
+
+    // returns the stored data of a file and its block flags
+    fn write_file(&self, file: &PendingFile, offset: u32) -> Result<(Vec<u8>, u32), Error> {
+        let options = file.options;
+        let mut flags = FILE_EXISTS;
+
+        // empty files are stored as they are
+        if file.data.is_empty() {
+            return Ok((Vec::new(), flags | FILE_SINGLE_UNIT));
+        }
+
+        let compression_flag = match options.compression {
+            Compression::None => 0,
+            Compression::Pkware => FILE_IMPLODE,
+            Compression::Zlib | Compression::Bzip2 => FILE_COMPRESS,
+        };
+
+        let compress_unit = |data: &[u8]| -> Result<Vec<u8>, Error> {
+            match options.compression {
+                Compression::None => Ok(data.to_vec()),
+                Compression::Zlib => compress(data, COMPRESSION_ZLIB),
+                Compression::Bzip2 => compress(data, COMPRESSION_BZIP2),
+                Compression::Pkware => Ok(implode(data)),
+            }
+        };
+
+        let mut file_key = 0;
+
+        if options.encrypted {
+            let basename = file.name.rsplit('\\').next().unwrap_or(&file.name);
+            file_key = hash_string(basename, 0x300);
+
+            if options.fix_key {
+                file_key = file_key.wrapping_add(offset) ^ file.data.len() as u32;
+                flags |= FILE_FIX_KEY;
+            }
+
+            flags |= FILE_ENCRYPTED;
+        }
+
+        if options.single_unit {
+            flags |= FILE_SINGLE_UNIT;
+
+            let mut data = compress_unit(&file.data)?;
+
+            // data which doesn't get smaller is stored uncompressed
+            if data.len() < file.data.len() {
+                flags |= compression_flag;
+            } else {
+                data = file.data.clone();
+            }
+
+            if options.encrypted {
+                encrypt(&mut data, file_key);
+            }
+
+            return Ok((data, flags));
+        }
+
+        let sector_size = 512 << self.sector_size_shift;
+
+        if compression_flag == 0 {
+            let mut data = file.data.clone();
+
+            if options.encrypted {
+                for (i, sector) in data.chunks_mut(sector_size).enumerate() {
+                    encrypt(sector, file_key.wrapping_add(i as u32));
+                }
+            }
+
+            return Ok((data, flags));
+        }
+
+        flags |= compression_flag;
+
+        // with sector checksums the offsets end with the end of the checksum sector
+        let num_sectors = file.data.len().div_ceil(sector_size);
+        let num_offsets = num_sectors + 1 + usize::from(options.sector_crc);
+        let sector_offsets_size = num_offsets * 4;
+        let mut sector_offsets = vec![0; sector_offsets_size];
+        let mut sectors = Vec::new();
+        let mut sector_checksums = Vec::new();
+
+        for (i, sector) in file.data.chunks(sector_size).enumerate() {
+            LittleEndian::write_u32(
+                &mut sector_offsets[i * 4..],
+                (sector_offsets_size + sectors.len()) as u32,
+            );
+
+            let mut data = compress_unit(sector)?;
+
+            // sectors which don't get smaller are stored uncompressed
+            if data.len() >= sector.len() {
+                data = sector.to_vec();
+            }
+
+            let mut adler = RollingAdler32::from_value(0);
+            adler.update_buffer(&data);
+            sector_checksums.extend(adler.hash().to_le_bytes());
+
+            if options.encrypted {
+                encrypt(&mut data, file_key.wrapping_add(i as u32));
+            }
+
+            sectors.extend(data);
+        }
+
+        LittleEndian::write_u32(
+            &mut sector_offsets[num_sectors * 4..],
+            (sector_offsets_size + sectors.len()) as u32,
+        );
+
+        if options.sector_crc {
+            sectors.extend(sector_checksums);
+            flags |= FILE_SECTOR_CRC;
+
+            LittleEndian::write_u32(
+                &mut sector_offsets[(num_sectors + 1) * 4..],
+                (sector_offsets_size + sectors.len()) as u32,
+            );
+        }
+
+        if options.encrypted {
+            encrypt(&mut sector_offsets, file_key.wrapping_sub(1));
+        }
+
+        sector_offsets.extend(sectors);
+
+        Ok((sector_offsets, flags))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArchiveBuilder, Compression, FileOptions};
+    use crate::Archive;
+    use std::fs;
+
+    fn excel_table(rows: usize) -> Vec<u8> {
+        let mut table = b"Name\tId\tAct\tMonLvl1\r\n".to_vec();
+
+        for i in 0..rows {
+            table.extend(format!("Level {i}\t{i}\t{}\t{}\r\n", i / 40, i % 85).bytes());
+        }
+
+        table
+    }
+
+    #[test]
+    fn round_trip() {
+        let files: Vec<(String, Vec<u8>, FileOptions)> = [
+            Compression::None,
+            Compression::Zlib,
+            Compression::Pkware,
+            Compression::Bzip2,
+        ]
+        .into_iter()
+        .flat_map(|compression| {
+            [
+                (false, false, false),
+                (true, false, false),
+                (true, true, false),
+                (true, true, true),
+                (false, false, true),
+            ]
+            .into_iter()
+            .map(move |(encrypted, fix_key, single_unit)| FileOptions {
+                compression,
+                encrypted,
+                fix_key,
+                single_unit,
+                ..FileOptions::default()
+            })
+        })
+        .enumerate()
+        .map(|(i, options)| {
+            (
+                format!("data\\global\\excel\\table{i}.txt"),
+                excel_table(i * 37),
+                options,
+            )
+        })
+        .collect();
+
+        let mut builder = ArchiveBuilder::new();
+        for (name, data, options) in &files {
+            builder.add_file(name, data, *options);
+        }
+        builder.add_file("data/local/empty.txt", &[], FileOptions::default());
+
+        let path =
+            std::env::temp_dir().join(format!("builder_round_trip_{}.mpq", std::process::id()));
+        builder.write(&path).unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+
+        for (name, data, options) in &files {
+            assert_eq!(
+                &archive.read_file(name).unwrap(),
+                data,
+                "{name} {options:?}"
+            );
+        }
+        assert!(archive
+            .read_file("data\\local\\empty.txt")
+            .unwrap()
+            .is_empty());
+
+        let file_list = archive.list_files().unwrap();
+        assert_eq!(file_list.names.len(), files.len() + 2);
+        assert!(file_list.unnamed.is_empty());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn add_file_replaces_same_name() {
+        let mut builder = ArchiveBuilder::new();
+        builder
+            .listfile(false)
+            .add_file(
+                "data\\global\\excel\\levels.txt",
+                b"old",
+                FileOptions::default(),
+            )
+            .add_file(
+                "DATA/GLOBAL/EXCEL/LEVELS.TXT",
+                b"new",
+                FileOptions::default(),
+            );
+
+        let path = std::env::temp_dir().join(format!("builder_replace_{}.mpq", std::process::id()));
+        builder.write(&path).unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        assert_eq!(
+            archive
+                .read_file("data\\global\\excel\\levels.txt")
+                .unwrap(),
+            b"new"
+        );
+        assert!(archive.open_file("(listfile)").is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+}
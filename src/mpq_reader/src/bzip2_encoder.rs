@@ -0,0 +1,357 @@
+const BLOCK_MAGIC: u64 = 0x314159265359;
+const FINAL_MAGIC: u64 = 0x177245385090;
+
+// compression level 9, blocks of up to 900k bytes after the initial run length encoding
+const BLOCK_SIZE_LEVEL: u8 = 9;
+// the initial run length encoding grows data by at most 5/4
+const MAX_BLOCK_INPUT: usize = 700_000;
+
+const NUM_TABLES: usize = 2;
+const GROUP_SIZE: usize = 50;
+const MAX_CODE_LENGTH: u8 = 17;
+
+const RUN_A: u16 = 0;
+const RUN_B: u16 = 1;
+
+// MSB first bit writer
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit_buffer: u64,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, count: u32) {
+        for bit in (0..count).rev() {
+            self.bit_buffer = (self.bit_buffer << 1) | ((value >> bit) & 1);
+            self.bit_count += 1;
+
+            if self.bit_count == 8 {
+                self.bytes.push(self.bit_buffer as u8);
+                self.bit_buffer = 0;
+                self.bit_count = 0;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.bytes
+                .push((self.bit_buffer << (8 - self.bit_count)) as u8);
+        }
+
+        self.bytes
+    }
+}
+
+fn crc(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFF_u32;
+
+    for &byte in data {
+        crc ^= u32::from(byte) << 24;
+
+        for _ in 0..8 {
+            crc = match crc & 0x80000000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x04C11DB7,
+            };
+        }
+    }
+
+    !crc
+}
+
+// Runs of 4 to 255 equal bytes are stored as 4 bytes followed by the number of remaining bytes
+fn run_length_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let byte = data[pos];
+        let run = data[pos..]
+            .iter()
+            .take(255)
+            .take_while(|&&b| b == byte)
+            .count();
+
+        if run >= 4 {
+            out.extend([byte; 4]);
+            out.push((run - 4) as u8);
+        } else {
+            out.extend(&data[pos..pos + run]);
+        }
+
+        pos += run;
+    }
+
+    out
+}
+
+// Burrows-Wheeler transform by prefix doubling of the rotations,
+// returns the last column and the row of the original data
+fn burrows_wheeler_transform(data: &[u8]) -> (Vec<u8>, usize) {
+    let n = data.len();
+    let mut rotations: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = data.iter().map(|&byte| usize::from(byte)).collect();
+    let mut next_rank = vec![0; n];
+    let mut k = 1;
+
+    loop {
+        let key = |i: usize| (rank[i], rank[(i + k) % n]);
+
+        rotations.sort_by_key(|&i| key(i));
+
+        next_rank[rotations[0]] = 0;
+        for j in 1..n {
+            let changed = key(rotations[j]) != key(rotations[j - 1]);
+            next_rank[rotations[j]] = next_rank[rotations[j - 1]] + usize::from(changed);
+        }
+
+        std::mem::swap(&mut rank, &mut next_rank);
+
+        if rank[rotations[n - 1]] == n - 1 || k >= n {
+            break;
+        }
+
+        k *= 2;
+    }
+
+    let last_column = rotations.iter().map(|&i| data[(i + n - 1) % n]).collect();
+    let origin = rotations.iter().position(|&i| i == 0).unwrap();
+
+    (last_column, origin)
+}
+
+// Move to front transform with the runs of zeros written as RUNA and RUNB digits,
+// the end of block symbol is the last symbol of the alphabet
+fn move_to_front_encode(data: &[u8], in_use: &[bool; 256]) -> (Vec<u16>, usize) {
+    let mut order: Vec<u8> = (0..=255).filter(|&b| in_use[b as usize]).collect();
+    let end_of_block = order.len() as u16 + 1;
+    let mut symbols = Vec::with_capacity(data.len() + 1);
+    let mut zeros = 0_usize;
+
+    let flush_zeros = |zeros: &mut usize, symbols: &mut Vec<u16>| {
+        if *zeros == 0 {
+            return;
+        }
+
+        let mut run = *zeros - 1;
+
+        loop {
+            symbols.push(if run & 1 == 0 { RUN_A } else { RUN_B });
+
+            if run < 2 {
+                break;
+            }
+
+            run = (run - 2) / 2;
+        }
+
+        *zeros = 0;
+    };
+
+    for &byte in data {
+        let index = order.iter().position(|&b| b == byte).unwrap();
+
+        if index == 0 {
+            zeros += 1;
+            continue;
+        }
+
+        flush_zeros(&mut zeros, &mut symbols);
+
+        order.remove(index);
+        order.insert(0, byte);
+
+        symbols.push(index as u16 + 1);
+    }
+
+    flush_zeros(&mut zeros, &mut symbols);
+    symbols.push(end_of_block);
+
+    (symbols, end_of_block as usize + 1)
+}
+
+// Huffman code lengths, frequencies are flattened until no code is longer than the maximum
+fn code_lengths(frequencies: &[u32]) -> Vec<u8> {
+    let mut weights: Vec<u64> = frequencies.iter().map(|&f| u64::from(f.max(1))).collect();
+
+    loop {
+        // nodes of the tree, the first ones are the leaves
+        let mut parents: Vec<usize> = vec![usize::MAX; weights.len()];
+        let mut nodes: Vec<(u64, usize)> = weights.iter().copied().zip(0..).collect();
+
+        while nodes.len() > 1 {
+            nodes.sort_by(|a, b| b.cmp(a));
+
+            let (weight_a, a) = nodes.pop().unwrap();
+            let (weight_b, b) = nodes.pop().unwrap();
+            let parent = parents.len();
+
+            parents.push(usize::MAX);
+            parents[a] = parent;
+            parents[b] = parent;
+            nodes.push((weight_a + weight_b, parent));
+        }
+
+        let lengths: Vec<u8> = (0..weights.len())
+            .map(|leaf| {
+                let mut length = 0;
+                let mut node = leaf;
+
+                while parents[node] != usize::MAX {
+                    node = parents[node];
+                    length += 1;
+                }
+
+                length.max(1)
+            })
+            .collect();
+
+        if lengths.iter().all(|&length| length <= MAX_CODE_LENGTH) {
+            return lengths;
+        }
+
+        for weight in &mut weights {
+            *weight = 1 + *weight / 2;
+        }
+    }
+}
+
+// canonical codes, assigned in order of length and symbol
+fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut codes = vec![0; lengths.len()];
+    let mut code = 0;
+
+    for length in 1..=MAX_CODE_LENGTH {
+        for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l == length) {
+            codes[symbol] = code;
+            code += 1;
+        }
+
+        code <<= 1;
+    }
+
+    codes
+}
+
+fn write_block(writer: &mut BitWriter, data: &[u8]) -> u32 {
+    let block_crc = crc(data);
+    let encoded = run_length_encode(data);
+    let (last_column, origin) = burrows_wheeler_transform(&encoded);
+
+    let mut in_use = [false; 256];
+    for &byte in &encoded {
+        in_use[byte as usize] = true;
+    }
+
+    let (symbols, alphabet_size) = move_to_front_encode(&last_column, &in_use);
+
+    writer.write(BLOCK_MAGIC, 48);
+    writer.write(u64::from(block_crc), 32);
+    writer.write(0, 1); // not randomised
+    writer.write(origin as u64, 24);
+
+    // map of the used bytes, in groups of 16
+    for group in in_use.chunks(16) {
+        writer.write(u64::from(group.contains(&true)), 1);
+    }
+    for group in in_use.chunks(16).filter(|group| group.contains(&true)) {
+        for &used in group {
+            writer.write(u64::from(used), 1);
+        }
+    }
+
+    // all groups of symbols use the same table, the decoder requires at least two
+    let mut frequencies = vec![0; alphabet_size];
+    for &symbol in &symbols {
+        frequencies[symbol as usize] += 1;
+    }
+
+    let lengths = code_lengths(&frequencies);
+    let codes = canonical_codes(&lengths);
+    let num_selectors = symbols.len().div_ceil(GROUP_SIZE);
+
+    writer.write(NUM_TABLES as u64, 3);
+    writer.write(num_selectors as u64, 15);
+    for _ in 0..num_selectors {
+        writer.write(0, 1);
+    }
+
+    // code lengths as deltas
+    for _ in 0..NUM_TABLES {
+        let mut current = lengths[0];
+        writer.write(u64::from(current), 5);
+
+        for &length in &lengths {
+            while current < length {
+                writer.write(0b10, 2);
+                current += 1;
+            }
+            while current > length {
+                writer.write(0b11, 2);
+                current -= 1;
+            }
+
+            writer.write(0, 1);
+        }
+    }
+
+    for &symbol in &symbols {
+        let symbol = symbol as usize;
+        writer.write(u64::from(codes[symbol]), u32::from(lengths[symbol]));
+    }
+
+    block_crc
+}
+
+/// Compresses data into a bzip2 stream
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        bytes: vec![b'B', b'Z', b'h', b'0' + BLOCK_SIZE_LEVEL],
+        bit_buffer: 0,
+        bit_count: 0,
+    };
+
+    let mut combined_crc = 0_u32;
+
+    for block in data.chunks(MAX_BLOCK_INPUT) {
+        let block_crc = write_block(&mut writer, block);
+        combined_crc = combined_crc.rotate_left(1) ^ block_crc;
+    }
+
+    writer.write(FINAL_MAGIC, 48);
+    writer.write(u64::from(combined_crc), 32);
+
+    writer.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::compress;
+    use bzip2_rs::DecoderReader;
+    use std::io::Read;
+
+    fn decompress(compressed: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        DecoderReader::new(compressed)
+            .read_to_end(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut data =
+            b"Name\tId\tAct\r\nAct 1 - Town\t1\t0\r\nAct 1 - Wilderness 1\t2\t0\r\n".repeat(20);
+        data.extend([0; 600]);
+        data.extend((0..=255).collect::<Vec<u8>>());
+
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len() / 3);
+        assert_eq!(decompress(&compressed), data);
+
+        assert_eq!(decompress(&compress(b"a")), b"a");
+        assert_eq!(decompress(&compress(&[7; 4096])), [7; 4096]);
+    }
+}
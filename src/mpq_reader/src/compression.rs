@@ -1,121 +1,167 @@
+use crate::{adpcm, bzip2_encoder, huffman};
 use bzip2_rs as bzip2;
 use implode::exploder::Exploder;
 use implode::symbol::DEFAULT_CODE_TABLE;
-use std::io::{self, Error, ErrorKind};
+use std::io::{self, Error, ErrorKind, Write};
 
 const COMPRESSION_HUFFMAN: u8 = 0x01;
-const COMPRESSION_ZLIB: u8 = 0x02;
+pub(crate) const COMPRESSION_ZLIB: u8 = 0x02;
 const COMPRESSION_PKWARE: u8 = 0x08;
-const COMPRESSION_BZIP2: u8 = 0x10;
+pub(crate) const COMPRESSION_BZIP2: u8 = 0x10;
 const COMPRESSION_SPARSE: u8 = 0x20;
 const COMPRESSION_ADPCM_MONO: u8 = 0x40;
 const COMPRESSION_ADPCM_STEREO: u8 = 0x80;
 const COMPRESSION_LZMA: u8 = 0x12;
 
-pub fn decompress(data: &mut [u8], out: &mut [u8]) -> Result<usize, Error> {
-    let compression_type = data[0];
+// Order in which the compression algorithms of a multi-compressed sector are undone.
+// The sector is compressed in the opposite order.
+const DECOMPRESSION_ORDER: [u8; 7] = [
+    COMPRESSION_BZIP2,
+    COMPRESSION_PKWARE,
+    COMPRESSION_ZLIB,
+    COMPRESSION_HUFFMAN,
+    COMPRESSION_ADPCM_STEREO,
+    COMPRESSION_ADPCM_MONO,
+    COMPRESSION_SPARSE,
+];
+
+/// Names of the algorithms in a compression type byte in the order they are undone,
+/// like "huffman+adpcm mono"
+pub fn compression_names(compression_type: u8) -> String {
+    if compression_type == COMPRESSION_LZMA {
+        return String::from("lzma");
+    }
 
-    if compression_type & COMPRESSION_BZIP2 != 0 {
-        let mut ouput = io::Cursor::new(out);
-        let mut reader = bzip2::DecoderReader::new(&data[1..]);
-        io::copy(&mut reader, &mut ouput)?;
-        return Ok(ouput.position() as usize);
+    let names: Vec<&str> = DECOMPRESSION_ORDER
+        .into_iter()
+        .filter(|compression| compression_type & compression != 0)
+        .map(|compression| match compression {
+            COMPRESSION_BZIP2 => "bzip2",
+            COMPRESSION_PKWARE => "pkware",
+            COMPRESSION_ZLIB => "zlib",
+            COMPRESSION_HUFFMAN => "huffman",
+            COMPRESSION_ADPCM_STEREO => "adpcm stereo",
+            COMPRESSION_ADPCM_MONO => "adpcm mono",
+            _ => "sparse",
+        })
+        .collect();
+
+    match names.is_empty() {
+        true => format!("{compression_type:#04x}"),
+        false => names.join("+"),
     }
+}
 
-    if compression_type & COMPRESSION_ZLIB != 0 {
-        let mut zlib = flate2::Decompress::new(true);
+pub fn decompress(data: &mut [u8], out: &mut [u8]) -> Result<usize, Error> {
+    let Some(&compression_type) = data.first() else {
+        return Err(Error::new(ErrorKind::InvalidData, "Empty compressed data"));
+    };
 
-        match zlib.decompress(&data[1..], out, flate2::FlushDecompress::None) {
-            Ok(_) => {}
-            Err(e) => return Err(Error::new(ErrorKind::Other, e)),
-        }
+    if compression_type == COMPRESSION_LZMA {
+        return Err(Error::other("Compression algorithm LZMA not supported"));
+    }
+
+    let compressions: Vec<u8> = DECOMPRESSION_ORDER
+        .into_iter()
+        .filter(|compression| compression_type & compression != 0)
+        .collect();
 
-        return Ok(zlib.total_out() as usize);
+    if compressions.is_empty() {
+        return Err(Error::other("No compression type found"));
     }
 
-    if compression_type & COMPRESSION_PKWARE != 0 {
-        let mut exploder = Exploder::new(&DEFAULT_CODE_TABLE);
+    let (last_compression, compressions) = compressions.split_last().unwrap();
 
-        let mut cpos: u32 = 1;
-        let len = data.len();
-        let mut c = 0;
+    let mut in_buf = data[1..].to_vec();
 
-        while !exploder.ended {
-            let abuf = &mut data[cpos as usize..len];
+    for &compression in compressions {
+        let mut out_buf = vec![0; out.len()];
+        let read = decompress_single(compression, &mut in_buf, &mut out_buf)?;
+        out_buf.truncate(read);
 
-            let x = exploder.explode_block(abuf).unwrap();
+        in_buf = out_buf;
+    }
 
-            cpos += x.0 as u32;
+    decompress_single(*last_compression, &mut in_buf, out)
+}
 
-            let bf = x.1;
+fn decompress_single(compression: u8, data: &mut [u8], out: &mut [u8]) -> Result<usize, Error> {
+    match compression {
+        COMPRESSION_BZIP2 => {
+            let mut ouput = io::Cursor::new(out);
+            let mut reader = bzip2::DecoderReader::new(&*data);
+            io::copy(&mut reader, &mut ouput)?;
+            Ok(ouput.position() as usize)
+        }
+        COMPRESSION_ZLIB => {
+            let mut zlib = flate2::Decompress::new(true);
 
-            for (d, s) in out.iter_mut().zip(bf.iter()) {
-                *d = *s;
-                c += 1;
+            match zlib.decompress(data, out, flate2::FlushDecompress::None) {
+                Ok(_) => {}
+                Err(e) => return Err(Error::other(e)),
             }
-        }
 
-        return Ok(c);
+            Ok(zlib.total_out() as usize)
+        }
+        COMPRESSION_PKWARE => explode(data, out),
+        COMPRESSION_HUFFMAN => huffman::decompress(data, out),
+        COMPRESSION_SPARSE => Err(Error::other("Compression algorithm Sparse not supported")),
+        COMPRESSION_ADPCM_STEREO => adpcm::decompress(data, out, 2),
+        COMPRESSION_ADPCM_MONO => adpcm::decompress(data, out, 1),
+        _ => Err(Error::other("No compression type found")),
     }
+}
 
-    if compression_type & COMPRESSION_HUFFMAN != 0 {
-        return Err(Error::new(
-            ErrorKind::Other,
-            "Compression algorithm Huffman not supported",
-        ));
-    }
+/// Compresses a sector, the compressed data starts with the compression type
+pub fn compress(data: &[u8], compression: u8) -> Result<Vec<u8>, Error> {
+    let mut out = vec![compression];
 
-    if compression_type & COMPRESSION_SPARSE != 0 {
-        return Err(Error::new(
-            ErrorKind::Other,
-            "Compression algorithm Sparse not supported",
-        ));
-    }
-
-    if compression_type & COMPRESSION_ADPCM_STEREO != 0 {
-        return Err(Error::new(
-            ErrorKind::Other,
-            "Compression algorithm ADPCM Stereo not supported",
-        ));
+    match compression {
+        COMPRESSION_ZLIB => {
+            let mut zlib = flate2::write::ZlibEncoder::new(out, flate2::Compression::best());
+            zlib.write_all(data)?;
+            out = zlib.finish()?;
+        }
+        COMPRESSION_BZIP2 => out.extend(bzip2_encoder::compress(data)),
+        _ => return Err(Error::other("Compression type not supported for writing")),
     }
 
-    if compression_type & COMPRESSION_ADPCM_MONO != 0 {
-        return Err(Error::new(
-            ErrorKind::Other,
-            "Compression algorithm ADPCM Stereo not supported",
-        ));
-    }
+    Ok(out)
+}
 
-    if compression_type & COMPRESSION_LZMA != 0 {
+pub fn explode(data: &mut [u8], out: &mut [u8]) -> Result<usize, Error> {
+    // ascii mode is not implemented by the exploder
+    if data.first() != Some(&0) {
         return Err(Error::new(
-            ErrorKind::Other,
-            "Compression algorithm LZMA not supported",
+            ErrorKind::InvalidData,
+            "PKWARE compression mode not supported",
         ));
     }
 
-    Err(Error::new(ErrorKind::Other, "No compression type found"))
-}
-
-pub fn explode(data: &mut [u8], out: &mut [u8]) -> Result<usize, Error> {
     let mut exploder = Exploder::new(&DEFAULT_CODE_TABLE);
 
-    let mut cpos: u32 = 0;
-    let len = data.len();
+    let mut cpos: usize = 0;
     let mut c = 0;
 
     while !exploder.ended {
-        let abuf = &mut data[cpos as usize..len];
+        let (consumed, bf) = exploder
+            .explode_block(&data[cpos..])
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("PKWARE {e:?}")))?;
 
-        let x = exploder.explode_block(abuf).unwrap();
+        cpos += consumed;
 
-        cpos += x.0 as u32;
-
-        let bf = x.1;
-
-        for (d, s) in out.iter_mut().zip(bf.iter()) {
+        for (d, s) in out[c..].iter_mut().zip(bf.iter()) {
             *d = *s;
             c += 1;
         }
+
+        // the data ends before the end marker, no more symbols can be decoded
+        if consumed == 0 && !exploder.ended && !exploder.need_swap {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "PKWARE data ends without end marker",
+            ));
+        }
     }
 
     Ok(c)
@@ -186,7 +186,7 @@ pub fn decrypt(data: &mut [u8], mut seed: u32) {
     let mut it = 0;
     let mut ch;
 
-    while it < data.len() - 3 {
+    while it + 3 < data.len() {
         seed2 = seed2.wrapping_add(CRYPT_TABLE[(0x400 + (seed & 0xff)) as usize]);
         ch = LittleEndian::read_u32(&data[it..]) ^ (seed.wrapping_add(seed2));
         seed = ((!seed << 0x15).wrapping_add(0x11111111)) | (seed >> 0x0b);
@@ -200,9 +200,115 @@ pub fn decrypt(data: &mut [u8], mut seed: u32) {
     }
 }
 
+pub fn encrypt(data: &mut [u8], mut seed: u32) {
+    let mut seed2: u32 = 0xeeeeeeee;
+    let mut it = 0;
+    let mut ch;
+
+    while it + 3 < data.len() {
+        seed2 = seed2.wrapping_add(CRYPT_TABLE[(0x400 + (seed & 0xff)) as usize]);
+        ch = LittleEndian::read_u32(&data[it..]);
+        LittleEndian::write_u32(&mut data[it..], ch ^ (seed.wrapping_add(seed2)));
+        seed = ((!seed << 0x15).wrapping_add(0x11111111)) | (seed >> 0x0b);
+        seed2 = ch
+            .wrapping_add(seed2)
+            .wrapping_add(seed2 << 5)
+            .wrapping_add(3);
+
+        it += 4;
+    }
+}
+
+// Bob Jenkins' lookup3 hashlittle2, c and b are both seeds and results
+fn hash_little2(key: &[u8], c: &mut u32, b: &mut u32) {
+    fn mix(a: &mut u32, b: &mut u32, c: &mut u32) {
+        *a = a.wrapping_sub(*c) ^ c.rotate_left(4);
+        *c = c.wrapping_add(*b);
+        *b = b.wrapping_sub(*a) ^ a.rotate_left(6);
+        *a = a.wrapping_add(*c);
+        *c = c.wrapping_sub(*b) ^ b.rotate_left(8);
+        *b = b.wrapping_add(*a);
+        *a = a.wrapping_sub(*c) ^ c.rotate_left(16);
+        *c = c.wrapping_add(*b);
+        *b = b.wrapping_sub(*a) ^ a.rotate_left(19);
+        *a = a.wrapping_add(*c);
+        *c = c.wrapping_sub(*b) ^ b.rotate_left(4);
+        *b = b.wrapping_add(*a);
+    }
+
+    fn finalize(a: &mut u32, b: &mut u32, c: &mut u32) {
+        *c = (*c ^ *b).wrapping_sub(b.rotate_left(14));
+        *a = (*a ^ *c).wrapping_sub(c.rotate_left(11));
+        *b = (*b ^ *a).wrapping_sub(a.rotate_left(25));
+        *c = (*c ^ *b).wrapping_sub(b.rotate_left(16));
+        *a = (*a ^ *c).wrapping_sub(c.rotate_left(4));
+        *b = (*b ^ *a).wrapping_sub(a.rotate_left(14));
+        *c = (*c ^ *b).wrapping_sub(b.rotate_left(24));
+    }
+
+    fn read_word(bytes: &[u8]) -> u32 {
+        let mut word = [0; 4];
+        word[..bytes.len()].copy_from_slice(bytes);
+        u32::from_le_bytes(word)
+    }
+
+    let mut a = 0xdeadbeef_u32
+        .wrapping_add(key.len() as u32)
+        .wrapping_add(*c);
+    let mut b2 = a;
+    let mut c2 = a.wrapping_add(*b);
+
+    let mut rest = key;
+
+    while rest.len() > 12 {
+        a = a.wrapping_add(read_word(&rest[0..4]));
+        b2 = b2.wrapping_add(read_word(&rest[4..8]));
+        c2 = c2.wrapping_add(read_word(&rest[8..12]));
+        mix(&mut a, &mut b2, &mut c2);
+        rest = &rest[12..];
+    }
+
+    if rest.is_empty() {
+        *c = c2;
+        *b = b2;
+        return;
+    }
+
+    a = a.wrapping_add(read_word(&rest[..rest.len().min(4)]));
+    if rest.len() > 4 {
+        b2 = b2.wrapping_add(read_word(&rest[4..rest.len().min(8)]));
+    }
+    if rest.len() > 8 {
+        c2 = c2.wrapping_add(read_word(&rest[8..]));
+    }
+
+    finalize(&mut a, &mut b2, &mut c2);
+
+    *c = c2;
+    *b = b2;
+}
+
+// file name hash used by the HET table of v3 and v4 archives
+pub fn hash_string_jenkins(key: &str) -> u64 {
+    let normalized: Vec<u8> = key
+        .bytes()
+        .map(|ch| match ch {
+            b'/' => b'\\',
+            ch => ch.to_ascii_lowercase(),
+        })
+        .collect();
+
+    let mut secondary_hash = 2;
+    let mut primary_hash = 1;
+
+    hash_little2(&normalized, &mut secondary_hash, &mut primary_hash);
+
+    (u64::from(primary_hash) << 32) | u64::from(secondary_hash)
+}
+
 #[cfg(test)]
 mod test {
-    use super::hash_string;
+    use super::{decrypt, encrypt, hash_little2, hash_string};
 
     #[test]
     fn hash() {
@@ -212,4 +318,31 @@ mod test {
         assert_eq!(0xF4E6C69D, hash_string("arr\\units.dat", 0));
         assert_eq!(0xA26067F3, hash_string("unit\\neutral\\acritter.grp", 0));
     }
+
+    #[test]
+    fn hash_little2_reference_values() {
+        let (mut c, mut b) = (0, 0);
+        hash_little2(b"", &mut c, &mut b);
+        assert_eq!((c, b), (0xdeadbeef, 0xdeadbeef));
+
+        let (mut c, mut b) = (0, 0);
+        hash_little2(b"Four score and seven years ago", &mut c, &mut b);
+        assert_eq!((c, b), (0x17770551, 0xce7226e6));
+
+        let (mut c, mut b) = (1, 0);
+        hash_little2(b"Four score and seven years ago", &mut c, &mut b);
+        assert_eq!((c, b), (0xcd628161, 0x6cbea4b3));
+    }
+
+    #[test]
+    fn encrypt_decrypt() {
+        let data: Vec<u8> = (0..32).collect();
+        let mut buf = data.clone();
+
+        encrypt(&mut buf, 0xEC83B3A3);
+        assert_ne!(buf, data);
+
+        decrypt(&mut buf, 0xEC83B3A3);
+        assert_eq!(buf, data);
+    }
 }
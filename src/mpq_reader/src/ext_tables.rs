@@ -0,0 +1,414 @@
+use crate::compression::decompress;
+use crate::crypt::{decrypt, hash_string_jenkins};
+use byteorder::{ByteOrder, LittleEndian};
+use std::io::{Error, ErrorKind};
+
+const ID_HET: &[u8] = b"HET\x1A";
+const ID_BET: &[u8] = b"BET\x1A";
+
+const EXT_HEADER_SIZE: usize = 12;
+const HET_HEADER_SIZE: usize = 32;
+const BET_HEADER_SIZE: usize = 76;
+
+const HET_ENTRY_FREE: u8 = 0x00;
+
+// Little endian bit array as used by the HET and BET tables
+fn read_bits(data: &[u8], position: u64, count: u32) -> Result<u64, Error> {
+    let end = position + u64::from(count);
+
+    if end > data.len() as u64 * 8 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Bit array read past end of table",
+        ));
+    }
+
+    let mut value = 0;
+
+    for bit in 0..u64::from(count) {
+        let index = position + bit;
+
+        if data[(index / 8) as usize] & (1 << (index % 8)) != 0 {
+            value |= 1 << bit;
+        }
+    }
+
+    Ok(value)
+}
+
+fn take<'a>(data: &mut &'a [u8], size: usize) -> Result<&'a [u8], Error> {
+    if data.len() < size {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "Table is truncated"));
+    }
+
+    let (head, tail) = data.split_at(size);
+    *data = tail;
+
+    Ok(head)
+}
+
+/// Decrypts and decompresses the data of an extended table,
+/// returning the table contents that follow the extended header.
+fn load_ext_table(mut buf: Vec<u8>, signature: &[u8], key: u32) -> Result<Vec<u8>, Error> {
+    if buf.len() < EXT_HEADER_SIZE || !buf.starts_with(signature) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Extended table signature mismatch",
+        ));
+    }
+
+    let data_size = LittleEndian::read_u32(&buf[8..]) as usize;
+    let data = &mut buf[EXT_HEADER_SIZE..];
+
+    if data.len() >= 4 {
+        decrypt(data, key);
+    }
+
+    // table is compressed
+    if data_size > data.len() {
+        let mut out = vec![0; data_size];
+        let read = decompress(data, &mut out)?;
+        out.truncate(read);
+
+        Ok(out)
+    } else {
+        Ok(data[..data_size].to_vec())
+    }
+}
+
+/// Hash table of v3 and v4 archives, maps file names to indices into the BET table
+#[derive(Debug, Clone)]
+pub struct HetTable {
+    total_count: u32,
+    name_hash_bit_size: u32,
+    index_size_total: u32,
+    index_size: u32,
+    name_hashes: Vec<u8>,
+    file_indices: Vec<u8>,
+}
+
+impl HetTable {
+    pub fn new(buf: Vec<u8>, key: u32) -> Result<HetTable, Error> {
+        let data = load_ext_table(buf, ID_HET, key)?;
+        let mut data = &data[..];
+
+        let header = take(&mut data, HET_HEADER_SIZE)?;
+
+        let total_count = LittleEndian::read_u32(&header[0x08..]);
+        let name_hash_bit_size = LittleEndian::read_u32(&header[0x0C..]);
+        let index_size_total = LittleEndian::read_u32(&header[0x10..]);
+        let index_size = LittleEndian::read_u32(&header[0x18..]);
+        let index_table_size = LittleEndian::read_u32(&header[0x1C..]);
+
+        if total_count == 0
+            || !(8..=64).contains(&name_hash_bit_size)
+            || index_size > 64
+            || index_size > index_size_total
+        {
+            return Err(Error::new(ErrorKind::InvalidData, "Invalid HET table"));
+        }
+
+        Ok(HetTable {
+            total_count,
+            name_hash_bit_size,
+            index_size_total,
+            index_size,
+            name_hashes: take(&mut data, total_count as usize)?.to_vec(),
+            file_indices: take(&mut data, index_table_size as usize)?.to_vec(),
+        })
+    }
+
+    /// Looks up the index of a file in the BET table
+    pub fn find(&self, filename: &str, bet_table: &BetTable) -> Option<usize> {
+        let bits = self.name_hash_bit_size;
+        let and_mask = if bits == 64 {
+            u64::MAX
+        } else {
+            (1 << bits) - 1
+        };
+        let or_mask = 1 << (bits - 1);
+
+        let file_name_hash = (hash_string_jenkins(filename) & and_mask) | or_mask;
+        let name_hash1 = (file_name_hash >> (bits - 8)) as u8;
+        let name_hash2 = file_name_hash & (and_mask >> 8);
+
+        let start_index = (file_name_hash % u64::from(self.total_count)) as usize;
+        let mut index = start_index;
+
+        loop {
+            match self.name_hashes[index] {
+                HET_ENTRY_FREE => return None,
+                hash if hash == name_hash1 => {
+                    let position = index as u64 * u64::from(self.index_size_total);
+
+                    if let Ok(file_index) = read_bits(&self.file_indices, position, self.index_size)
+                    {
+                        let file_index = file_index as usize;
+
+                        if bet_table.name_hash2(file_index) == Some(name_hash2) {
+                            return Some(file_index);
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            index = (index + 1) % self.total_count as usize;
+
+            if index == start_index {
+                return None;
+            }
+        }
+    }
+}
+
+/// A file entry of the BET table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BetEntry {
+    pub offset: u64,
+    pub unpacked_size: u64,
+    pub packed_size: u64,
+    pub flags: u32,
+}
+
+/// Block table of v3 and v4 archives, stores the file entries as bit packed records
+#[derive(Debug, Clone)]
+pub struct BetTable {
+    pub entries: Vec<BetEntry>,
+    name_hash2_bit_total: u32,
+    name_hash2_bit_count: u32,
+    name_hashes: Vec<u8>,
+}
+
+impl BetTable {
+    pub fn new(buf: Vec<u8>, key: u32) -> Result<BetTable, Error> {
+        let data = load_ext_table(buf, ID_BET, key)?;
+        let mut data = &data[..];
+
+        let header = take(&mut data, BET_HEADER_SIZE)?;
+        let field = |index: usize| LittleEndian::read_u32(&header[index * 4..]);
+
+        let entry_count = field(1);
+        let table_entry_size = field(3);
+        let bit_indices = [field(4), field(5), field(6), field(7)];
+        let bit_counts = [field(9), field(10), field(11), field(12)];
+        let name_hash2_bit_total = field(14);
+        let name_hash2_bit_count = field(16);
+        let name_hash_array_size = field(17);
+        let flag_count = field(18);
+
+        if bit_counts.iter().any(|&count| count > 64) || name_hash2_bit_count > 64 {
+            return Err(Error::new(ErrorKind::InvalidData, "Invalid BET table"));
+        }
+
+        let flags: Vec<u32> = take(&mut data, flag_count as usize * 4)?
+            .chunks_exact(4)
+            .map(LittleEndian::read_u32)
+            .collect();
+
+        let file_table_size = (u64::from(entry_count) * u64::from(table_entry_size)).div_ceil(8);
+        let file_table = take(&mut data, file_table_size as usize)?;
+
+        let mut entries = Vec::with_capacity(entry_count as usize);
+
+        for i in 0..u64::from(entry_count) {
+            let record = i * u64::from(table_entry_size);
+            let read = |field: usize| {
+                read_bits(
+                    file_table,
+                    record + u64::from(bit_indices[field]),
+                    bit_counts[field],
+                )
+            };
+
+            let flag_index = read(3)? as usize;
+
+            entries.push(BetEntry {
+                offset: read(0)?,
+                unpacked_size: read(1)?,
+                packed_size: read(2)?,
+                flags: flags.get(flag_index).copied().unwrap_or(0),
+            });
+        }
+
+        Ok(BetTable {
+            entries,
+            name_hash2_bit_total,
+            name_hash2_bit_count,
+            name_hashes: take(&mut data, name_hash_array_size as usize)?.to_vec(),
+        })
+    }
+
+    fn name_hash2(&self, index: usize) -> Option<u64> {
+        if index >= self.entries.len() {
+            return None;
+        }
+
+        read_bits(
+            &self.name_hashes,
+            index as u64 * u64::from(self.name_hash2_bit_total),
+            self.name_hash2_bit_count,
+        )
+        .ok()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{BetEntry, BetTable, HetTable};
+    use crate::crypt::{encrypt, hash_string, hash_string_jenkins};
+
+    fn write_bits(data: &mut [u8], position: u64, value: u64, count: u32) {
+        for bit in 0..u64::from(count) {
+            let index = position + bit;
+
+            if value & (1 << bit) != 0 {
+                data[(index / 8) as usize] |= 1 << (index % 8);
+            }
+        }
+    }
+
+    fn ext_table(signature: &[u8], data: Vec<u8>, key: u32) -> Vec<u8> {
+        let mut table = signature.to_vec();
+        table.extend(1_u32.to_le_bytes());
+        table.extend((data.len() as u32).to_le_bytes());
+        table.extend(data);
+
+        encrypt(&mut table[12..], key);
+
+        table
+    }
+
+    /// Builds an encrypted HET table with 64 bit name hashes,
+    /// the BET index of each file is its position in `names`
+    pub(crate) fn build_het(names: &[&str]) -> Vec<u8> {
+        let total_count = names.len() as u32 * 2;
+        let index_size = 8;
+
+        let mut name_hashes = vec![0; total_count as usize];
+        let mut file_indices = vec![0; total_count as usize];
+
+        for (file_index, name) in names.iter().enumerate() {
+            let file_name_hash = hash_string_jenkins(name) | (1 << 63);
+            let mut index = (file_name_hash % u64::from(total_count)) as usize;
+
+            while name_hashes[index] != 0 {
+                index = (index + 1) % total_count as usize;
+            }
+
+            name_hashes[index] = (file_name_hash >> 56) as u8;
+            write_bits(
+                &mut file_indices,
+                index as u64 * index_size,
+                file_index as u64,
+                index_size as u32,
+            );
+        }
+
+        let mut data = Vec::new();
+        for field in [
+            0,
+            names.len() as u32,
+            total_count,
+            64,
+            index_size as u32,
+            0,
+            index_size as u32,
+            file_indices.len() as u32,
+        ] {
+            data.extend(field.to_le_bytes());
+        }
+        data.extend(name_hashes);
+        data.extend(file_indices);
+
+        ext_table(b"HET\x1A", data, hash_string("(hash table)", 0x300))
+    }
+
+    /// Builds an encrypted BET table with 32 bit offsets and sizes
+    pub(crate) fn build_bet(names: &[&str], entries: &[BetEntry]) -> Vec<u8> {
+        let entry_size = 32 * 3 + 8;
+        let mut file_table = vec![0; (entries.len() * entry_size).div_ceil(8)];
+        let mut name_hashes = vec![0; names.len() * 7];
+
+        for (i, entry) in entries.iter().enumerate() {
+            let record = (i * entry_size) as u64;
+
+            write_bits(&mut file_table, record, entry.offset, 32);
+            write_bits(&mut file_table, record + 32, entry.unpacked_size, 32);
+            write_bits(&mut file_table, record + 64, entry.packed_size, 32);
+            write_bits(&mut file_table, record + 96, i as u64, 8);
+        }
+
+        for (i, name) in names.iter().enumerate() {
+            let name_hash2 = hash_string_jenkins(name) & (u64::MAX >> 8);
+            write_bits(&mut name_hashes, i as u64 * 56, name_hash2, 56);
+        }
+
+        let mut data = Vec::new();
+        for field in [
+            0,
+            entries.len() as u32,
+            0x10,
+            entry_size as u32,
+            0,
+            32,
+            64,
+            96,
+            0,
+            32,
+            32,
+            32,
+            8,
+            0,
+            56,
+            0,
+            56,
+            name_hashes.len() as u32,
+            entries.len() as u32,
+        ] {
+            data.extend(field.to_le_bytes());
+        }
+        for entry in entries {
+            data.extend(entry.flags.to_le_bytes());
+        }
+        data.extend(file_table);
+        data.extend(name_hashes);
+
+        ext_table(b"BET\x1A", data, hash_string("(block table)", 0x300))
+    }
+
+    #[test]
+    fn find_file_in_het_table() {
+        let names = [
+            "(listfile)",
+            "data\\global\\excel\\levels.txt",
+            "Data/Local/Font.dc6",
+        ];
+        let entries: Vec<BetEntry> = (0..names.len() as u64)
+            .map(|i| BetEntry {
+                offset: 0x20 + i * 0x100,
+                unpacked_size: 0x80 + i,
+                packed_size: 0x40 + i,
+                flags: 0x80000000 | i as u32,
+            })
+            .collect();
+
+        let het = HetTable::new(build_het(&names), hash_string("(hash table)", 0x300)).unwrap();
+        let bet = BetTable::new(
+            build_bet(&names, &entries),
+            hash_string("(block table)", 0x300),
+        )
+        .unwrap();
+
+        assert_eq!(bet.entries, entries);
+
+        for (i, name) in names.iter().enumerate() {
+            assert_eq!(het.find(name, &bet), Some(i));
+        }
+
+        // lookups ignore case and path separators
+        assert_eq!(het.find("DATA\\GLOBAL\\EXCEL\\LEVELS.TXT", &bet), Some(1));
+        assert_eq!(het.find("data/local/font.dc6", &bet), Some(2));
+        assert_eq!(het.find("data\\global\\excel\\missiles.txt", &bet), None);
+    }
+}
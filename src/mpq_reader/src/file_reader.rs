@@ -0,0 +1,161 @@
+use crate::archive::{Archive, File};
+use std::io::{self, Error, ErrorKind, Read, Seek, SeekFrom};
+
+/// Reads a file of an archive as a stream, decoding one sector at a time.
+///
+/// Only the sector at the current position is held in memory, files stored as a single
+/// unit are decoded completely on the first read. The sector checksums are checked, the
+/// checksums of `(attributes)` only by [`File::read`] with verification enabled.
+///
+/// ```no_run
+/// use std::io::Read;
+///
+/// let archive = mpq_reader::Archive::open("d2data.mpq").unwrap();
+/// let mut reader = archive.open_reader("data\\global\\excel\\levels.txt").unwrap();
+///
+/// let mut header = [0; 4];
+/// reader.read_exact(&mut header).unwrap();
+/// ```
+pub struct FileReader<'a> {
+    archive: &'a Archive,
+    file: File,
+    position: u64,
+    // index and decoded contents of the unit at the position
+    unit: Option<(usize, Vec<u8>)>,
+}
+
+impl<'a> FileReader<'a> {
+    pub(crate) fn new(archive: &'a Archive, file: File) -> FileReader<'a> {
+        FileReader {
+            archive,
+            file,
+            position: 0,
+            unit: None,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        u64::from(self.file.size())
+    }
+
+    fn load_unit(&mut self, index: usize) -> Result<&[u8], Error> {
+        if self.unit.as_ref().map(|(i, _)| *i) != Some(index) {
+            let unit_size = self.file.unit_size(self.archive);
+            let mut unit = vec![0; unit_size];
+
+            let read = self.file.read_unit(self.archive, index, &mut unit)?;
+            unit.truncate(read);
+
+            self.unit = Some((index, unit));
+        }
+
+        Ok(&self.unit.as_ref().unwrap().1)
+    }
+}
+
+impl Read for FileReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.size() || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let unit_size = self.file.unit_size(self.archive) as u64;
+        let index = (self.position / unit_size) as usize;
+        let offset = (self.position % unit_size) as usize;
+
+        let unit = self.load_unit(index)?;
+
+        if offset >= unit.len() {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "Sector is shorter than expected",
+            ));
+        }
+
+        let count = buf.len().min(unit.len() - offset);
+        buf[..count].copy_from_slice(&unit[offset..offset + count]);
+
+        self.position += count as u64;
+
+        Ok(count)
+    }
+}
+
+impl Seek for FileReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        match position {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Seek to a negative position",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ArchiveBuilder, Compression, FileOptions};
+    use std::fs;
+    use std::io::{Read, Seek, SeekFrom};
+
+    #[test]
+    fn read_and_seek_across_sectors() {
+        let data: Vec<u8> = (0..5000_u32).flat_map(|i| (i * 7).to_le_bytes()).collect();
+        let options = [
+            FileOptions::default(),
+            FileOptions {
+                compression: Compression::None,
+                encrypted: true,
+                ..FileOptions::default()
+            },
+            FileOptions {
+                compression: Compression::Pkware,
+                single_unit: true,
+                ..FileOptions::default()
+            },
+        ];
+
+        let mut builder = ArchiveBuilder::new();
+        builder.sector_size_shift(0);
+        for (i, options) in options.iter().enumerate() {
+            builder.add_file(&format!("data\\file{i}.bin"), &data, *options);
+        }
+
+        let path = std::env::temp_dir().join(format!("file_reader_{}.mpq", std::process::id()));
+        builder.write(&path).unwrap();
+
+        let archive = crate::Archive::open(&path).unwrap();
+
+        for i in 0..options.len() {
+            let mut reader = archive.open_reader(&format!("data\\file{i}.bin")).unwrap();
+
+            let mut all = Vec::new();
+            reader.read_to_end(&mut all).unwrap();
+            assert_eq!(all, data);
+
+            // a range crossing the border of the first two sectors
+            let mut range = [0; 100];
+            reader.seek(SeekFrom::Start(470)).unwrap();
+            reader.read_exact(&mut range).unwrap();
+            assert_eq!(range, data[470..570]);
+
+            reader.seek(SeekFrom::End(-10)).unwrap();
+            reader.read_exact(&mut range[..10]).unwrap();
+            assert_eq!(range[..10], data[data.len() - 10..]);
+
+            assert!(reader.seek(SeekFrom::Current(-100_000)).is_err());
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+}
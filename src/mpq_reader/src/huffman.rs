@@ -0,0 +1,775 @@
+use std::io::{Error, ErrorKind};
+
+const END_OF_STREAM: u16 = 0x100;
+const NEW_VALUE: u16 = 0x101;
+const NUM_VALUES: usize = 0x102;
+const MAX_ITEMS: usize = 0x203;
+
+// Index of the sentinel item. Its `next` is the first (heaviest) item of the list and its `prev`
+// is the last (lightest) item of the list.
+const LIST_HEAD: usize = 0;
+
+// Initial weights of the bytes, one table per huffman compression type. The tables are the ones
+// of Storm, the last two entries are the end of stream and new value items.
+//
+// Compression type 0x00, which is used for generic data.
+// Bytes 0x00 and 0x01 have weight 0x0A, every other byte has weight 0x01.
+const WEIGHTS_TYPE_0: [u8; NUM_VALUES] = {
+    let mut weights = [1; NUM_VALUES];
+    weights[0x00] = 0x0A;
+    weights[0x01] = 0x0A;
+    weights[0x100] = 0;
+    weights[0x101] = 0;
+    weights
+};
+
+// Compression type 0x01, used for binary data
+const WEIGHTS_TYPE_1: [u8; NUM_VALUES] = [
+    0x54, 0x16, 0x16, 0x0D, 0x0C, 0x08, 0x06, 0x05, 0x06, 0x05, 0x06, 0x03, 0x04, 0x04, 0x03, 0x05,
+    0x0E, 0x0B, 0x14, 0x13, 0x13, 0x09, 0x0B, 0x06, 0x05, 0x04, 0x03, 0x02, 0x03, 0x02, 0x02, 0x02,
+    0x0D, 0x07, 0x09, 0x06, 0x06, 0x04, 0x03, 0x02, 0x04, 0x03, 0x03, 0x03, 0x03, 0x03, 0x02, 0x02,
+    0x09, 0x06, 0x04, 0x04, 0x04, 0x04, 0x03, 0x02, 0x03, 0x02, 0x02, 0x02, 0x02, 0x03, 0x02, 0x04,
+    0x08, 0x03, 0x04, 0x07, 0x09, 0x05, 0x03, 0x03, 0x03, 0x03, 0x02, 0x02, 0x02, 0x03, 0x02, 0x02,
+    0x03, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x01, 0x01, 0x01, 0x02, 0x01, 0x02, 0x02,
+    0x06, 0x0A, 0x08, 0x08, 0x06, 0x07, 0x04, 0x03, 0x04, 0x04, 0x02, 0x02, 0x04, 0x02, 0x03, 0x03,
+    0x04, 0x03, 0x07, 0x07, 0x09, 0x06, 0x04, 0x03, 0x03, 0x02, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02,
+    0x0A, 0x02, 0x02, 0x03, 0x02, 0x02, 0x01, 0x01, 0x02, 0x02, 0x02, 0x06, 0x03, 0x05, 0x02, 0x03,
+    0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x03, 0x01, 0x01, 0x01,
+    0x02, 0x01, 0x02, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x04, 0x04,
+    0x04, 0x07, 0x09, 0x08, 0x0C, 0x02, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x03,
+    0x04, 0x01, 0x02, 0x04, 0x05, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x04, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x03, 0x01, 0x01, 0x01,
+    0x00, 0x00,
+];
+
+// Compression type 0x02, used for text
+const WEIGHTS_TYPE_2: [u8; NUM_VALUES] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x02, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x29, 0x07, 0x01, 0x02, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x00, 0x00, 0x03, 0x07, 0x04, 0x01,
+    0x03, 0x05, 0x04, 0x02, 0x02, 0x02, 0x01, 0x01, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01,
+    0x01, 0x05, 0x02, 0x04, 0x03, 0x04, 0x03, 0x02, 0x03, 0x06, 0x01, 0x01, 0x04, 0x03, 0x03, 0x03,
+    0x02, 0x01, 0x04, 0x07, 0x06, 0x02, 0x01, 0x02, 0x01, 0x01, 0x01, 0x01, 0x00, 0x01, 0x00, 0x00,
+    0x00, 0x15, 0x04, 0x09, 0x09, 0x23, 0x05, 0x05, 0x0A, 0x13, 0x01, 0x02, 0x0B, 0x0A, 0x13, 0x0F,
+    0x04, 0x00, 0x10, 0x10, 0x19, 0x07, 0x02, 0x05, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00,
+];
+
+// Compression type 0x03, used for binary data
+const WEIGHTS_TYPE_3: [u8; NUM_VALUES] = [
+    0xFF, 0x0B, 0x07, 0x05, 0x0B, 0x02, 0x02, 0x02, 0x06, 0x02, 0x02, 0x01, 0x04, 0x02, 0x01, 0x03,
+    0x09, 0x01, 0x01, 0x01, 0x03, 0x04, 0x01, 0x01, 0x02, 0x01, 0x01, 0x01, 0x02, 0x01, 0x01, 0x01,
+    0x05, 0x01, 0x01, 0x01, 0x0D, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x01, 0x01, 0x01,
+    0x0A, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x02, 0x02, 0x01, 0x01, 0x03, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x0A, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x02, 0x02, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x11,
+    0x00, 0x00,
+];
+
+// Compression type 0x04, only the lowest byte values are known up front
+const WEIGHTS_TYPE_4: [u8; NUM_VALUES] = [
+    0xFF, 0xFB, 0x98, 0x9A, 0x84, 0x85, 0x63, 0x64, 0x3E, 0x3E, 0x22, 0x22, 0x13, 0x13, 0x18, 0x17,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00,
+];
+
+// Compression type 0x05, only the lowest byte values are known up front
+const WEIGHTS_TYPE_5: [u8; NUM_VALUES] = [
+    0xFF, 0xF1, 0x9D, 0x9E, 0x9A, 0x9B, 0x9A, 0x97, 0x93, 0x93, 0x8C, 0x8E, 0x86, 0x88, 0x80, 0x82,
+    0x7C, 0x7C, 0x72, 0x73, 0x69, 0x6B, 0x5F, 0x60, 0x55, 0x56, 0x4A, 0x4B, 0x40, 0x41, 0x37, 0x37,
+    0x2F, 0x2F, 0x27, 0x27, 0x21, 0x21, 0x1B, 0x1C, 0x17, 0x17, 0x13, 0x13, 0x10, 0x10, 0x0D, 0x0D,
+    0x0B, 0x0B, 0x09, 0x09, 0x08, 0x08, 0x07, 0x07, 0x06, 0x05, 0x05, 0x04, 0x04, 0x04, 0x19, 0x18,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00,
+];
+
+// Compression type 0x06, used for ADPCM compressed sectors. The samples are in 0x00..0x10,
+// the negative samples in 0x40..0x50 and the step commands are 0x80 and 0x81.
+const WEIGHTS_TYPE_6: [u8; NUM_VALUES] = [
+    0xC3, 0xCB, 0xF5, 0x41, 0xFF, 0x7B, 0xF7, 0x21, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xBF, 0xCC, 0xF2, 0x40, 0xFD, 0x7C, 0xF7, 0x22, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x7A, 0x46, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00,
+];
+
+// Compression type 0x07, used for ADPCM compressed sectors
+const WEIGHTS_TYPE_7: [u8; NUM_VALUES] = [
+    0xC3, 0xD9, 0xEF, 0x3D, 0xF9, 0x7C, 0xE9, 0x1E, 0xFD, 0xAB, 0xF1, 0x2C, 0xFC, 0x5B, 0xFE, 0x17,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xBD, 0xD9, 0xEC, 0x3D, 0xF5, 0x7D, 0xE8, 0x1D, 0xFB, 0xAE, 0xF0, 0x2C, 0xFB, 0x5C, 0xFF, 0x18,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x70, 0x6C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00,
+];
+
+// Compression type 0x08, used for ADPCM compressed sectors
+const WEIGHTS_TYPE_8: [u8; NUM_VALUES] = [
+    0xBA, 0xC5, 0xDA, 0x33, 0xE3, 0x6D, 0xD8, 0x18, 0xE5, 0x94, 0xDA, 0x23, 0xDF, 0x4A, 0xD1, 0x10,
+    0xEE, 0xAF, 0xE4, 0x2C, 0xEA, 0x5A, 0xDE, 0x15, 0xF4, 0x87, 0xE9, 0x21, 0xF6, 0x43, 0xFC, 0x12,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xB0, 0xC7, 0xD8, 0x33, 0xE3, 0x6B, 0xD6, 0x18, 0xE7, 0x95, 0xD8, 0x23, 0xDB, 0x49, 0xD0, 0x11,
+    0xE9, 0xB2, 0xE2, 0x2B, 0xE8, 0x5C, 0xDD, 0x15, 0xF1, 0x87, 0xE7, 0x20, 0xF7, 0x44, 0xFF, 0x13,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x5F, 0x9E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00,
+];
+
+fn get_weights(compression_type: u8) -> Result<&'static [u8; NUM_VALUES], Error> {
+    match compression_type {
+        0x00 => Ok(&WEIGHTS_TYPE_0),
+        0x01 => Ok(&WEIGHTS_TYPE_1),
+        0x02 => Ok(&WEIGHTS_TYPE_2),
+        0x03 => Ok(&WEIGHTS_TYPE_3),
+        0x04 => Ok(&WEIGHTS_TYPE_4),
+        0x05 => Ok(&WEIGHTS_TYPE_5),
+        0x06 => Ok(&WEIGHTS_TYPE_6),
+        0x07 => Ok(&WEIGHTS_TYPE_7),
+        0x08 => Ok(&WEIGHTS_TYPE_8),
+        _ => Err(Error::other(format!(
+            "Huffman compression type {compression_type} not supported"
+        ))),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Item {
+    /// next item in the list, which has a lower or equal weight
+    next: usize,
+    /// previous item in the list, which has a higher or equal weight
+    prev: usize,
+    /// decompressed value of a leaf
+    value: u16,
+    weight: u32,
+    parent: Option<usize>,
+    /// the child with the lower weight. The other child is always the item just before it
+    child_lo: Option<usize>,
+    linked: bool,
+}
+
+impl Item {
+    fn new(value: u16, weight: u32) -> Self {
+        Self {
+            next: LIST_HEAD,
+            prev: LIST_HEAD,
+            value,
+            weight,
+            parent: None,
+            child_lo: None,
+            linked: false,
+        }
+    }
+}
+
+/// The adaptive huffman tree used by Storm.
+///
+/// All items are kept in a list which is sorted by weight in descending order.
+/// The two children of an item are always next to each other in the list, which is what allows
+/// the tree to be rebalanced by swapping items in the list.
+struct HuffmanTree {
+    items: Vec<Item>,
+    items_by_value: [Option<usize>; NUM_VALUES],
+    max_weight: u32,
+}
+
+impl HuffmanTree {
+    fn new(weights: &[u8; NUM_VALUES]) -> Result<Self, Error> {
+        let mut tree = Self {
+            items: vec![Item::new(0, 0)],
+            items_by_value: [None; NUM_VALUES],
+            max_weight: 0,
+        };
+
+        for (value, &weight) in weights.iter().enumerate().take(0x100) {
+            if weight != 0 {
+                let item = tree.create_item(value as u16, u32::from(weight))?;
+                tree.sort_new_item(item);
+                tree.items_by_value[value] = Some(item);
+            }
+        }
+
+        for value in [END_OF_STREAM, NEW_VALUE] {
+            let item = tree.create_item(value, 1)?;
+            tree.insert_before(item, LIST_HEAD);
+            tree.items_by_value[value as usize] = Some(item);
+        }
+
+        let mut child_lo = tree.last();
+
+        while child_lo != LIST_HEAD {
+            let child_hi = tree.items[child_lo].prev;
+            if child_hi == LIST_HEAD {
+                break;
+            }
+
+            let weight = tree.items[child_hi].weight + tree.items[child_lo].weight;
+            let parent = tree.create_item(0, weight)?;
+
+            tree.items[child_lo].parent = Some(parent);
+            tree.items[child_hi].parent = Some(parent);
+            tree.items[parent].child_lo = Some(child_lo);
+
+            tree.sort_new_item(parent);
+
+            child_lo = tree.items[child_hi].prev;
+        }
+
+        Ok(tree)
+    }
+
+    fn first(&self) -> usize {
+        self.items[LIST_HEAD].next
+    }
+
+    fn last(&self) -> usize {
+        self.items[LIST_HEAD].prev
+    }
+
+    fn create_item(&mut self, value: u16, weight: u32) -> Result<usize, Error> {
+        // The sentinel is not counted
+        if self.items.len() > MAX_ITEMS {
+            return Err(Error::other("Huffman tree is full"));
+        }
+
+        self.items.push(Item::new(value, weight));
+
+        Ok(self.items.len() - 1)
+    }
+
+    // Items with the highest weight so far are put at the top of the list.
+    // All other items are put after the last item with a higher or equal weight.
+    fn sort_new_item(&mut self, item: usize) {
+        let weight = self.items[item].weight;
+
+        if weight >= self.max_weight {
+            self.max_weight = weight;
+            self.insert_after(item, LIST_HEAD);
+        } else {
+            let position = self.find_higher_or_equal_item(self.last(), weight);
+            self.insert_after(item, position);
+        }
+    }
+
+    // Walks towards the top of the list and returns the first item with a higher or equal weight
+    fn find_higher_or_equal_item(&self, mut item: usize, weight: u32) -> usize {
+        while item != LIST_HEAD {
+            if self.items[item].weight >= weight {
+                return item;
+            }
+
+            item = self.items[item].prev;
+        }
+
+        LIST_HEAD
+    }
+
+    fn unlink(&mut self, item: usize) {
+        let Item {
+            next, prev, linked, ..
+        } = self.items[item];
+
+        if linked {
+            self.items[prev].next = next;
+            self.items[next].prev = prev;
+        }
+    }
+
+    fn insert_after(&mut self, item: usize, position: usize) {
+        self.unlink(item);
+
+        let next = self.items[position].next;
+
+        self.items[item].prev = position;
+        self.items[item].next = next;
+        self.items[item].linked = true;
+        self.items[next].prev = item;
+        self.items[position].next = item;
+    }
+
+    fn insert_before(&mut self, item: usize, position: usize) {
+        self.unlink(item);
+
+        let prev = self.items[position].prev;
+
+        self.items[item].next = position;
+        self.items[item].prev = prev;
+        self.items[item].linked = true;
+        self.items[prev].next = item;
+        self.items[position].prev = item;
+    }
+
+    fn decode_value(&self, bits: &mut BitReader) -> Result<u16, Error> {
+        let mut item = self.first();
+
+        while let Some(child_lo) = self.items[item].child_lo {
+            item = if bits.read_bit()? {
+                self.items[child_lo].prev
+            } else {
+                child_lo
+            };
+        }
+
+        Ok(self.items[item].value)
+    }
+
+    // Increments the weight of the item and all its parents.
+    // An item whose weight is no longer sorted is swapped with the first item of its old weight.
+    fn increment_weight(&mut self, mut item: usize) {
+        loop {
+            self.items[item].weight += 1;
+
+            let weight = self.items[item].weight;
+            let higher_item = self.find_higher_or_equal_item(self.items[item].prev, weight);
+            let leader = self.items[higher_item].next;
+
+            if leader != item {
+                self.swap_items(item, leader, higher_item);
+            }
+
+            match self.items[item].parent {
+                Some(parent) => item = parent,
+                None => break,
+            }
+        }
+    }
+
+    fn swap_items(&mut self, item: usize, leader: usize, higher_item: usize) {
+        self.insert_before(leader, item);
+        self.insert_after(item, higher_item);
+
+        let item_parent = self.items[item].parent;
+        let leader_parent = self.items[leader].parent;
+
+        let item_was_child_lo =
+            item_parent.is_some_and(|parent| self.items[parent].child_lo == Some(item));
+        let leader_was_child_lo =
+            leader_parent.is_some_and(|parent| self.items[parent].child_lo == Some(leader));
+
+        // The lower child is the one at the later position in the list, so it follows the position
+        if let (Some(parent), true) = (item_parent, item_was_child_lo) {
+            self.items[parent].child_lo = Some(leader);
+        }
+
+        if let (Some(parent), true) = (leader_parent, leader_was_child_lo) {
+            self.items[parent].child_lo = Some(item);
+        }
+
+        self.items[item].parent = leader_parent;
+        self.items[leader].parent = item_parent;
+    }
+
+    // Splits the last item into the old value and the new value, where the new value starts with
+    // a weight of zero
+    fn insert_value(&mut self, value: u16) -> Result<(), Error> {
+        let last = self.last();
+        let last_value = self.items[last].value;
+        let last_weight = self.items[last].weight;
+
+        let child_hi = self.create_item(last_value, last_weight)?;
+        self.insert_before(child_hi, LIST_HEAD);
+        self.items[child_hi].parent = Some(last);
+        self.items_by_value[last_value as usize] = Some(child_hi);
+
+        let child_lo = self.create_item(value, 0)?;
+        self.insert_before(child_lo, LIST_HEAD);
+        self.items[child_lo].parent = Some(last);
+        self.items[last].child_lo = Some(child_lo);
+        self.items_by_value[value as usize] = Some(child_lo);
+
+        self.increment_weight(child_lo);
+
+        Ok(())
+    }
+
+    fn increment_value_weight(&mut self, value: u16) {
+        if let Some(item) = self.items_by_value[value as usize] {
+            self.increment_weight(item);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    byte_offset: usize,
+    bit_offset: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            byte_offset: 0,
+            bit_offset: 0,
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error> {
+        let byte = self.data.get(self.byte_offset).ok_or_else(|| {
+            Error::new(
+                ErrorKind::UnexpectedEof,
+                "Huffman stream ended unexpectedly",
+            )
+        })?;
+
+        let bit = byte & (1 << self.bit_offset) != 0;
+
+        self.bit_offset += 1;
+        if self.bit_offset == 8 {
+            self.bit_offset = 0;
+            self.byte_offset += 1;
+        }
+
+        Ok(bit)
+    }
+
+    fn read_byte(&mut self) -> Result<u8, Error> {
+        let mut byte = 0;
+
+        for i in 0..8 {
+            if self.read_bit()? {
+                byte |= 1 << i;
+            }
+        }
+
+        Ok(byte)
+    }
+}
+
+pub fn decompress(data: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+    if out.is_empty() {
+        return Ok(0);
+    }
+
+    let mut bits = BitReader::new(data);
+
+    let compression_type = bits.read_byte()?;
+    let mut tree = HuffmanTree::new(get_weights(compression_type)?)?;
+
+    decompress_with_tree(&mut tree, compression_type == 0, &mut bits, out)
+}
+
+fn decompress_with_tree(
+    tree: &mut HuffmanTree,
+    adaptive: bool,
+    bits: &mut BitReader,
+    out: &mut [u8],
+) -> Result<usize, Error> {
+    let mut written = 0;
+
+    loop {
+        let mut value = tree.decode_value(bits)?;
+
+        if value == END_OF_STREAM {
+            break;
+        }
+
+        if value == NEW_VALUE {
+            value = u16::from(bits.read_byte()?);
+
+            tree.insert_value(value)?;
+
+            if !adaptive {
+                tree.increment_value_weight(value);
+            }
+        }
+
+        out[written] = value as u8;
+        written += 1;
+
+        if written == out.len() {
+            break;
+        }
+
+        // Compression type 0 updates the weights of every decompressed byte
+        if adaptive {
+            tree.increment_value_weight(value);
+        }
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{
+        decompress, decompress_with_tree, get_weights, BitReader, HuffmanTree, END_OF_STREAM,
+        NEW_VALUE, NUM_VALUES, WEIGHTS_TYPE_0,
+    };
+
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bit_offset: u8,
+    }
+
+    impl BitWriter {
+        fn write_bit(&mut self, bit: bool) {
+            if self.bit_offset == 0 {
+                self.bytes.push(0);
+            }
+
+            if bit {
+                *self.bytes.last_mut().unwrap() |= 1 << self.bit_offset;
+            }
+
+            self.bit_offset = (self.bit_offset + 1) % 8;
+        }
+
+        fn write_byte(&mut self, byte: u8) {
+            for i in 0..8 {
+                self.write_bit(byte & (1 << i) != 0);
+            }
+        }
+    }
+
+    // Walks from the leaf to the root and writes the path from the root to the leaf
+    fn write_value(tree: &HuffmanTree, value: u16, bits: &mut BitWriter) -> bool {
+        let Some(mut item) = tree.items_by_value[value as usize] else {
+            return false;
+        };
+
+        let mut path = Vec::new();
+
+        while let Some(parent) = tree.items[item].parent {
+            path.push(tree.items[parent].child_lo != Some(item));
+            item = parent;
+        }
+
+        for bit in path.into_iter().rev() {
+            bits.write_bit(bit);
+        }
+
+        true
+    }
+
+    // Mirrors the decompressor so that test sectors can be built by hand
+    fn compress(weights: &[u8; NUM_VALUES], compression_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut tree = HuffmanTree::new(weights).unwrap();
+        let mut bits = BitWriter::default();
+        let adaptive = compression_type == 0;
+
+        bits.write_byte(compression_type);
+
+        for &byte in data {
+            if !write_value(&tree, u16::from(byte), &mut bits) {
+                write_value(&tree, NEW_VALUE, &mut bits);
+                bits.write_byte(byte);
+
+                tree.insert_value(u16::from(byte)).unwrap();
+
+                if !adaptive {
+                    tree.increment_value_weight(u16::from(byte));
+                }
+            }
+
+            if adaptive {
+                tree.increment_value_weight(u16::from(byte));
+            }
+        }
+
+        write_value(&tree, END_OF_STREAM, &mut bits);
+
+        bits.bytes
+    }
+
+    pub(crate) fn compress_type_0(data: &[u8]) -> Vec<u8> {
+        compress(&WEIGHTS_TYPE_0, 0, data)
+    }
+
+    pub(crate) fn compress_with_type(compression_type: u8, data: &[u8]) -> Vec<u8> {
+        compress(
+            get_weights(compression_type).unwrap(),
+            compression_type,
+            data,
+        )
+    }
+
+    #[test]
+    fn every_compression_type_builds_a_tree() {
+        for compression_type in 0..=8 {
+            let weights = get_weights(compression_type).unwrap();
+            assert_eq!(weights[END_OF_STREAM as usize], 0);
+            assert_eq!(weights[NEW_VALUE as usize], 0);
+
+            let data = b"\x00\x01\x40\x80\x81\xFFdata with bytes which aren't in the table";
+            let compressed = compress_with_type(compression_type, data);
+
+            let mut out = vec![0; data.len()];
+            assert_eq!(decompress(&compressed, &mut out).unwrap(), data.len());
+            assert_eq!(&out, data);
+        }
+
+        assert!(get_weights(9).is_err());
+    }
+
+    #[test]
+    fn tree_is_sorted_by_weight() {
+        let tree = HuffmanTree::new(&WEIGHTS_TYPE_0).unwrap();
+
+        let mut item = tree.first();
+        let mut last_weight = u32::MAX;
+        let mut num_items = 0;
+
+        while item != super::LIST_HEAD {
+            assert!(tree.items[item].weight <= last_weight);
+            last_weight = tree.items[item].weight;
+            item = tree.items[item].next;
+            num_items += 1;
+        }
+
+        // 258 leaves and 257 branches
+        assert_eq!(num_items, 515);
+        assert_eq!(tree.items[tree.first()].weight, 20 + 254 + 2);
+    }
+
+    #[test]
+    fn decompress_type_0() {
+        let data = b"\x00\x00\x01Huffman coded sector with some repeated bytes: aaaaaaaaaaaa";
+        let compressed = compress(&WEIGHTS_TYPE_0, 0, data);
+
+        let mut out = vec![0; data.len()];
+        let written = decompress(&compressed, &mut out).unwrap();
+
+        assert_eq!(written, data.len());
+        assert_eq!(&out, data);
+    }
+
+    #[test]
+    fn decompress_stops_at_end_of_stream() {
+        let data = b"short";
+        let compressed = compress(&WEIGHTS_TYPE_0, 0, data);
+
+        let mut out = vec![0; 64];
+        let written = decompress(&compressed, &mut out).unwrap();
+
+        assert_eq!(&out[..written], data);
+    }
+
+    #[test]
+    fn decompress_inserts_new_values() {
+        // Only 'a' and 'b' are known up front, every other byte is sent after the escape value
+        let mut weights = [0; NUM_VALUES];
+        weights[b'a' as usize] = 4;
+        weights[b'b' as usize] = 2;
+
+        let data = b"abracadabra, abracadabra!";
+        let compressed = compress(&weights, 1, data);
+
+        let mut tree = HuffmanTree::new(&weights).unwrap();
+        let mut bits = BitReader::new(&compressed);
+        assert_eq!(bits.read_byte().unwrap(), 1);
+
+        let mut out = vec![0; data.len()];
+        let written = decompress_with_tree(&mut tree, false, &mut bits, &mut out).unwrap();
+
+        assert_eq!(&out[..written], data);
+    }
+
+    #[test]
+    fn decompress_zlib_then_huffman_sector() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::io::Write;
+
+        let data = b"Sector that was huffman coded first and deflated afterwards".repeat(4);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&compress(&WEIGHTS_TYPE_0, 0, &data))
+            .unwrap();
+
+        // Huffman (0x01) and zlib (0x02)
+        let mut sector = vec![0x03];
+        sector.extend(encoder.finish().unwrap());
+
+        let mut out = vec![0; data.len()];
+        let written = crate::compression::decompress(&mut sector, &mut out).unwrap();
+
+        assert_eq!(written, data.len());
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn truncated_stream_is_an_error() {
+        let compressed = compress(&WEIGHTS_TYPE_0, 0, b"truncated");
+
+        let mut out = vec![0; 64];
+        assert!(decompress(&compressed[..compressed.len() / 2], &mut out).is_err());
+    }
+}
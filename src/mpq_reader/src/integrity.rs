@@ -0,0 +1,195 @@
+use byteorder::{ByteOrder, LittleEndian};
+use md5::{Digest, Md5};
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+const ATTRIBUTES_VERSION: u32 = 100;
+
+pub(crate) const ATTRIBUTE_CRC32: u32 = 0x01;
+pub(crate) const ATTRIBUTE_FILETIME: u32 = 0x02;
+pub(crate) const ATTRIBUTE_MD5: u32 = 0x04;
+
+/// The check a file of an archive failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityFailure {
+    /// adler32 checksum of a sector doesn't match the stored one
+    SectorChecksum { sector: usize },
+    /// CRC32 of the file doesn't match the one in `(attributes)`
+    Crc32 { expected: u32, actual: u32 },
+    /// MD5 of the file doesn't match the one in `(attributes)`
+    Md5,
+    /// the archive ends before the file data
+    Truncated { sector: Option<usize> },
+    /// the file data can't be decoded
+    Corrupted {
+        sector: Option<usize>,
+        reason: String,
+    },
+}
+
+/// A file of an archive which is damaged, carried by the `io::Error` of a failed read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityError {
+    pub file: String,
+    pub failure: IntegrityFailure,
+}
+
+impl IntegrityError {
+    pub(crate) fn new(file: &str, failure: IntegrityFailure) -> IntegrityError {
+        IntegrityError {
+            file: String::from(file),
+            failure,
+        }
+    }
+
+    /// Returns the integrity error carried by an error returned when reading a file
+    pub fn from_io(error: &Error) -> Option<&IntegrityError> {
+        error.get_ref()?.downcast_ref::<IntegrityError>()
+    }
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let file = &self.file;
+
+        match &self.failure {
+            IntegrityFailure::SectorChecksum { sector } => {
+                write!(f, "{file}: checksum of sector {sector} does not match")
+            }
+            IntegrityFailure::Crc32 { expected, actual } => write!(
+                f,
+                "{file}: CRC32 {actual:#010x} does not match {expected:#010x} of (attributes)"
+            ),
+            IntegrityFailure::Md5 => write!(f, "{file}: MD5 does not match (attributes)"),
+            IntegrityFailure::Truncated {
+                sector: Some(sector),
+            } => {
+                write!(f, "{file}: archive is truncated in sector {sector}")
+            }
+            IntegrityFailure::Truncated { sector: None } => {
+                write!(f, "{file}: archive is truncated")
+            }
+            IntegrityFailure::Corrupted {
+                sector: Some(sector),
+                reason,
+            } => write!(f, "{file}: sector {sector} is corrupted, {reason}"),
+            IntegrityFailure::Corrupted {
+                sector: None,
+                reason,
+            } => write!(f, "{file}: file is corrupted, {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+impl From<IntegrityError> for Error {
+    fn from(error: IntegrityError) -> Self {
+        let kind = match error.failure {
+            IntegrityFailure::Truncated { .. } => ErrorKind::UnexpectedEof,
+            _ => ErrorKind::InvalidData,
+        };
+
+        Error::new(kind, error)
+    }
+}
+
+/// CRC32 and MD5 of the files by block index, as stored in the `(attributes)` file
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Attributes {
+    crc32: Vec<u32>,
+    md5: Vec<[u8; 16]>,
+}
+
+impl Attributes {
+    pub(crate) fn new(bytes: &[u8], block_count: usize) -> Result<Attributes, Error> {
+        if bytes.len() < 8 || LittleEndian::read_u32(bytes) != ATTRIBUTES_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Unsupported (attributes) version",
+            ));
+        }
+
+        let flags = LittleEndian::read_u32(&bytes[4..]);
+        let mut data = &bytes[8..];
+
+        // the arrays are cut short by some tools, missing entries are not verified
+        let mut take = |entry_size: usize| {
+            let size = (block_count * entry_size).min(data.len() - data.len() % entry_size);
+            let (head, tail) = data.split_at(size);
+            data = tail;
+            head
+        };
+
+        let mut attributes = Attributes::default();
+
+        if flags & ATTRIBUTE_CRC32 != 0 {
+            attributes.crc32 = take(4)
+                .chunks_exact(4)
+                .map(LittleEndian::read_u32)
+                .collect();
+        }
+
+        if flags & ATTRIBUTE_FILETIME != 0 {
+            take(8);
+        }
+
+        if flags & ATTRIBUTE_MD5 != 0 {
+            attributes.md5 = take(16)
+                .chunks_exact(16)
+                .map(|md5| md5.try_into().unwrap())
+                .collect();
+        }
+
+        Ok(attributes)
+    }
+
+    pub(crate) fn verify(
+        &self,
+        file: &str,
+        block_index: usize,
+        data: &[u8],
+    ) -> Result<(), IntegrityError> {
+        // zero means that no checksum was stored for the file
+        if let Some(&expected) = self.crc32.get(block_index).filter(|&&crc| crc != 0) {
+            let actual = crc32fast::hash(data);
+
+            if actual != expected {
+                return Err(IntegrityError::new(
+                    file,
+                    IntegrityFailure::Crc32 { expected, actual },
+                ));
+            }
+        }
+
+        if let Some(expected) = self.md5.get(block_index).filter(|md5| **md5 != [0; 16]) {
+            if md5(data) != *expected {
+                return Err(IntegrityError::new(file, IntegrityFailure::Md5));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    Md5::digest(data).into()
+}
+
+/// Builds an `(attributes)` file with the CRC32 and MD5 of the files by block index,
+/// `None` entries are left empty
+pub(crate) fn build_attributes(files: &[Option<&[u8]>]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(ATTRIBUTES_VERSION.to_le_bytes());
+    bytes.extend((ATTRIBUTE_CRC32 | ATTRIBUTE_MD5).to_le_bytes());
+
+    for data in files {
+        bytes.extend(data.map_or(0, crc32fast::hash).to_le_bytes());
+    }
+
+    for data in files {
+        bytes.extend(data.map_or([0; 16], md5));
+    }
+
+    bytes
+}
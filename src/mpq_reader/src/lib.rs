@@ -1,5 +1,20 @@
+mod adpcm;
 mod archive;
+mod builder;
+mod bzip2_encoder;
 mod compression;
 mod crypt;
+mod ext_tables;
+mod file_reader;
+mod huffman;
+mod integrity;
+mod listfile;
+mod pkware;
+mod wav;
 
-pub use crate::archive::{Archive, File};
+pub use crate::archive::{Archive, ArchiveInfo, BlockInfo, File};
+pub use crate::builder::{ArchiveBuilder, Compression, FileOptions};
+pub use crate::file_reader::FileReader;
+pub use crate::integrity::{IntegrityError, IntegrityFailure};
+pub use crate::listfile::{matches_pattern, FileList, UnnamedEntry};
+pub use crate::wav::Wav;
@@ -0,0 +1,126 @@
+/// A hash table entry which has a file but no name in any of the listfiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnnamedEntry {
+    /// index into the hash table, `None` for archives that only have a HET table
+    pub hash_index: Option<usize>,
+    /// index into the block table of the file
+    pub block_index: usize,
+}
+
+/// The files of an archive, as far as their names are known
+#[derive(Debug, Clone, Default)]
+pub struct FileList {
+    /// names of the files found in the archive, sorted and without duplicates
+    pub names: Vec<String>,
+    /// entries of files whose names are not in any listfile
+    pub unnamed: Vec<UnnamedEntry>,
+}
+
+impl FileList {
+    /// Names of the files matching a glob pattern, see [`matches_pattern`]
+    pub fn matching<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = &'a str> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .filter(move |name| matches_pattern(pattern, name))
+    }
+}
+
+/// Splits the contents of a listfile into file names.
+///
+/// Names are separated by new lines or semicolons, surrounding whitespace is ignored.
+pub fn parse_listfile(bytes: &[u8]) -> impl Iterator<Item = String> + '_ {
+    bytes
+        .split(|&ch| ch == b'\r' || ch == b'\n' || ch == b';')
+        .map(|name| String::from_utf8_lossy(name).trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+fn is_separator(ch: u8) -> bool {
+    ch == b'\\' || ch == b'/'
+}
+
+fn chars_match(pattern: u8, name: u8) -> bool {
+    pattern.eq_ignore_ascii_case(&name) || (is_separator(pattern) && is_separator(name))
+}
+
+/// Matches an archive path against a glob pattern like `data\global\monsters\*\cof\*.cof`.
+///
+/// Matching ignores case and treats `\` and `/` the same, like the archive lookups do.
+/// `?` matches a single character and `*` any number of characters within one path component,
+/// `**` also matches across path separators.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    matches(pattern.as_bytes(), name.as_bytes())
+}
+
+fn matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern {
+        [] => name.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+        [b'*', rest @ ..] => {
+            let component_end = name
+                .iter()
+                .position(|&ch| is_separator(ch))
+                .unwrap_or(name.len());
+
+            (0..=component_end).any(|i| matches(rest, &name[i..]))
+        }
+        [b'?', rest @ ..] => match name {
+            [ch, name_rest @ ..] if !is_separator(*ch) => matches(rest, name_rest),
+            _ => false,
+        },
+        [ch, rest @ ..] => match name {
+            [name_ch, name_rest @ ..] if chars_match(*ch, *name_ch) => matches(rest, name_rest),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{matches_pattern, parse_listfile};
+
+    #[test]
+    fn parse() {
+        let names: Vec<String> =
+            parse_listfile(b"data\\global\\excel\\levels.txt\r\n\r\n (listfile);Font.dc6\n")
+                .collect();
+
+        assert_eq!(
+            names,
+            ["data\\global\\excel\\levels.txt", "(listfile)", "Font.dc6"]
+        );
+    }
+
+    #[test]
+    fn glob() {
+        let pattern = "data\\global\\monsters\\*\\cof\\*.cof";
+
+        assert!(matches_pattern(
+            pattern,
+            "data\\global\\monsters\\FA\\COF\\FANUHTH.COF"
+        ));
+        assert!(matches_pattern(
+            pattern,
+            "data/global/monsters/zm/cof/zmneutr.cof"
+        ));
+        assert!(!matches_pattern(
+            pattern,
+            "data\\global\\monsters\\zm\\tr\\zmtrlitlhth.dcc"
+        ));
+        assert!(!matches_pattern(
+            pattern,
+            "data\\global\\monsters\\zm\\x\\cof\\a.cof"
+        ));
+
+        assert!(matches_pattern(
+            "data\\**.dc6",
+            "data\\local\\font\\latin\\font16.DC6"
+        ));
+        assert!(matches_pattern(
+            "data\\global\\excel\\level?.txt",
+            "data\\global\\excel\\Levels.txt"
+        ));
+        assert!(!matches_pattern("*.txt", "data\\global\\excel\\levels.txt"));
+    }
+}
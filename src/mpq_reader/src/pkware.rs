@@ -0,0 +1,205 @@
+use implode::symbol::{CodeTable, DEFAULT_CODE_TABLE};
+
+const MODE_BINARY: u8 = 0;
+// dictionary of 4096 bytes
+const DICT_BITS: u32 = 6;
+const DICT_SIZE: usize = 64 << DICT_BITS;
+
+const MIN_MATCH: usize = 2;
+const MAX_MATCH: usize = 518;
+// length value of the end of stream marker
+const END_OF_STREAM: usize = 517;
+// longest distance of a match with the minimum length
+const MAX_SHORT_DISTANCE: usize = 64 << 2;
+
+const HASH_SIZE: usize = 0x1000;
+const MAX_CHAIN: usize = 64;
+
+// LSB first bit writer, the bit order of the PKWARE Data Compression Library
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bit_buffer |= value << self.bit_count;
+        self.bit_count += count;
+
+        while self.bit_count >= 8 {
+            self.bytes.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.bytes.push(self.bit_buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+// The code of a symbol are the low bits of any index of the decoding table pointing to the symbol
+fn code_of(decode_table: &[u8; 256], symbol: usize, bits: u8) -> u32 {
+    let index = decode_table
+        .iter()
+        .position(|&entry| entry as usize == symbol)
+        .unwrap();
+
+    index as u32 & ((1 << bits) - 1)
+}
+
+struct Encoder {
+    table: &'static CodeTable,
+    writer: BitWriter,
+}
+
+impl Encoder {
+    fn literal(&mut self, byte: u8) {
+        self.writer.write(u32::from(byte) << 1, 9);
+    }
+
+    fn length(&mut self, length_value: usize) {
+        let table = self.table;
+        let symbol = (0..16)
+            .rev()
+            .find(|&symbol| usize::from(table.len_base[symbol]) <= length_value)
+            .unwrap();
+        let bits = table.len_bits[symbol];
+
+        self.writer.write(1, 1);
+        self.writer
+            .write(code_of(&table.len_codes, symbol, bits), u32::from(bits));
+        self.writer.write(
+            (length_value - usize::from(table.len_base[symbol])) as u32,
+            u32::from(table.extra_len_bits[symbol]),
+        );
+    }
+
+    fn pair(&mut self, length: usize, distance: usize) {
+        self.length(length - MIN_MATCH);
+
+        let extra_bits = if length == MIN_MATCH { 2 } else { DICT_BITS };
+        let distance = distance - 1;
+        let symbol = distance >> extra_bits;
+        let bits = self.table.dist_bits[symbol];
+
+        self.writer.write(
+            code_of(&self.table.dist_codes, symbol, bits),
+            u32::from(bits),
+        );
+        self.writer
+            .write((distance & ((1 << extra_bits) - 1)) as u32, extra_bits);
+    }
+}
+
+fn hash(data: &[u8]) -> usize {
+    ((usize::from(data[0]) << 8) ^ (usize::from(data[1]) << 4) ^ usize::from(data[2])) % HASH_SIZE
+}
+
+/// Compresses data in the binary mode of the PKWARE Data Compression Library
+/// with a dictionary of 4096 bytes, as read by [`crate::compression::explode`].
+pub fn implode(data: &[u8]) -> Vec<u8> {
+    let mut encoder = Encoder {
+        table: &DEFAULT_CODE_TABLE,
+        writer: BitWriter {
+            bytes: vec![MODE_BINARY, DICT_BITS as u8],
+            bit_buffer: 0,
+            bit_count: 0,
+        },
+    };
+
+    // hash chains of the positions starting with the same three bytes
+    let mut head = vec![usize::MAX; HASH_SIZE];
+    let mut previous = vec![usize::MAX; data.len()];
+
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let max_length = MAX_MATCH.min(data.len() - pos);
+        let mut best = (0, 0);
+
+        if pos + 3 <= data.len() {
+            let mut candidate = head[hash(&data[pos..])];
+            let mut chain = 0;
+
+            while candidate != usize::MAX && pos - candidate <= DICT_SIZE && chain < MAX_CHAIN {
+                let length = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+
+                if length > best.0 {
+                    best = (length, pos - candidate);
+                }
+
+                candidate = previous[candidate];
+                chain += 1;
+            }
+        }
+
+        // matches of two bytes can only reach back a short distance
+        if best.0 <= MIN_MATCH && max_length >= MIN_MATCH {
+            best = (0, 0);
+
+            let start = pos.saturating_sub(MAX_SHORT_DISTANCE);
+
+            if let Some(candidate) = (start..pos)
+                .rev()
+                .find(|&c| data[c..c + MIN_MATCH] == data[pos..pos + MIN_MATCH])
+            {
+                best = (MIN_MATCH, pos - candidate);
+            }
+        }
+
+        if best.0 >= MIN_MATCH {
+            encoder.pair(best.0, best.1);
+        } else {
+            encoder.literal(data[pos]);
+            best.0 = 1;
+        }
+
+        for p in (pos..pos + best.0).filter(|p| p + 3 <= data.len()) {
+            let h = hash(&data[p..]);
+            previous[p] = head[h];
+            head[h] = p;
+        }
+
+        pos += best.0;
+    }
+
+    encoder.length(END_OF_STREAM);
+
+    encoder.writer.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::implode;
+    use crate::compression::explode;
+
+    #[test]
+    fn round_trip() {
+        let mut data = b"data\\global\\excel\\levels.txt\r\ndata\\global\\excel\\".repeat(30);
+        data.extend((0..=255).collect::<Vec<u8>>());
+        data.resize(4096, 0);
+
+        let mut compressed = implode(&data);
+        assert!(compressed.len() < data.len() / 4);
+
+        let mut out = vec![0; data.len()];
+        let read = explode(&mut compressed, &mut out).unwrap();
+
+        assert_eq!(read, data.len());
+        assert_eq!(out, data);
+
+        // truncated data is an error instead of an endless loop
+        let half = compressed.len() / 2;
+        assert!(explode(&mut compressed[..half], &mut out).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use byteorder::{ByteOrder, LittleEndian};
+use std::io::{Error, ErrorKind};
+
+const ID_RIFF: &[u8] = b"RIFF";
+const ID_WAVE: &[u8] = b"WAVE";
+const ID_FMT: &[u8] = b"fmt ";
+const ID_DATA: &[u8] = b"data";
+
+const FORMAT_PCM: u16 = 1;
+const RIFF_HEADER_SIZE: usize = 12;
+const CHUNK_HEADER_SIZE: usize = 8;
+const FMT_CHUNK_SIZE: usize = 16;
+
+/// A PCM sound as stored in the wav files of the sound archives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wav {
+    pub num_channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    /// interleaved little endian samples
+    pub data: Vec<u8>,
+}
+
+impl Wav {
+    /// Parses a wav file extracted from an archive.
+    ///
+    /// The chunk sizes written by the game tools are not always correct,
+    /// so a data chunk that is larger than the file is cut at the end of the file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < RIFF_HEADER_SIZE
+            || !bytes.starts_with(ID_RIFF)
+            || &bytes[8..RIFF_HEADER_SIZE] != ID_WAVE
+        {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a wav file"));
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut pos = RIFF_HEADER_SIZE;
+
+        while pos + CHUNK_HEADER_SIZE <= bytes.len() {
+            let chunk_id = &bytes[pos..pos + 4];
+            let chunk_size = LittleEndian::read_u32(&bytes[pos + 4..]) as usize;
+            let chunk_start = pos + CHUNK_HEADER_SIZE;
+            let chunk_end = chunk_start.saturating_add(chunk_size).min(bytes.len());
+            let chunk = &bytes[chunk_start..chunk_end];
+
+            if chunk_id == ID_FMT {
+                if chunk.len() < FMT_CHUNK_SIZE {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "Wav format is truncated",
+                    ));
+                }
+
+                format = Some((
+                    LittleEndian::read_u16(chunk),
+                    LittleEndian::read_u16(&chunk[2..]),
+                    LittleEndian::read_u32(&chunk[4..]),
+                    LittleEndian::read_u16(&chunk[14..]),
+                ));
+            } else if chunk_id == ID_DATA {
+                data = Some(chunk.to_vec());
+            }
+
+            // Chunks are padded to an even size
+            pos = chunk_end + (chunk_size & 1);
+        }
+
+        let Some((format_tag, num_channels, sample_rate, bits_per_sample)) = format else {
+            return Err(Error::new(ErrorKind::InvalidData, "Wav format not found"));
+        };
+
+        if format_tag != FORMAT_PCM {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Wav format {format_tag} not supported"),
+            ));
+        }
+
+        let Some(data) = data else {
+            return Err(Error::new(ErrorKind::InvalidData, "Wav data not found"));
+        };
+
+        Ok(Self {
+            num_channels,
+            sample_rate,
+            bits_per_sample,
+            data,
+        })
+    }
+
+    fn block_align(&self) -> u16 {
+        self.num_channels * self.bits_per_sample.div_ceil(8)
+    }
+
+    pub fn num_samples(&self) -> usize {
+        match self.block_align() {
+            0 => 0,
+            block_align => self.data.len() / block_align as usize,
+        }
+    }
+
+    /// Writes a canonical PCM wav file with correct chunk sizes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let data_size = self.data.len() + (self.data.len() & 1);
+        let riff_size = 4 + CHUNK_HEADER_SIZE + FMT_CHUNK_SIZE + CHUNK_HEADER_SIZE + data_size;
+        let block_align = self.block_align();
+
+        let mut bytes = vec![0; RIFF_HEADER_SIZE + riff_size - 4];
+
+        bytes[0..4].copy_from_slice(ID_RIFF);
+        LittleEndian::write_u32(&mut bytes[4..], riff_size as u32);
+        bytes[8..12].copy_from_slice(ID_WAVE);
+
+        bytes[12..16].copy_from_slice(ID_FMT);
+        LittleEndian::write_u32(&mut bytes[16..], FMT_CHUNK_SIZE as u32);
+        LittleEndian::write_u16(&mut bytes[20..], FORMAT_PCM);
+        LittleEndian::write_u16(&mut bytes[22..], self.num_channels);
+        LittleEndian::write_u32(&mut bytes[24..], self.sample_rate);
+        LittleEndian::write_u32(&mut bytes[28..], self.sample_rate * u32::from(block_align));
+        LittleEndian::write_u16(&mut bytes[32..], block_align);
+        LittleEndian::write_u16(&mut bytes[34..], self.bits_per_sample);
+
+        bytes[36..40].copy_from_slice(ID_DATA);
+        LittleEndian::write_u32(&mut bytes[40..], self.data.len() as u32);
+        bytes[44..44 + self.data.len()].copy_from_slice(&self.data);
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Wav;
+
+    #[test]
+    fn round_trip() {
+        let wav = Wav {
+            num_channels: 2,
+            sample_rate: 22050,
+            bits_per_sample: 16,
+            data: vec![1, 0, 2, 0, 3, 0, 4, 0],
+        };
+
+        let bytes = wav.to_bytes();
+
+        assert_eq!(bytes.len(), 44 + 8);
+        assert_eq!(Wav::from_bytes(&bytes).unwrap(), wav);
+        assert_eq!(wav.num_samples(), 2);
+    }
+
+    #[test]
+    fn data_chunk_larger_than_file() {
+        let mut bytes = Wav {
+            num_channels: 1,
+            sample_rate: 22050,
+            bits_per_sample: 16,
+            data: vec![1, 0, 2, 0],
+        }
+        .to_bytes();
+
+        // Data chunk claims to hold 0x1000 bytes
+        bytes[40..44].copy_from_slice(&[0x00, 0x10, 0x00, 0x00]);
+
+        assert_eq!(Wav::from_bytes(&bytes).unwrap().data, [1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn not_pcm() {
+        let mut bytes = Wav {
+            num_channels: 1,
+            sample_rate: 22050,
+            bits_per_sample: 4,
+            data: vec![0; 4],
+        }
+        .to_bytes();
+
+        // IMA ADPCM format tag
+        bytes[20] = 0x11;
+
+        assert!(Wav::from_bytes(&bytes).is_err());
+    }
+}